@@ -5,9 +5,14 @@ use parking_lot::RwLock;
 use plain_bitassets::{
     miner::{self, Miner},
     node::{self, Node},
+    parent_chain::{
+        self, ParentChainClientTrait, SwapAsset, SwapId, client::TxId,
+        config::ParentChainType, proof,
+    },
+    state,
     types::{
         self, Address, AmountOverflowError, BitcoinOutputContent, Body,
-        FilledOutput, OutPoint, Output, Transaction,
+        FilledOutput, OutPoint, Output, Transaction, Txid,
         proto::mainchain::{
             self,
             generated::{validator_service_server, wallet_service_server},
@@ -32,12 +37,41 @@ pub enum Error {
     CusfMainchain(#[from] plain_bitassets::types::proto::Error),
     #[error("io error")]
     Io(#[from] std::io::Error),
+    #[error(
+        "L1 transaction is in block {including_block_hash}, but the swap monitor last saw it in block {monitored_block_hash}"
+    )]
+    L1BlockMismatch {
+        including_block_hash: bitcoin::BlockHash,
+        monitored_block_hash: String,
+    },
     #[error("miner error: {0}")]
     Miner(#[from] miner::Error),
+    #[error(
+        "Monero swaps are not supported, since Monero payments cannot be proven to the sidechain"
+    )]
+    MoneroUnsupported,
     #[error("node error")]
     Node(#[source] Box<node::Error>),
+    #[error("No claimable fill found for this swap")]
+    NoClaimableFill,
     #[error("No CUSF mainchain wallet client")]
     NoCusfMainchainWalletClient,
+    #[error("Swap has no L1 checkpoint to prove against")]
+    NoL1Checkpoint,
+    #[error("No locked outputs found for this swap")]
+    NoLockedOutputs,
+    #[error("No parent chain client configured")]
+    NoParentChainClient,
+    #[error("Open swap offer has not been filled")]
+    NotFilled,
+    #[error(transparent)]
+    ParentChain(#[from] parent_chain::client::Error),
+    #[error(transparent)]
+    Proof(#[from] proof::Error),
+    #[error("state error")]
+    State(#[source] Box<state::Error>),
+    #[error("Swap not found")]
+    SwapNotFound(SwapId),
     #[error("Unable to verify existence of CUSF mainchain service(s) at {url}")]
     VerifyMainchainServices {
         url: Box<url::Url>,
@@ -53,6 +87,12 @@ impl From<node::Error> for Error {
     }
 }
 
+impl From<state::Error> for Error {
+    fn from(err: state::Error) -> Self {
+        Self::State(Box::new(err))
+    }
+}
+
 fn update_wallet(node: &Node, wallet: &Wallet) -> Result<(), Error> {
     tracing::trace!("starting wallet update");
     let addresses = wallet.get_addresses()?;
@@ -327,6 +367,214 @@ impl App {
         Ok(())
     }
 
+    /// Sign a tx with the keys that consensus requires for its inputs, and
+    /// send it. Used for swap claims, which spend escrow outputs that the
+    /// wallet does not own.
+    pub fn sign_and_send_with_authorizers(
+        &self,
+        tx: Transaction,
+    ) -> Result<(), Error> {
+        let authorizers = self.node.get_authorizers(&tx)?;
+        let authorized_transaction =
+            self.wallet.authorize_for(tx, &authorizers)?;
+        self.node.submit_transaction(authorized_transaction)?;
+        let () = self.update()?;
+        Ok(())
+    }
+
+    /// Client for a configured parent chain
+    fn get_parent_chain_client(
+        &self,
+        parent_chain: &ParentChainType,
+    ) -> Result<&dyn ParentChainClientTrait, Error> {
+        self.node
+            .parent_chain_client()
+            .ok_or(Error::NoParentChainClient)?
+            .get_client(parent_chain)
+            .ok_or_else(|| {
+                parent_chain::client::Error::ChainNotConfigured(*parent_chain)
+                    .into()
+            })
+    }
+
+    /// Create an L2 → L1 swap offer, and send its `SwapCreate` tx.
+    /// The swap commits to a recent L1 header that claims are proven
+    /// against, so swaps can only be created on chains with L1 proofs.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_swap(
+        &self,
+        parent_chain: ParentChainType,
+        l1_recipient_address: String,
+        l1_amount: bitcoin::Amount,
+        l2_recipient: Option<Address>,
+        l2_asset: SwapAsset,
+        l2_amount: u64,
+        required_confirmations: Option<u32>,
+        fill_bond: bitcoin::Amount,
+        fill_timeout: u32,
+        expiry_blocks: Option<u32>,
+        partial_fills: bool,
+    ) -> Result<SwapId, Error> {
+        let current_height =
+            self.node.try_get_tip_height()?.map_or(0, |h| h + 1);
+        if parent_chain == ParentChainType::Xmr {
+            return Err(Error::MoneroUnsupported);
+        }
+        if !proof::is_supported(&parent_chain) {
+            return Err(proof::Error::UnsupportedChain(parent_chain).into());
+        }
+        let client = self.get_parent_chain_client(&parent_chain)?;
+        // Payments to an address that does not decode could never be
+        // matched to the swap
+        let _: bitcoin::ScriptBuf =
+            client.script_pubkey(&l1_recipient_address)?;
+        let l1_checkpoint = Some(client.get_checkpoint().await?);
+        let (tx, swap_id) = self.wallet.create_swap_create_tx(
+            parent_chain,
+            l1_recipient_address,
+            l1_amount,
+            l2_recipient,
+            l2_asset,
+            l2_amount,
+            required_confirmations,
+            l1_checkpoint,
+            fill_bond,
+            fill_timeout,
+            expiry_blocks.map(|expiry_blocks| current_height + expiry_blocks),
+            partial_fills,
+            current_height,
+        )?;
+        let () = self.sign_and_send(tx)?;
+        Ok(swap_id)
+    }
+
+    /// Claim a swap with a proof of its L1 payment, and send the
+    /// `SwapClaim` tx. For partially fillable offers, `l1_txid` selects the
+    /// fill to claim, and defaults to the latest claimable fill of this
+    /// wallet.
+    pub async fn claim_swap(
+        &self,
+        swap_id: &SwapId,
+        l1_txid: Option<TxId>,
+    ) -> Result<Txid, Error> {
+        let rotxn = self.node.env().read_txn().map_err(node::Error::from)?;
+        let state = self.node.state();
+        let swap = state
+            .get_swap(&rotxn, swap_id)?
+            .ok_or_else(|| Error::SwapNotFound(swap_id.clone()))?;
+        // Each fill of a partially fillable offer is claimed separately
+        let claiming_fill = if swap.partial_fills {
+            let fill = match l1_txid {
+                Some(l1_txid) => swap.claimable_fill(&l1_txid),
+                None => {
+                    let addresses = self.wallet.get_addresses()?;
+                    swap.fills.iter().rev().find(|fill| {
+                        addresses.contains(&fill.l2_recipient)
+                            && swap.claimable_fill(&fill.l1_txid).is_some()
+                    })
+                }
+            };
+            Some(fill.ok_or(Error::NoClaimableFill)?)
+        } else {
+            swap.current_fill()
+        };
+        // Without a fill, the L1 transaction is only known to this node
+        let local_swap = self
+            .node
+            .get_local_swap(swap_id)?
+            .ok_or_else(|| Error::SwapNotFound(swap_id.clone()))?;
+        let expected_l1_txid = claiming_fill
+            .map_or(local_swap.expected_l1_txid(), |fill| &fill.l1_txid);
+
+        // Build the L1 proof that every node will verify for this claim
+        let checkpoint = swap.l1_checkpoint.ok_or(Error::NoL1Checkpoint)?;
+        let proof_data = self
+            .get_parent_chain_client(&swap.parent_chain)?
+            .get_tx_proof(
+                expected_l1_txid,
+                &checkpoint,
+                swap.required_confirmations,
+            )
+            .await?;
+        // If the header relay already buries the including block deeply
+        // enough, the remaining headers are not needed
+        let including_block_hash = proof_data.including_header()?.block_hash();
+        // With several backends, one that has not seen a reorg yet could
+        // still prove a payment in an orphaned block, so the block must be
+        // the one that the swap monitor last saw
+        if let Some(swap_manager) = self.node.swap_manager() {
+            let swap_manager = swap_manager.lock().await;
+            if let Some(monitored) = swap_manager.get_swap(swap_id)
+                && monitored.expected_l1_txid() == expected_l1_txid
+                && let Some(l1_block_hash) = &monitored.l1_block_hash
+                && *l1_block_hash != including_block_hash.to_string()
+            {
+                return Err(Error::L1BlockMismatch {
+                    including_block_hash,
+                    monitored_block_hash: l1_block_hash.clone(),
+                });
+            }
+        }
+        let relay_confirmed = state
+            .header_relay()
+            .is_confirmed_after_checkpoint(
+                &rotxn,
+                &swap.parent_chain,
+                &checkpoint,
+                &including_block_hash,
+                swap.required_confirmations,
+            )
+            .map_err(state::Error::from)?;
+        let proof_data = if relay_confirmed {
+            proof_data.including_block_only()?
+        } else {
+            proof_data
+        };
+
+        // Locked outputs are taken from the node, since they are not owned
+        // by the claimer's wallet.
+        // Bonds of other fillers stay locked to the swap, and cannot be
+        // spent by the claiming filler. Neither can its own bond, once its
+        // fill has timed out and the bond is forfeited to the offerer.
+        let other_bonds: Vec<_> = swap
+            .fills
+            .iter()
+            .filter(|fill| claiming_fill != Some(*fill))
+            .filter_map(|fill| fill.bond)
+            .collect();
+        let height = state.try_get_height(&rotxn)?.map_or(0, |h| h + 1);
+        let mut locked_outputs = HashMap::new();
+        for (outpoint, output) in self.node.get_all_utxos()? {
+            if other_bonds.contains(&outpoint)
+                || swap.is_bond_forfeited(&outpoint, height)
+            {
+                continue;
+            }
+            if state.is_output_locked_to_swap(&rotxn, &outpoint)?.as_ref()
+                == Some(swap_id)
+            {
+                locked_outputs.insert(outpoint, output);
+            }
+        }
+        drop(rotxn);
+        if locked_outputs.is_empty() {
+            return Err(Error::NoLockedOutputs);
+        }
+        let recipient = swap
+            .claimant(&proof_data.l1_txid())
+            .ok_or(Error::NotFilled)?;
+        let tx = self.wallet.create_swap_claim_tx(
+            &swap,
+            locked_outputs,
+            recipient,
+            proof_data,
+        )?;
+        // The escrow is signed for with the recipient's key
+        let txid = tx.txid();
+        let () = self.sign_and_send_with_authorizers(tx)?;
+        Ok(txid)
+    }
+
     pub fn get_new_main_address(
         &self,
     ) -> Result<bitcoin::Address<bitcoin::address::NetworkChecked>, Error> {
@@ -158,9 +158,17 @@ pub(super) struct Cli {
     pub zmq_addr: SocketAddr,
     /// Parent chain node URL (format: CHAIN:URL, e.g., BTC:http://localhost:8332)
     /// Can be specified multiple times for different chains
+    /// Nodes for the same chain are queried together, see --parent-chain-quorum
     /// Supported chains: BTC, BCH, LTC, XMR, ETH, TRON
+    /// BTC-family chains can also use Esplora (esplora+https://...) and
+    /// Electrum (electrum://host:port) servers
     #[arg(long, value_name = "CHAIN:URL")]
     pub parent_chain_node: Vec<String>,
+    /// How many nodes for a parent chain must agree on a transaction
+    /// (format: CHAIN:POLICY, e.g., BTC:2)
+    /// POLICY is `all` (the default) or the number of nodes that must agree
+    #[arg(long, value_name = "CHAIN:POLICY")]
+    pub parent_chain_quorum: Vec<String>,
     /// Path to parent chain configuration file (JSON format)
     /// If specified, overrides individual --parent-chain-node arguments
     #[arg(long)]
@@ -220,7 +228,19 @@ impl Cli {
                     .map_err(|_| anyhow::anyhow!("Unknown chain: {}", parts[0]))?;
                 let url = Url::parse(parts[1])
                     .map_err(|e| anyhow::anyhow!("Invalid URL {}: {}", parts[1], e))?;
-                config.set_chain(chain, url, None, None);
+                config.add_node(chain, url, None);
+            }
+            for chain_quorum in &self.parent_chain_quorum {
+                let (chain, quorum) =
+                    chain_quorum.split_once(':').ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Invalid parent chain quorum format: {}. Expected CHAIN:POLICY",
+                            chain_quorum
+                        )
+                    })?;
+                let chain = ParentChainType::from_str(chain)
+                    .map_err(|_| anyhow::anyhow!("Unknown chain: {}", chain))?;
+                config.set_quorum(chain, quorum.parse()?)?;
             }
             config
         };
@@ -15,6 +15,7 @@ mod messaging;
 mod miner;
 mod parent_chain;
 mod seed;
+mod swaps;
 mod util;
 
 use activity::Activity;
@@ -26,6 +27,7 @@ use messaging::Messaging;
 use miner::Miner;
 use parent_chain::ParentChain;
 use seed::SetSeed;
+use swaps::Swaps;
 use util::{BITCOIN_LOGO_FA, BITCOIN_ORANGE, UiExt, show_btc_amount};
 
 /// Bottom panel, if initialized
@@ -165,6 +167,7 @@ pub struct EguiApp {
     miner: Miner,
     parent_chain: ParentChain,
     set_seed: SetSeed,
+    swaps: Swaps,
     tab: Tab,
 }
 
@@ -177,6 +180,8 @@ enum Tab {
     Coins,
     #[strum(to_string = "BitAssets")]
     BitAssets,
+    #[strum(to_string = "Swaps")]
+    Swaps,
     #[strum(to_string = "Messaging")]
     Messaging,
     #[strum(to_string = "Activity")]
@@ -230,6 +235,7 @@ impl EguiApp {
             miner: Miner::default(),
             parent_chain,
             set_seed: SetSeed::default(),
+            swaps: Swaps::default(),
             tab: Tab::default(),
         }
     }
@@ -271,6 +277,9 @@ impl eframe::App for EguiApp {
                 Tab::BitAssets => {
                     self.bitassets.show(self.app.as_ref(), ui);
                 }
+                Tab::Swaps => {
+                    self.swaps.show(self.app.as_ref(), ui);
+                }
                 Tab::Messaging => {
                     self.messaging.show(self.app.as_ref(), ui);
                 }
@@ -0,0 +1,267 @@
+use eframe::egui::{self, Button};
+use plain_bitassets::{
+    parent_chain::{Swap, SwapAsset, SwapId, SwapState, TxId},
+    types::{FilledOutput, GetBitcoinValue as _, OutPoint},
+};
+
+use crate::{
+    app::App,
+    gui::util::{UiExt, show_btc_amount},
+};
+
+#[derive(Debug, Default)]
+pub(super) struct AllSwaps {
+    selected: Option<SwapId>,
+    l1_txid: String,
+}
+
+fn show_l1_txid(l1_txid: &TxId) -> String {
+    match l1_txid {
+        TxId::Hash32(hash) => hex::encode(hash),
+        TxId::Hash(hash) => hex::encode(hash),
+    }
+}
+
+fn show_state(state: &SwapState) -> String {
+    match state {
+        SwapState::Pending => "Pending".to_owned(),
+        SwapState::WaitingConfirmations {
+            current_confirmations,
+            required_confirmations,
+        } => format!(
+            "{current_confirmations}/{required_confirmations} confirmations"
+        ),
+        SwapState::ReadyToClaim => "Ready to claim".to_owned(),
+        SwapState::Completed => "Completed".to_owned(),
+        SwapState::Cancelled => "Cancelled".to_owned(),
+    }
+}
+
+fn show_l2_amount(swap: &Swap) -> String {
+    if swap.l2_asset == SwapAsset::BITCOIN {
+        show_btc_amount(swap.l2_amount)
+    } else {
+        format!("{} units of {:?}", swap.l2_amount.to_sat(), swap.l2_asset)
+    }
+}
+
+/// Swaps from state, with this node's local data applied, newest first
+fn get_swaps(app: &App) -> anyhow::Result<Vec<Swap>> {
+    let rotxn = app.node.env().read_txn()?;
+    let mut swaps = app.node.state().load_all_swaps(&rotxn)?;
+    drop(rotxn);
+    for swap in &mut swaps {
+        if let Some(watch) = app.node.get_swap_watch(&swap.id)? {
+            watch.apply(swap);
+        }
+    }
+    swaps.sort_by_key(|swap| std::cmp::Reverse(swap.created_at_height));
+    Ok(swaps)
+}
+
+/// Outputs that are locked to a swap
+fn get_locked_outputs(
+    app: &App,
+    swap_id: &SwapId,
+) -> anyhow::Result<Vec<(OutPoint, FilledOutput)>> {
+    let utxos = app.node.get_all_utxos()?;
+    let rotxn = app.node.env().read_txn()?;
+    let state = app.node.state();
+    let mut locked_outputs = Vec::new();
+    for (outpoint, output) in utxos {
+        if state.is_output_locked_to_swap(&rotxn, &outpoint)?.as_ref()
+            == Some(swap_id)
+        {
+            locked_outputs.push((outpoint, output));
+        }
+    }
+    Ok(locked_outputs)
+}
+
+impl AllSwaps {
+    fn show_swap(&mut self, app: &App, swap: &Swap, ui: &mut egui::Ui) {
+        egui::Grid::new("swap_details")
+            .striped(true)
+            .show(ui, |ui| {
+                let mut show_line = |descriptor: &str, value: String| {
+                    ui.monospace(descriptor);
+                    ui.monospace_selectable_singleline(true, value);
+                    ui.end_row();
+                };
+                show_line("Swap ID", hex::encode(swap.id.0));
+                show_line("Parent chain", swap.parent_chain.to_string());
+                show_line("State", show_state(&swap.state));
+                show_line(
+                    "L1 address",
+                    swap.l1_recipient_address.clone().unwrap_or_default(),
+                );
+                show_line(
+                    "L1 amount",
+                    swap.l1_amount
+                        .map(|l1_amount| l1_amount.to_sat().to_string())
+                        .unwrap_or_default(),
+                );
+                show_line("L1 txid", show_l1_txid(swap.expected_l1_txid()));
+                show_line(
+                    "Required confirmations",
+                    swap.required_confirmations.to_string(),
+                );
+                show_line("L2 amount", show_l2_amount(swap));
+                show_line(
+                    "L2 amount remaining",
+                    show_btc_amount(swap.l2_amount_remaining),
+                );
+                show_line(
+                    "L2 recipient",
+                    swap.l2_recipient
+                        .map(|l2_recipient| l2_recipient.to_string())
+                        .unwrap_or_else(|| "open offer".to_owned()),
+                );
+                show_line(
+                    "Created at height",
+                    swap.created_at_height.to_string(),
+                );
+                show_line(
+                    "Expires at height",
+                    swap.expires_at_height
+                        .map(|height| height.to_string())
+                        .unwrap_or_default(),
+                );
+                show_line("Fills", swap.fills.len().to_string());
+            });
+        ui.separator();
+        ui.heading("Locked Outputs");
+        match get_locked_outputs(app, &swap.id) {
+            Ok(locked_outputs) => {
+                egui::Grid::new("locked_outputs").striped(true).show(
+                    ui,
+                    |ui| {
+                        ui.monospace("outpoint");
+                        ui.monospace("address");
+                        ui.monospace("value");
+                        ui.end_row();
+                        for (outpoint, output) in locked_outputs {
+                            ui.monospace_selectable_singleline(
+                                true,
+                                outpoint.to_string(),
+                            );
+                            ui.monospace_selectable_singleline(
+                                true,
+                                output.address.to_string(),
+                            );
+                            ui.monospace(show_btc_amount(
+                                output.get_bitcoin_value(),
+                            ));
+                            ui.end_row();
+                        }
+                    },
+                );
+            }
+            Err(err) => {
+                ui.monospace_selectable_multiline(format!("{err:#}"));
+            }
+        }
+        if matches!(swap.state, SwapState::Completed | SwapState::Cancelled) {
+            return;
+        }
+        ui.separator();
+        ui.heading("Actions");
+        ui.horizontal(|ui| {
+            let l1_txid_edit = egui::TextEdit::singleline(&mut self.l1_txid)
+                .hint_text("L1 txid")
+                .desired_width(300.);
+            ui.add(l1_txid_edit);
+        });
+        let l1_txid = hex::decode(&self.l1_txid).ok().map(|l1_txid_bytes| {
+            match <[u8; 32]>::try_from(l1_txid_bytes) {
+                Ok(hash) => TxId::Hash32(hash),
+                Err(hash_bytes) => TxId::Hash(hash_bytes),
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    l1_txid.is_some() && !self.l1_txid.is_empty(),
+                    Button::new("attach L1 txid"),
+                )
+                .clicked()
+            {
+                // The L1 txid is only tracked by this node
+                match app.runtime.block_on(app.node.set_swap_l1_txid(
+                    &swap.id,
+                    l1_txid.expect("should not happen"),
+                    None,
+                )) {
+                    Ok(()) => {
+                        self.l1_txid.clear();
+                    }
+                    Err(err) => {
+                        let err = anyhow::Error::from(err);
+                        tracing::error!("{err:#}")
+                    }
+                }
+            }
+            if ui.button("claim").clicked() {
+                match app.runtime.block_on(app.claim_swap(&swap.id, None)) {
+                    Ok(txid) => {
+                        tracing::info!(%txid, "Claimed swap");
+                    }
+                    Err(err) => {
+                        let err = anyhow::Error::from(err);
+                        tracing::error!("{err:#}")
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        let Some(app) = app else {
+            return;
+        };
+        let swaps = match get_swaps(app) {
+            Ok(swaps) => swaps,
+            Err(err) => {
+                ui.monospace_selectable_multiline(format!("{err:#}"));
+                return;
+            }
+        };
+        egui::SidePanel::left("swap_picker")
+            .resizable(true)
+            .show_inside(ui, |ui| {
+                ui.heading("Swaps");
+                ui.separator();
+                egui::Grid::new("swaps").striped(true).show(ui, |ui| {
+                    ui.monospace("swap ID");
+                    ui.monospace("chain");
+                    ui.monospace("L2 amount");
+                    ui.monospace("state");
+                    ui.end_row();
+                    for swap in &swaps {
+                        let swap_id = &hex::encode(swap.id.0)[0..8];
+                        if ui
+                            .selectable_label(
+                                self.selected.as_ref() == Some(&swap.id),
+                                swap_id,
+                            )
+                            .clicked()
+                        {
+                            self.selected = Some(swap.id.clone());
+                        }
+                        ui.monospace(swap.parent_chain.to_string());
+                        ui.monospace(show_l2_amount(swap));
+                        ui.monospace(show_state(&swap.state));
+                        ui.end_row();
+                    }
+                });
+            });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            let Some(swap) = self.selected.as_ref().and_then(|swap_id| {
+                swaps.iter().find(|swap| swap.id == *swap_id)
+            }) else {
+                return;
+            };
+            self.show_swap(app, swap, ui);
+        });
+    }
+}
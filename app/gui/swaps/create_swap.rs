@@ -0,0 +1,156 @@
+use eframe::egui::{self, Button};
+use plain_bitassets::parent_chain::{
+    ParentChainType, SwapAsset, SwapId, swap::DEFAULT_FILL_TIMEOUT,
+};
+use strum::IntoEnumIterator;
+
+use crate::{app::App, gui::util::UiExt};
+
+#[derive(Debug)]
+pub(super) struct CreateSwap {
+    parent_chain: ParentChainType,
+    l1_recipient_address: String,
+    l1_amount: String,
+    l2_amount: String,
+    required_confirmations: String,
+    expiry_blocks: String,
+    /// ID of the last swap that was created
+    created: Option<SwapId>,
+}
+
+impl Default for CreateSwap {
+    fn default() -> Self {
+        Self {
+            parent_chain: ParentChainType::Btc,
+            l1_recipient_address: String::new(),
+            l1_amount: String::new(),
+            l2_amount: String::new(),
+            required_confirmations: String::new(),
+            expiry_blocks: String::new(),
+            created: None,
+        }
+    }
+}
+
+/// Parse an optional integer, that is `None` if left empty
+fn parse_optional_u32(s: &str) -> Option<Option<u32>> {
+    if s.is_empty() {
+        Some(None)
+    } else {
+        s.parse().ok().map(Some)
+    }
+}
+
+impl CreateSwap {
+    pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        ui.heading("Create Swap Offer");
+        ui.label(
+            "Offer L2 coins for a payment on a parent chain. Any filler can \
+             take the offer.",
+        );
+        egui::ComboBox::from_label("Parent chain")
+            .selected_text(self.parent_chain.to_string())
+            .show_ui(ui, |ui| {
+                for parent_chain in ParentChainType::iter() {
+                    ui.selectable_value(
+                        &mut self.parent_chain,
+                        parent_chain,
+                        parent_chain.to_string(),
+                    );
+                }
+            });
+        ui.horizontal(|ui| {
+            let l1_recipient_address_edit =
+                egui::TextEdit::singleline(&mut self.l1_recipient_address)
+                    .hint_text("L1 address that receives the payment")
+                    .desired_width(300.);
+            ui.add(l1_recipient_address_edit);
+        });
+        ui.horizontal(|ui| {
+            let l1_amount_edit =
+                egui::TextEdit::singleline(&mut self.l1_amount)
+                    .hint_text("L1 amount")
+                    .desired_width(150.);
+            ui.add(l1_amount_edit);
+            ui.label("base units of the L1 asset");
+        });
+        ui.horizontal(|ui| {
+            let l2_amount_edit =
+                egui::TextEdit::singleline(&mut self.l2_amount)
+                    .hint_text("L2 amount")
+                    .desired_width(150.);
+            ui.add(l2_amount_edit);
+            ui.label("BTC");
+        });
+        ui.horizontal(|ui| {
+            let required_confirmations_edit =
+                egui::TextEdit::singleline(&mut self.required_confirmations)
+                    .hint_text("L1 confirmations (optional)")
+                    .desired_width(150.);
+            ui.add(required_confirmations_edit);
+        });
+        ui.horizontal(|ui| {
+            let expiry_blocks_edit =
+                egui::TextEdit::singleline(&mut self.expiry_blocks)
+                    .hint_text("expiry in blocks (optional)")
+                    .desired_width(150.);
+            ui.add(expiry_blocks_edit);
+        });
+        let l1_amount: Option<u64> = self.l1_amount.parse().ok();
+        let l2_amount = bitcoin::Amount::from_str_in(
+            &self.l2_amount,
+            bitcoin::Denomination::Bitcoin,
+        );
+        let required_confirmations =
+            parse_optional_u32(&self.required_confirmations);
+        let expiry_blocks = parse_optional_u32(&self.expiry_blocks);
+        if ui
+            .add_enabled(
+                app.is_some()
+                    && !self.l1_recipient_address.is_empty()
+                    && l1_amount.is_some()
+                    && l2_amount.is_ok()
+                    && required_confirmations.is_some()
+                    && expiry_blocks.is_some(),
+                Button::new("create swap"),
+            )
+            .clicked()
+        {
+            let app = app.unwrap();
+            let res = app.runtime.block_on(app.create_swap(
+                self.parent_chain,
+                self.l1_recipient_address.clone(),
+                bitcoin::Amount::from_sat(
+                    l1_amount.expect("should not happen"),
+                ),
+                None,
+                SwapAsset::BITCOIN,
+                l2_amount.expect("should not happen").to_sat(),
+                required_confirmations.expect("should not happen"),
+                bitcoin::Amount::ZERO,
+                DEFAULT_FILL_TIMEOUT,
+                expiry_blocks.expect("should not happen"),
+                false,
+            ));
+            match res {
+                Ok(swap_id) => {
+                    *self = Self {
+                        parent_chain: self.parent_chain,
+                        created: Some(swap_id),
+                        ..Self::default()
+                    };
+                }
+                Err(err) => {
+                    let err = anyhow::Error::from(err);
+                    tracing::error!("{err:#}")
+                }
+            }
+        }
+        if let Some(swap_id) = &self.created {
+            ui.horizontal(|ui| {
+                ui.monospace("Created swap: ");
+                ui.monospace_selectable_singleline(true, hex::encode(swap_id.0))
+            });
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use eframe::egui;
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::app::App;
+
+mod all_swaps;
+mod create_swap;
+
+use all_swaps::AllSwaps;
+use create_swap::CreateSwap;
+
+#[derive(Default, EnumIter, Eq, PartialEq, strum::Display)]
+enum Tab {
+    #[default]
+    #[strum(to_string = "All Swaps")]
+    AllSwaps,
+    #[strum(to_string = "Create Swap")]
+    CreateSwap,
+}
+
+#[derive(Default)]
+pub struct Swaps {
+    all_swaps: AllSwaps,
+    create_swap: CreateSwap,
+    tab: Tab,
+}
+
+impl Swaps {
+    pub fn show(&mut self, app: Option<&App>, ui: &mut egui::Ui) {
+        egui::TopBottomPanel::top("swaps_tabs").show(ui.ctx(), |ui| {
+            ui.horizontal(|ui| {
+                Tab::iter().for_each(|tab_variant| {
+                    let tab_name = tab_variant.to_string();
+                    ui.selectable_value(&mut self.tab, tab_variant, tab_name);
+                })
+            });
+        });
+        egui::CentralPanel::default().show(ui.ctx(), |ui| match self.tab {
+            Tab::AllSwaps => {
+                let () = self.all_swaps.show(app, ui);
+            }
+            Tab::CreateSwap => {
+                let () = self.create_swap.show(app, ui);
+            }
+        });
+    }
+}
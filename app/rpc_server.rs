@@ -1,4 +1,4 @@
-use std::{borrow::Cow, cmp::Ordering, net::SocketAddr};
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, net::SocketAddr};
 
 use bitcoin::Amount;
 use fraction::Fraction;
@@ -11,8 +11,14 @@ use jsonrpsee::{
 use plain_bitassets::{
     authorization::{self, Dst, Signature},
     net::Peer,
-    parent_chain::{swap::{Swap, SwapState}, config::ParentChainType, SwapId, client::TxId},
-    state::{self, AmmPair, AmmPoolState, BitAssetSeqId, DutchAuctionState},
+    parent_chain::{
+        swap::{DEFAULT_FILL_TIMEOUT, Swap, SwapStatus},
+        config::ParentChainType, SwapAsset, SwapId, client::TxId,
+    },
+    state::{
+        self, AmmPair, AmmPoolState, BitAssetSeqId, DutchAuctionState,
+        RelayHeader, RelayTip,
+    },
     types::{
         Address, AssetId, Authorization, BitAssetData, BitAssetId, Block,
         BlockHash, DutchAuctionId, DutchAuctionParams, EncryptionPubKey,
@@ -745,36 +751,33 @@ impl RpcServer for RpcServerImpl {
         parent_chain: ParentChainType,
         l1_recipient_address: String,
         l1_amount_sats: u64,
-        l2_recipient: Address,
+        l2_recipient: Option<Address>,
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        fill_bond_sats: Option<u64>,
+        fill_timeout: Option<u32>,
+        expiry_blocks: Option<u32>,
+        l2_asset: Option<SwapAsset>,
+        partial_fills: Option<bool>,
     ) -> RpcResult<String> {
-        // Get current height
-        let current_height = self
-            .app
-            .node
-            .try_get_tip_height()
-            .map_err(custom_err)?
-            .map_or(0, |h| h + 1);
-
-        // Create swap transaction
-        let (tx, swap_id) = self
+        let swap_id = self
             .app
-            .wallet
-            .create_swap_create_tx(
+            .create_swap(
                 parent_chain,
                 l1_recipient_address,
                 Amount::from_sat(l1_amount_sats),
                 l2_recipient,
-                Amount::from_sat(l2_amount_sats),
+                l2_asset.unwrap_or(SwapAsset::BITCOIN),
+                l2_amount_sats,
                 required_confirmations,
-                current_height,
+                Amount::from_sat(fill_bond_sats.unwrap_or(0)),
+                fill_timeout.unwrap_or(DEFAULT_FILL_TIMEOUT),
+                expiry_blocks,
+                partial_fills.unwrap_or(false),
             )
+            .await
             .map_err(custom_err)?;
 
-        let txid = tx.txid();
-        self.app.sign_and_send(tx).map_err(custom_err)?;
-
         // Return swap ID as hex string
         Ok(hex::encode(swap_id.0))
     }
@@ -783,6 +786,7 @@ impl RpcServer for RpcServerImpl {
         &self,
         swap_id: String,
         l1_txid: String,
+        l1_payment_proof: Option<String>,
     ) -> RpcResult<()> {
         // Parse swap ID
         let swap_id_bytes = hex::decode(&swap_id)
@@ -813,39 +817,89 @@ impl RpcServer for RpcServerImpl {
             TxId::Hash(hash_bytes)
         };
 
-        // Get swap from state
-        let env = self.app.node.env();
-        let rotxn = env.read_txn().map_err(custom_err)?;
-        let state = self.app.node.state();
-        let mut swap = state
-            .get_swap(&rotxn, &swap_id)
-            .map_err(custom_err)?
-            .ok_or_else(|| custom_err_msg("Swap not found"))?;
+        // The L1 txid is only tracked by this node, and is not written to
+        // state
+        self.app
+            .node
+            .set_swap_l1_txid(&swap_id, l1_txid, l1_payment_proof)
+            .await
+            .map_err(custom_err)
+    }
 
-        // Update L1 transaction ID
-        swap.set_l1_txid(l1_txid)
-            .map_err(|e| custom_err_msg(format!("Failed to update swap: {}", e)))?;
+    async fn set_swap_note(
+        &self,
+        swap_id: String,
+        note: Option<String>,
+    ) -> RpcResult<()> {
+        let swap_id_bytes = hex::decode(&swap_id)
+            .map_err(|e| custom_err_msg(format!("Invalid swap ID: {}", e)))?;
+        let swap_id_array: [u8; 32] = swap_id_bytes
+            .try_into()
+            .map_err(|_| custom_err_msg("Swap ID must be 32 bytes"))?;
+        let swap_id = SwapId(swap_id_array);
+        self.app
+            .node
+            .set_swap_note(&swap_id, note)
+            .map_err(custom_err)
+    }
 
-        // Save updated swap
-        let mut rwtxn = env.write_txn().map_err(|e| custom_err_msg(format!("Database error: {}", e)))?;
-        state.save_swap(&mut rwtxn, &swap).map_err(custom_err)?;
-        rwtxn.commit().map_err(|e| custom_err_msg(format!("Database commit error: {}", e)))?;
+    async fn fill_swap(
+        &self,
+        swap_id: String,
+        l1_txid: String,
+        l2_recipient: Option<Address>,
+        fee_sats: u64,
+        l1_amount_sats: Option<u64>,
+    ) -> RpcResult<Txid> {
+        // Parse swap ID
+        let swap_id_bytes = hex::decode(&swap_id)
+            .map_err(|e| custom_err_msg(format!("Invalid swap ID: {}", e)))?;
+        let swap_id_array: [u8; 32] = swap_id_bytes
+            .try_into()
+            .map_err(|_| custom_err_msg("Swap ID must be 32 bytes"))?;
+        let swap_id = SwapId(swap_id_array);
 
-        // Update swap manager
-        if let Some(swap_manager) = self.app.node.swap_manager() {
-            let mut sm = swap_manager.lock().await;
-            if let Some(swap_mut) = sm.get_swap_mut(&swap_id) {
-                *swap_mut = swap;
-            }
-        }
+        // Parse L1 transaction ID
+        let l1_txid_bytes = hex::decode(&l1_txid)
+            .map_err(|e| custom_err_msg(format!("Invalid L1 txid: {}", e)))?;
+        let l1_txid = match <[u8; 32]>::try_from(l1_txid_bytes) {
+            Ok(hash) => TxId::Hash32(hash),
+            Err(hash_bytes) => TxId::Hash(hash_bytes),
+        };
 
-        Ok(())
+        // The bond is set by the offer
+        let swap = {
+            let env = self.app.node.env();
+            let rotxn = env.read_txn().map_err(custom_err)?;
+            self.app
+                .node
+                .state()
+                .get_swap(&rotxn, &swap_id)
+                .map_err(custom_err)?
+                .ok_or_else(|| custom_err_msg("Swap not found"))?
+        };
+        let l2_recipient = match l2_recipient {
+            Some(l2_recipient) => l2_recipient,
+            None => self.app.wallet.get_new_address().map_err(custom_err)?,
+        };
+        let tx = self
+            .app
+            .wallet
+            .create_swap_fill_tx(
+                swap_id,
+                l2_recipient,
+                l1_txid,
+                l1_amount_sats.map(Amount::from_sat),
+                swap.fill_bond,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
     }
 
-    async fn get_swap_status(
-        &self,
-        swap_id: String,
-    ) -> RpcResult<Swap> {
+    async fn get_swap_status(&self, swap_id: String) -> RpcResult<SwapStatus> {
         // Parse swap ID
         let swap_id_bytes = hex::decode(&swap_id)
             .map_err(|e| custom_err_msg(format!("Invalid swap ID: {}", e)))?;
@@ -865,12 +919,28 @@ impl RpcServer for RpcServerImpl {
             .map_err(custom_err)?
             .ok_or_else(|| custom_err_msg("Swap not found"))?;
 
-        Ok(swap)
+        // Report disagreements from the last time that the L1 transaction
+        // was looked up
+        let l1_disagreement =
+            self.app.node.parent_chain_client().and_then(|client| {
+                client.l1_disagreement(
+                    &swap.parent_chain,
+                    swap.expected_l1_txid(),
+                )
+            });
+        let watch =
+            self.app.node.get_swap_watch(&swap_id).map_err(custom_err)?;
+        Ok(SwapStatus {
+            swap,
+            l1_disagreement,
+            watch,
+        })
     }
 
     async fn claim_swap(
         &self,
         swap_id: String,
+        l1_txid: Option<String>,
     ) -> RpcResult<Txid> {
         // Parse swap ID
         let swap_id_bytes = hex::decode(&swap_id)
@@ -882,88 +952,254 @@ impl RpcServer for RpcServerImpl {
         swap_id_array.copy_from_slice(&swap_id_bytes);
         let swap_id = SwapId(swap_id_array);
 
-        // Get swap from state to verify it's ready and get recipient
+        let l1_txid = match l1_txid {
+            Some(l1_txid) => {
+                let l1_txid_bytes = hex::decode(&l1_txid).map_err(|e| {
+                    custom_err_msg(format!("Invalid L1 txid: {}", e))
+                })?;
+                match <[u8; 32]>::try_from(l1_txid_bytes) {
+                    Ok(hash) => Some(TxId::Hash32(hash)),
+                    Err(hash_bytes) => Some(TxId::Hash(hash_bytes)),
+                }
+            }
+            None => None,
+        };
+        self.app
+            .claim_swap(&swap_id, l1_txid)
+            .await
+            .map_err(custom_err)
+    }
+
+    async fn refund_swap(
+        &self,
+        swap_id: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        // Parse swap ID
+        let swap_id_bytes = hex::decode(&swap_id)
+            .map_err(|e| custom_err_msg(format!("Invalid swap ID: {}", e)))?;
+        let swap_id_array: [u8; 32] = swap_id_bytes
+            .try_into()
+            .map_err(|_| custom_err_msg("Swap ID must be 32 bytes"))?;
+        let swap_id = SwapId(swap_id_array);
+
         let env = self.app.node.env();
-        let rotxn = env.read_txn().map_err(|e| custom_err_msg(format!("Database error: {}", e)))?;
+        let rotxn = env.read_txn().map_err(custom_err)?;
         let state = self.app.node.state();
-        let swap = state
-            .get_swap(&rotxn, &swap_id)
+        let Some(swap) =
+            state.get_swap(&rotxn, &swap_id).map_err(custom_err)?
+        else {
+            return Err(custom_err_msg("Swap not found"));
+        };
+        let height = state
+            .try_get_height(&rotxn)
             .map_err(custom_err)?
-            .ok_or_else(|| custom_err_msg("Swap not found"))?;
+            .map_or(0, |height| height + 1);
 
-        // Verify swap is ready to claim
-        if !matches!(swap.state, SwapState::ReadyToClaim) {
-            return Err(custom_err_msg(format!(
-                "Swap not ready to claim. Current state: {:?}",
-                swap.state
-            )));
-        }
-
-        // Find locked outputs for this swap
-        // We need to get UTXOs from the node, not wallet, since locked outputs
-        // might not be in the wallet yet (they're in the state)
-        let mut locked_outputs = Vec::new();
-        let all_utxos = self.app.node.get_all_utxos().map_err(custom_err)?;
-        
-        for (outpoint, _output) in &all_utxos {
-            if let Some(locked_swap_id) = state.is_output_locked_to_swap(&rotxn, outpoint).map_err(custom_err)? {
-                if locked_swap_id == swap_id {
-                    locked_outputs.push(*outpoint);
-                }
+        // Refund the locked outputs that this wallet can sign for: the
+        // escrow and forfeited bonds for the offerer, or a bond for a filler
+        let addresses = self.app.wallet.get_addresses().map_err(custom_err)?;
+        let utxos = self.app.node.get_all_utxos().map_err(custom_err)?;
+        let mut locked_outputs = HashMap::new();
+        for (outpoint, output) in utxos {
+            if state
+                .is_output_locked_to_swap(&rotxn, &outpoint)
+                .map_err(custom_err)?
+                .as_ref()
+                != Some(&swap_id)
+            {
+                continue;
+            }
+            let owner = if swap.is_bond_forfeited(&outpoint, height) {
+                swap.l2_offerer
+            } else {
+                Some(output.address)
+            };
+            if owner.is_some_and(|owner| addresses.contains(&owner)) {
+                locked_outputs.insert(outpoint, output);
             }
         }
-
         if locked_outputs.is_empty() {
             return Err(custom_err_msg("No locked outputs found for this swap"));
         }
 
-        // Create claim transaction
-        // Note: The wallet method will try to get outputs from wallet UTXOs
-        // If they're not there, we need to ensure they're available
-        // For now, we'll create the transaction directly using state UTXOs
-        let mut inputs = Vec::new();
-        let mut total_value = Amount::ZERO;
-        
-        for outpoint in &locked_outputs {
-            if let Some(output) = all_utxos.get(outpoint) {
-                total_value = total_value
-                    .checked_add(output.get_bitcoin_value())
-                    .ok_or(AmountOverflowError)
-                    .map_err(|e| custom_err_msg(format!("Amount overflow: {}", e)))?;
-                inputs.push(*outpoint);
-            }
-        }
+        let tx = self
+            .app
+            .wallet
+            .create_swap_refund_tx(
+                swap_id,
+                locked_outputs,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        // Forfeited bonds are signed for with the offerer's key
+        let txid = tx.txid();
+        self.app
+            .sign_and_send_with_authorizers(tx)
+            .map_err(custom_err)?;
+        Ok(txid)
+    }
 
-        if inputs.is_empty() {
-            return Err(custom_err_msg("No valid locked outputs found"));
-        }
+    async fn list_swaps(&self) -> RpcResult<Vec<Swap>> {
+        let env = self.app.node.env();
+        let rotxn = env.read_txn().map_err(|e| custom_err_msg(format!("Database error: {}", e)))?;
+        let state = self.app.node.state();
+        let swaps = state.load_all_swaps(&rotxn).map_err(custom_err)?;
+        Ok(swaps)
+    }
 
-        // Create transaction manually since wallet might not have these UTXOs yet
-        use plain_bitassets::types::{Output, OutputContent, BitcoinOutputContent, Transaction, TxData};
-        let outputs = vec![Output {
-            address: swap.l2_recipient,
-            content: OutputContent::Bitcoin(BitcoinOutputContent(total_value)),
-            memo: Vec::new(),
-        }];
+    async fn create_htlc(
+        &self,
+        recipient: Address,
+        value_sats: u64,
+        hash_lock: String,
+        timeout_height: u32,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let hash_lock: [u8; 32] = hex::decode(&hash_lock)
+            .map_err(|e| custom_err_msg(format!("Invalid hash lock: {}", e)))?
+            .try_into()
+            .map_err(|_| custom_err_msg("Hash lock must be 32 bytes"))?;
+        let tx = self
+            .app
+            .wallet
+            .create_htlc_tx(
+                recipient,
+                Amount::from_sat(value_sats),
+                hash_lock,
+                timeout_height,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        let () = self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
 
-        let mut tx = Transaction::new(inputs, outputs);
-        tx.data = Some(TxData::SwapClaim {
-            swap_id: swap_id.0,
-            proof_data: None,
-        });
+    async fn redeem_htlc(
+        &self,
+        outpoint: OutPoint,
+        preimage: String,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let preimage: [u8; 32] = hex::decode(&preimage)
+            .map_err(|e| custom_err_msg(format!("Invalid preimage: {}", e)))?
+            .try_into()
+            .map_err(|_| custom_err_msg("Preimage must be 32 bytes"))?;
+        // The HTLC output is taken from the node, since it need not be
+        // owned by this wallet
+        let htlc = self
+            .app
+            .node
+            .get_all_utxos()
+            .map_err(custom_err)?
+            .remove(&outpoint)
+            .and_then(|output| output.htlc().cloned())
+            .ok_or_else(|| custom_err_msg("HTLC output not found"))?;
+        if !htlc.is_preimage(&preimage) {
+            return Err(custom_err_msg(
+                "Preimage does not match the hash lock",
+            ));
+        }
+        let tx = self
+            .app
+            .wallet
+            .create_htlc_redeem_tx(
+                outpoint,
+                &htlc,
+                preimage,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        // The HTLC is signed for with the recipient's key
+        let txid = tx.txid();
+        self.app
+            .sign_and_send_with_authorizers(tx)
+            .map_err(custom_err)?;
+        Ok(txid)
+    }
 
+    async fn refund_htlc(
+        &self,
+        outpoint: OutPoint,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let htlc = self
+            .app
+            .node
+            .get_all_utxos()
+            .map_err(custom_err)?
+            .remove(&outpoint)
+            .and_then(|output| output.htlc().cloned())
+            .ok_or_else(|| custom_err_msg("HTLC output not found"))?;
+        let tx = self
+            .app
+            .wallet
+            .create_htlc_refund_tx(outpoint, &htlc, Amount::from_sat(fee_sats))
+            .map_err(custom_err)?;
         let txid = tx.txid();
         self.app.sign_and_send(tx).map_err(custom_err)?;
+        Ok(txid)
+    }
 
+    async fn relay_parent_chain_headers(
+        &self,
+        parent_chain: ParentChainType,
+        start_height: u32,
+        headers: Vec<String>,
+        fee_sats: u64,
+    ) -> RpcResult<Txid> {
+        let headers = headers
+            .iter()
+            .map(|header| {
+                let header_bytes = hex::decode(header).map_err(|e| {
+                    custom_err_msg(format!("Invalid header hex: {}", e))
+                })?;
+                bitcoin::consensus::deserialize(&header_bytes).map_err(|e| {
+                    custom_err_msg(format!("Invalid header: {}", e))
+                })
+            })
+            .collect::<Result<Vec<bitcoin::block::Header>, _>>()?;
+        let tx = self
+            .app
+            .wallet
+            .create_parent_chain_headers_tx(
+                parent_chain,
+                start_height,
+                headers,
+                Amount::from_sat(fee_sats),
+            )
+            .map_err(custom_err)?;
+        let txid = tx.txid();
+        self.app.sign_and_send(tx).map_err(custom_err)?;
         Ok(txid)
     }
 
-    async fn list_swaps(&self) -> RpcResult<Vec<Swap>> {
+    async fn get_relay_tip(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Option<RelayTip>> {
         let env = self.app.node.env();
-        let rotxn = env.read_txn().map_err(|e| custom_err_msg(format!("Database error: {}", e)))?;
+        let rotxn = env.read_txn().map_err(custom_err)?;
         let state = self.app.node.state();
-        let swaps = state.load_all_swaps(&rotxn).map_err(custom_err)?;
-        Ok(swaps)
+        state
+            .header_relay()
+            .try_get_tip(&rotxn, &parent_chain)
+            .map_err(custom_err)
+    }
+
+    async fn get_relay_header(
+        &self,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> RpcResult<Option<RelayHeader>> {
+        let env = self.app.node.env();
+        let rotxn = env.read_txn().map_err(custom_err)?;
+        let state = self.app.node.state();
+        state
+            .header_relay()
+            .try_get_header(&rotxn, &parent_chain, &block_hash)
+            .map_err(custom_err)
     }
 }
 
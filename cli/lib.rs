@@ -8,9 +8,10 @@ use http::HeaderMap;
 use jsonrpsee::{core::client::ClientT, http_client::HttpClientBuilder};
 use plain_bitassets::{
     authorization::{Dst, Signature},
+    parent_chain::SwapAsset,
     types::{
         Address, AssetId, BitAssetData, BitAssetId, BlockHash, DutchAuctionId,
-        DutchAuctionParams, EncryptionPubKey, THIS_SIDECHAIN, Txid,
+        DutchAuctionParams, EncryptionPubKey, OutPoint, THIS_SIDECHAIN, Txid,
         VerifyingKey,
     },
 };
@@ -266,12 +267,53 @@ pub enum Command {
         l1_recipient_address: String,
         #[arg(long)]
         l1_amount_sats: u64,
+        /// If not set, the swap is an open offer that any filler can take
         #[arg(long)]
-        l2_recipient: Address,
+        l2_recipient: Option<Address>,
         #[arg(long)]
         l2_amount_sats: u64,
         #[arg(long)]
         required_confirmations: Option<u32>,
+        /// Bond that a filler of an open offer must lock
+        #[arg(long)]
+        fill_bond_sats: Option<u64>,
+        /// Number of sidechain blocks for which a fill reserves an open offer
+        #[arg(long)]
+        fill_timeout: Option<u32>,
+        /// Number of sidechain blocks after which the swap expires, and the
+        /// locked coins can be refunded
+        #[arg(long)]
+        expiry_blocks: Option<u32>,
+        /// Offer an asset instead of L2 coins. `l2_amount_sats` is then in
+        /// base units of the asset.
+        #[arg(long, conflicts_with = "l2_lp_token")]
+        l2_asset: Option<AssetId>,
+        /// Offer LP tokens of the AMM pool for an asset pair instead of L2
+        /// coins. `l2_amount_sats` is then the amount of LP tokens.
+        #[arg(long, num_args = 2, value_names = ["ASSET0", "ASSET1"])]
+        l2_lp_token: Option<Vec<AssetId>>,
+        /// Let several fillers of an open offer each pay part of the L1
+        /// amount, for a pro-rata part of the L2 amount
+        #[arg(long)]
+        partial_fills: bool,
+    },
+    /// Fill an open swap offer
+    FillSwap {
+        #[arg(long)]
+        swap_id: String,
+        /// L1 transaction that pays the offerer
+        #[arg(long)]
+        l1_txid: String,
+        /// L2 address that will claim the swap. Defaults to a new wallet
+        /// address.
+        #[arg(long)]
+        l2_recipient: Option<Address>,
+        #[arg(long)]
+        fee_sats: u64,
+        /// Part of the L1 amount paid by the L1 transaction. Required for
+        /// partially fillable offers.
+        #[arg(long)]
+        l1_amount_sats: Option<u64>,
     },
     /// Update swap with L1 transaction ID (when L1 payment is sent)
     UpdateSwap {
@@ -279,6 +321,19 @@ pub enum Command {
         swap_id: String,
         #[arg(long)]
         l1_txid: String,
+        /// Reserved for Monero swaps: tx key (from `get_tx_key`) or tx proof
+        /// (from `get_tx_proof`) of the L1 transaction. Monero swaps are not
+        /// supported yet, so this is rejected.
+        #[arg(long)]
+        l1_payment_proof: Option<String>,
+    },
+    /// Set or clear this node's note on a swap
+    SetSwapNote {
+        #[arg(long)]
+        swap_id: String,
+        /// Omit to clear the note
+        #[arg(long)]
+        note: Option<String>,
     },
     /// Get swap status
     SwapStatus {
@@ -289,9 +344,82 @@ pub enum Command {
     ClaimSwap {
         #[arg(long)]
         swap_id: String,
+        /// L1 transaction of the fill to claim, for partially fillable
+        /// offers. Defaults to the latest claimable fill of this wallet.
+        #[arg(long)]
+        l1_txid: Option<String>,
+    },
+    /// Refund the coins locked to an expired swap, or forfeited bonds
+    RefundSwap {
+        #[arg(long)]
+        swap_id: String,
+        #[arg(long)]
+        fee_sats: u64,
     },
     /// List all swaps
     ListSwaps,
+    /// Lock coins in a hash time-locked output
+    CreateHtlc {
+        /// Address that can redeem the output with the preimage
+        #[arg(long)]
+        recipient: Address,
+        #[arg(long)]
+        value_sats: u64,
+        /// Hex encoded SHA-256 hash of the preimage
+        #[arg(long)]
+        hash_lock: String,
+        /// Sidechain height from which the output can only be refunded
+        #[arg(long)]
+        timeout_height: u32,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Redeem a hash time-locked output with the preimage of its hash lock
+    RedeemHtlc {
+        #[arg(long)]
+        txid: Txid,
+        #[arg(long)]
+        vout: u32,
+        /// Hex encoded preimage
+        #[arg(long)]
+        preimage: String,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Refund a timed out hash time-locked output
+    RefundHtlc {
+        #[arg(long)]
+        txid: Txid,
+        #[arg(long)]
+        vout: u32,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Relay parent chain headers to the sidechain
+    RelayParentChainHeaders {
+        #[arg(long)]
+        parent_chain: plain_bitassets::parent_chain::config::ParentChainType,
+        /// Parent chain height of the first header
+        #[arg(long)]
+        start_height: u32,
+        /// Hex encoded block headers, in order
+        #[arg(long, num_args = 1..)]
+        headers: Vec<String>,
+        #[arg(long)]
+        fee_sats: u64,
+    },
+    /// Get the tip of the relayed chain with the most work
+    GetRelayTip {
+        #[arg(long)]
+        parent_chain: plain_bitassets::parent_chain::config::ParentChainType,
+    },
+    /// Get a relayed header, including its height and chain work
+    GetRelayHeader {
+        #[arg(long)]
+        parent_chain: plain_bitassets::parent_chain::config::ParentChainType,
+        #[arg(long)]
+        block_hash: bitcoin::BlockHash,
+    },
 }
 
 const DEFAULT_RPC_HOST: Host = Host::Ipv4(Ipv4Addr::LOCALHOST);
@@ -642,7 +770,19 @@ where
             l2_recipient,
             l2_amount_sats,
             required_confirmations,
+            fill_bond_sats,
+            fill_timeout,
+            expiry_blocks,
+            l2_asset,
+            l2_lp_token,
+            partial_fills,
         } => {
+            let l2_asset = match (l2_asset, l2_lp_token.as_deref()) {
+                (_, Some(&[asset0, asset1])) => {
+                    Some(SwapAsset::AmmLpToken { asset0, asset1 })
+                }
+                (l2_asset, _) => l2_asset.map(SwapAsset::from),
+            };
             let swap_id = rpc_client
                 .create_swap(
                     parent_chain,
@@ -651,26 +791,129 @@ where
                     l2_recipient,
                     l2_amount_sats,
                     required_confirmations,
+                    fill_bond_sats,
+                    fill_timeout,
+                    expiry_blocks,
+                    l2_asset,
+                    Some(partial_fills),
                 )
                 .await?;
             format!("Swap created! Swap ID: {}\nYour coins are locked. Share this swap ID with the filler.", swap_id)
         }
-        Command::UpdateSwap { swap_id, l1_txid } => {
-            rpc_client.update_swap_l1_txid(swap_id, l1_txid).await?;
+        Command::FillSwap {
+            swap_id,
+            l1_txid,
+            l2_recipient,
+            fee_sats,
+            l1_amount_sats,
+        } => {
+            let txid = rpc_client
+                .fill_swap(
+                    swap_id,
+                    l1_txid,
+                    l2_recipient,
+                    fee_sats,
+                    l1_amount_sats,
+                )
+                .await?;
+            format!("{txid}")
+        }
+        Command::UpdateSwap {
+            swap_id,
+            l1_txid,
+            l1_payment_proof,
+        } => {
+            rpc_client
+                .update_swap_l1_txid(swap_id, l1_txid, l1_payment_proof)
+                .await?;
             "Swap updated! Waiting for confirmations...".to_string()
         }
+        Command::SetSwapNote { swap_id, note } => {
+            let () = rpc_client.set_swap_note(swap_id, note).await?;
+            String::default()
+        }
         Command::SwapStatus { swap_id } => {
             let swap = rpc_client.get_swap_status(swap_id).await?;
             serde_json::to_string_pretty(&swap)?
         }
-        Command::ClaimSwap { swap_id } => {
-            let txid = rpc_client.claim_swap(swap_id).await?;
+        Command::ClaimSwap { swap_id, l1_txid } => {
+            let txid = rpc_client.claim_swap(swap_id, l1_txid).await?;
             format!("Swap claimed! Transaction ID: {}\nYour L2 coins will be available after the next block.", txid)
         }
+        Command::RefundSwap { swap_id, fee_sats } => {
+            let txid = rpc_client.refund_swap(swap_id, fee_sats).await?;
+            format!("{txid}")
+        }
         Command::ListSwaps => {
             let swaps = rpc_client.list_swaps().await?;
             serde_json::to_string_pretty(&swaps)?
         }
+        Command::CreateHtlc {
+            recipient,
+            value_sats,
+            hash_lock,
+            timeout_height,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .create_htlc(
+                    recipient,
+                    value_sats,
+                    hash_lock,
+                    timeout_height,
+                    fee_sats,
+                )
+                .await?;
+            format!("{txid}")
+        }
+        Command::RedeemHtlc {
+            txid,
+            vout,
+            preimage,
+            fee_sats,
+        } => {
+            let outpoint = OutPoint::Regular { txid, vout };
+            let txid =
+                rpc_client.redeem_htlc(outpoint, preimage, fee_sats).await?;
+            format!("{txid}")
+        }
+        Command::RefundHtlc {
+            txid,
+            vout,
+            fee_sats,
+        } => {
+            let outpoint = OutPoint::Regular { txid, vout };
+            let txid = rpc_client.refund_htlc(outpoint, fee_sats).await?;
+            format!("{txid}")
+        }
+        Command::RelayParentChainHeaders {
+            parent_chain,
+            start_height,
+            headers,
+            fee_sats,
+        } => {
+            let txid = rpc_client
+                .relay_parent_chain_headers(
+                    parent_chain,
+                    start_height,
+                    headers,
+                    fee_sats,
+                )
+                .await?;
+            format!("{txid}")
+        }
+        Command::GetRelayTip { parent_chain } => {
+            let tip = rpc_client.get_relay_tip(parent_chain).await?;
+            serde_json::to_string_pretty(&tip)?
+        }
+        Command::GetRelayHeader {
+            parent_chain,
+            block_hash,
+        } => {
+            let header =
+                rpc_client.get_relay_header(parent_chain, block_hash).await?;
+            serde_json::to_string_pretty(&header)?
+        }
     })
 }
 
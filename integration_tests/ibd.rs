@@ -44,6 +44,7 @@ async fn setup(
         Init {
             bitassets_app: bin_paths.bitassets.clone(),
             data_dir_suffix: Some("sender".to_owned()),
+            parent_chain_nodes: Vec::new(),
         },
         &enforcer_post_setup,
         res_tx.clone(),
@@ -54,6 +55,7 @@ async fn setup(
         Init {
             bitassets_app: bin_paths.bitassets.clone(),
             data_dir_suffix: Some("syncer".to_owned()),
+            parent_chain_nodes: Vec::new(),
         },
         &enforcer_post_setup,
         res_tx,
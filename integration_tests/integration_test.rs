@@ -7,6 +7,7 @@ use futures::{FutureExt, future::BoxFuture};
 use crate::{
     ibd::ibd_trial,
     setup::{Init, PostSetup},
+    swap::swap_trial,
     unknown_withdrawal::unknown_withdrawal_trial,
     util::BinPaths,
     vote::vote_trial,
@@ -21,6 +22,7 @@ fn deposit_withdraw_roundtrip(
             Init {
                 bitassets_app: bin_paths.bitassets,
                 data_dir_suffix: None,
+                parent_chain_nodes: Vec::new(),
             },
         ).await
     }.boxed())
@@ -33,6 +35,7 @@ pub fn tests(
         deposit_withdraw_roundtrip(bin_paths.clone()),
         ibd_trial(bin_paths.clone()),
         unknown_withdrawal_trial(bin_paths.clone()),
-        vote_trial(bin_paths),
+        vote_trial(bin_paths.clone()),
+        swap_trial(bin_paths),
     ]
 }
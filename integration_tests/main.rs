@@ -4,6 +4,7 @@ use tracing_subscriber::{filter as tracing_filter, layer::SubscriberExt};
 mod ibd;
 mod integration_test;
 mod setup;
+mod swap;
 mod unknown_withdrawal;
 mod util;
 mod vote;
@@ -39,6 +39,8 @@ impl ReservedPorts {
 pub struct Init {
     pub bitassets_app: PathBuf,
     pub data_dir_suffix: Option<String>,
+    /// Parent chain nodes, as `CHAIN:URL`
+    pub parent_chain_nodes: Vec<String>,
 }
 
 #[derive(Debug, Error)]
@@ -171,8 +173,11 @@ impl Sidechain for PostSetup {
             rpc_port: reserved_ports.rpc.port(),
             zmq_port: reserved_ports.zmq.port(),
         };
+        let args = init.parent_chain_nodes.into_iter().flat_map(|node| {
+            ["--parent-chain-node".to_owned(), node]
+        });
         let bitassets_app_task = bitassets_app
-            .spawn_command_with_args::<String, String, _, _, _>([], [], {
+            .spawn_command_with_args::<String, String, _, _, _>([], args, {
                 let res_tx = res_tx.clone();
                 move |err| {
                     let _err: Result<(), _> = res_tx.unbounded_send(Err(err));
@@ -224,7 +229,8 @@ impl Sidechain for PostSetup {
                     | FilledOutputContent::BitAsset(..)
                     | FilledOutputContent::BitAssetControl(_)
                     | FilledOutputContent::BitAssetReservation(_, _)
-                    | FilledOutputContent::DutchAuctionReceipt(_) => false,
+                    | FilledOutputContent::DutchAuctionReceipt(_)
+                    | FilledOutputContent::Htlc(_) => false,
                 }
                 && match utxo.outpoint {
                     plain_bitassets::types::OutPoint::Deposit(outpoint) => {
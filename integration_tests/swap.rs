@@ -0,0 +1,331 @@
+//! Test an L2 → L1 swap against a mock parent chain
+
+use std::{path::Path, time::Duration};
+
+use bip300301_enforcer_integration_tests::{
+    integration_test::{
+        activate_sidechain, deposit, fund_enforcer, propose_sidechain,
+    },
+    setup::{
+        Mode, Network, PostSetup as EnforcerPostSetup, Sidechain as _,
+        setup as setup_enforcer,
+    },
+    util::{AbortOnDrop, AsyncTrial},
+};
+use futures::{
+    FutureExt as _, StreamExt as _, channel::mpsc, future::BoxFuture,
+};
+use plain_bitassets::{
+    parent_chain::{
+        MockChain, MockTx, ParentChainType, SwapState, TxId, address,
+        client::CHECKPOINT_DEPTH,
+    },
+    types::{GetBitcoinValue as _, Txid},
+};
+use plain_bitassets_app_rpc_api::RpcClient as _;
+use tokio::time::sleep;
+use tracing::Instrument as _;
+
+use crate::{
+    setup::{Init, PostSetup},
+    util::BinPaths,
+};
+
+#[derive(Debug)]
+struct BitAssetsNodes {
+    /// Sidechain process that offers L2 coins for BTC
+    alice: PostSetup,
+    /// Sidechain process that pays BTC for L2 coins
+    bob: PostSetup,
+}
+
+impl BitAssetsNodes {
+    async fn setup(
+        bin_paths: &BinPaths,
+        res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+        enforcer_post_setup: &EnforcerPostSetup,
+        parent_chain_node: &str,
+    ) -> anyhow::Result<Self> {
+        // Initialize a single node
+        let setup_single = |suffix: &str| {
+            PostSetup::setup(
+                Init {
+                    bitassets_app: bin_paths.bitassets.clone(),
+                    data_dir_suffix: Some(suffix.to_owned()),
+                    parent_chain_nodes: vec![parent_chain_node.to_owned()],
+                },
+                enforcer_post_setup,
+                res_tx.clone(),
+            )
+        };
+        let res = Self {
+            alice: setup_single("alice").await?,
+            bob: setup_single("bob").await?,
+        };
+        tracing::debug!(
+            alice_addr = %res.alice.net_addr(),
+            bob_addr = %res.bob.net_addr(),
+            "Connecting Alice to Bob");
+        let () = res
+            .alice
+            .rpc_client
+            .connect_peer(res.bob.net_addr().into())
+            .await?;
+        Ok(res)
+    }
+
+    /// Wait for both nodes to reach the same sidechain tip
+    async fn wait_for_sync(&self) -> anyhow::Result<()> {
+        for _ in 0..30 {
+            let alice_blocks = self.alice.rpc_client.getblockcount().await?;
+            let bob_blocks = self.bob.rpc_client.getblockcount().await?;
+            if alice_blocks == bob_blocks {
+                return Ok(());
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+        Err(anyhow::anyhow!("Alice and Bob did not sync"))
+    }
+}
+
+const DEPOSIT_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(21_000_000);
+const DEPOSIT_FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+/// L2 coins sent to Bob, to pay sidechain fees
+const BOB_FUNDING: bitcoin::Amount = bitcoin::Amount::from_sat(1_000_000);
+const FEE: bitcoin::Amount = bitcoin::Amount::from_sat(1_000);
+/// Alice's BTC address, that Bob pays
+const ALICE_L1_ADDRESS: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+const L1_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(100_000);
+const L2_AMOUNT: bitcoin::Amount = bitcoin::Amount::from_sat(100_000);
+const REQUIRED_CONFIRMATIONS: u32 = 3;
+
+/// Script a step on the mock BTC chain that both nodes watch
+fn update_mock_chain<F, T>(path: &Path, f: F) -> std::io::Result<T>
+where
+    F: FnOnce(&mut MockChain) -> T,
+{
+    MockChain::update_file(ParentChainType::Btc, path, f)
+}
+
+/// Initial setup for the test
+async fn setup(
+    bin_paths: &BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<(EnforcerPostSetup, BitAssetsNodes)> {
+    let mut enforcer_post_setup = setup_enforcer(
+        &bin_paths.others,
+        Network::Regtest,
+        Mode::Mempool,
+        res_tx.clone(),
+    )
+    .await?;
+    let () = propose_sidechain::<PostSetup>(&mut enforcer_post_setup).await?;
+    tracing::info!("Proposed sidechain successfully");
+    let () = activate_sidechain::<PostSetup>(&mut enforcer_post_setup).await?;
+    tracing::info!("Activated sidechain successfully");
+    let () = fund_enforcer::<PostSetup>(&mut enforcer_post_setup).await?;
+    // Swaps are created with a buried checkpoint, so the mock chain needs
+    // some blocks first
+    let mock_chain_path = mock_chain_path(&enforcer_post_setup);
+    let () = update_mock_chain(&mock_chain_path, |chain| {
+        chain.mine(CHECKPOINT_DEPTH as u32)
+    })?;
+    let parent_chain_node = format!("BTC:mock://{}", mock_chain_path.display());
+    let mut bitassets_nodes = BitAssetsNodes::setup(
+        bin_paths,
+        res_tx,
+        &enforcer_post_setup,
+        &parent_chain_node,
+    )
+    .await?;
+    let alice_deposit_address =
+        bitassets_nodes.alice.get_deposit_address().await?;
+    let () = deposit(
+        &mut enforcer_post_setup,
+        &mut bitassets_nodes.alice,
+        &alice_deposit_address,
+        DEPOSIT_AMOUNT,
+        DEPOSIT_FEE,
+    )
+    .await?;
+    tracing::info!("Deposited to sidechain successfully");
+    Ok((enforcer_post_setup, bitassets_nodes))
+}
+
+fn mock_chain_path(
+    enforcer_post_setup: &EnforcerPostSetup,
+) -> std::path::PathBuf {
+    enforcer_post_setup.out_dir.path().join("mock-btc.json")
+}
+
+async fn swap_task(
+    bin_paths: BinPaths,
+    res_tx: mpsc::UnboundedSender<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let (mut enforcer_post_setup, bitassets_nodes) =
+        setup(&bin_paths, res_tx.clone()).await?;
+    let mock_chain_path = mock_chain_path(&enforcer_post_setup);
+    tracing::info!("Funding Bob");
+    let bob_addr = bitassets_nodes.bob.rpc_client.get_new_address().await?;
+    let _: Txid = bitassets_nodes
+        .alice
+        .rpc_client
+        .transfer(bob_addr, BOB_FUNDING.to_sat(), FEE.to_sat(), None)
+        .await?;
+    bitassets_nodes
+        .alice
+        .bmm_single(&mut enforcer_post_setup)
+        .await?;
+    tracing::info!("Creating swap offer");
+    let swap_id = bitassets_nodes
+        .alice
+        .rpc_client
+        .create_swap(
+            ParentChainType::Btc,
+            ALICE_L1_ADDRESS.to_owned(),
+            L1_AMOUNT.to_sat(),
+            None,
+            L2_AMOUNT.to_sat(),
+            Some(REQUIRED_CONFIRMATIONS),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await?;
+    bitassets_nodes
+        .alice
+        .bmm_single(&mut enforcer_post_setup)
+        .await?;
+    let () = bitassets_nodes.wait_for_sync().await?;
+    let swap_status = bitassets_nodes
+        .bob
+        .rpc_client
+        .get_swap_status(swap_id.clone())
+        .await?;
+    anyhow::ensure!(swap_status.swap.state == SwapState::Pending);
+    tracing::info!("Paying Alice on the mock BTC chain");
+    let l1_tx = bitcoin::Transaction {
+        version: bitcoin::transaction::Version::TWO,
+        lock_time: bitcoin::absolute::LockTime::ZERO,
+        input: vec![bitcoin::TxIn::default()],
+        output: vec![bitcoin::TxOut {
+            value: L1_AMOUNT,
+            script_pubkey: address::script_pubkey(
+                &ParentChainType::Btc,
+                ALICE_L1_ADDRESS,
+            )?,
+        }],
+    };
+    let l1_txid = l1_tx.compute_txid();
+    let () = update_mock_chain(&mock_chain_path, |chain| {
+        let _: TxId = chain.broadcast(MockTx::Bitcoin(l1_tx));
+        chain.mine(1)
+    })?;
+    tracing::info!("Filling swap offer");
+    let bob_claim_addr =
+        bitassets_nodes.bob.rpc_client.get_new_address().await?;
+    let _: Txid = bitassets_nodes
+        .bob
+        .rpc_client
+        .fill_swap(
+            swap_id.clone(),
+            l1_txid.to_string(),
+            Some(bob_claim_addr),
+            FEE.to_sat(),
+            None,
+        )
+        .await?;
+    bitassets_nodes
+        .bob
+        .bmm_single(&mut enforcer_post_setup)
+        .await?;
+    let () = bitassets_nodes.wait_for_sync().await?;
+    tracing::info!("Checking that claims need L1 confirmations");
+    anyhow::ensure!(
+        bitassets_nodes
+            .bob
+            .rpc_client
+            .claim_swap(swap_id.clone(), None)
+            .await
+            .is_err()
+    );
+    tracing::info!("Checking that claims fail during an L1 outage");
+    let () = update_mock_chain(&mock_chain_path, |chain| {
+        chain.mine(REQUIRED_CONFIRMATIONS - 1);
+        chain.set_offline(true)
+    })?;
+    anyhow::ensure!(
+        bitassets_nodes
+            .bob
+            .rpc_client
+            .claim_swap(swap_id.clone(), None)
+            .await
+            .is_err()
+    );
+    let () =
+        update_mock_chain(&mock_chain_path, |chain| chain.set_offline(false))?;
+    tracing::info!("Claiming swap");
+    let _: Txid = bitassets_nodes
+        .bob
+        .rpc_client
+        .claim_swap(swap_id.clone(), None)
+        .await?;
+    bitassets_nodes
+        .bob
+        .bmm_single(&mut enforcer_post_setup)
+        .await?;
+    let () = bitassets_nodes.wait_for_sync().await?;
+    let swap_status = bitassets_nodes
+        .alice
+        .rpc_client
+        .get_swap_status(swap_id)
+        .await?;
+    anyhow::ensure!(swap_status.swap.state == SwapState::Completed);
+    let claimed: u64 = bitassets_nodes
+        .bob
+        .rpc_client
+        .my_utxos()
+        .await?
+        .into_iter()
+        .filter(|utxo| utxo.output.address == bob_claim_addr)
+        .map(|utxo| utxo.output.content.get_bitcoin_value().to_sat())
+        .sum();
+    anyhow::ensure!(claimed == L2_AMOUNT.to_sat());
+    // Cleanup
+    {
+        drop(bitassets_nodes);
+        tracing::info!(
+            "Removing {}",
+            enforcer_post_setup.out_dir.path().display()
+        );
+        drop(enforcer_post_setup.tasks);
+        // Wait for tasks to die
+        sleep(std::time::Duration::from_secs(1)).await;
+        enforcer_post_setup.out_dir.cleanup()?;
+    }
+    Ok(())
+}
+
+async fn swap(bin_paths: BinPaths) -> anyhow::Result<()> {
+    let (res_tx, mut res_rx) = mpsc::unbounded();
+    let _test_task: AbortOnDrop<()> = tokio::task::spawn({
+        let res_tx = res_tx.clone();
+        async move {
+            let res = swap_task(bin_paths, res_tx.clone()).await;
+            let _send_err: Result<(), _> = res_tx.unbounded_send(res);
+        }
+        .in_current_span()
+    })
+    .into();
+    res_rx.next().await.ok_or_else(|| {
+        anyhow::anyhow!("Unexpected end of test task result stream")
+    })?
+}
+
+pub fn swap_trial(
+    bin_paths: BinPaths,
+) -> AsyncTrial<BoxFuture<'static, anyhow::Result<()>>> {
+    AsyncTrial::new("swap", swap(bin_paths).boxed())
+}
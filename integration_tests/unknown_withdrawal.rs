@@ -58,6 +58,7 @@ async fn unknown_withdrawal_task(
         Init {
             bitassets_app: bin_paths.bitassets.clone(),
             data_dir_suffix: Some("withdrawer".to_owned()),
+            parent_chain_nodes: Vec::new(),
         },
         &enforcer_post_setup,
         res_tx.clone(),
@@ -89,6 +90,7 @@ async fn unknown_withdrawal_task(
         Init {
             bitassets_app: bin_paths.bitassets,
             data_dir_suffix: Some("successor".to_owned()),
+            parent_chain_nodes: Vec::new(),
         },
         &enforcer_post_setup,
         res_tx,
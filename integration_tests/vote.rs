@@ -50,6 +50,7 @@ impl BitAssetsNodes {
                 Init {
                     bitassets_app: bin_paths.bitassets.clone(),
                     data_dir_suffix: Some(suffix.to_owned()),
+                    parent_chain_nodes: Vec::new(),
                 },
                 enforcer_post_setup,
                 res_tx.clone(),
@@ -6,9 +6,12 @@ use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::types::{
-    Address, AuthorizedTransaction, Body, GetAddress, Transaction, Verify,
-    VerifyingKey,
+use crate::{
+    parent_chain::Swap,
+    types::{
+        Address, AuthorizedTransaction, Body, FilledOutput, GetAddress,
+        OutPoint, Transaction, TxData, Verify, VerifyingKey,
+    },
 };
 
 pub use ed25519_dalek::{SignatureError, Signer, SigningKey, Verifier};
@@ -152,6 +155,50 @@ pub fn get_address(verifying_key: &VerifyingKey) -> Address {
     Address(output)
 }
 
+/// Address whose key must authorize spending `spent_output` at `outpoint`
+/// in `transaction`, in a block at sidechain height `height`. `locked_to` is
+/// the swap that the output is locked to, if any.
+///
+/// Usually this is the address of the spent output. A `SwapClaim` spends the
+/// offerer's escrow without the offerer's signature, and is authorized by the
+/// key of the swap recipient instead, or of the filler that paid the proven
+/// L1 tx if the swap is partially fillable. The bond of a fill that timed out
+/// before it was claimed is forfeited, and spent by the key of the offerer.
+/// A hash time-locked output is redeemed by the key of its recipient, in an
+/// `HtlcRedeem` tx that reveals the preimage of its hash lock.
+pub fn get_authorizer(
+    transaction: &Transaction,
+    outpoint: &OutPoint,
+    spent_output: &FilledOutput,
+    locked_to: Option<&Swap>,
+    height: u32,
+) -> Address {
+    if let Some(TxData::HtlcRedeem { preimage }) = &transaction.data
+        && let Some(htlc) = spent_output.htlc()
+        && htlc.is_preimage(preimage)
+    {
+        return htlc.recipient;
+    }
+    if let Some(TxData::SwapClaim {
+        swap_id,
+        proof_data,
+    }) = &transaction.data
+        && let Some(swap) = locked_to
+        && swap.id.0 == *swap_id
+        && swap.l2_offerer == Some(spent_output.address)
+        && let Some(recipient) = swap.claimant(&proof_data.l1_txid())
+    {
+        return recipient;
+    }
+    if let Some(swap) = locked_to
+        && let Some(offerer) = swap.l2_offerer
+        && swap.is_bond_forfeited(outpoint, height)
+    {
+        return offerer;
+    }
+    spent_output.address
+}
+
 struct Package<'a> {
     messages: Vec<&'a [u8]>,
     signatures: Vec<ed25519_dalek::Signature>,
@@ -19,7 +19,12 @@ use crate::{
     archive::{self, Archive},
     mempool::{self, MemPool},
     net::{self, Net, Peer},
-    parent_chain::{swap::SwapManager, ParentChainClient},
+    parent_chain::{
+        ParentChainClient,
+        client::TxId,
+        swap::{Swap, SwapError, SwapId, SwapManager},
+        watcher::{self, SwapWatch, SwapWatcher},
+    },
     state::{
         self, AmmPair, AmmPoolState, BitAssetSeqId, DutchAuctionState, State,
     },
@@ -85,6 +90,16 @@ pub enum Error {
     SendMainchainTaskRequest,
     #[error("state error")]
     State(#[source] Box<state::Error>),
+    #[error(
+        "L1 payment proofs are only used for Monero swaps, which are not supported"
+    )]
+    L1PaymentProofUnsupported,
+    #[error(transparent)]
+    Swap(#[from] SwapError),
+    #[error("Swap not found")]
+    SwapNotFound(SwapId),
+    #[error("swap watcher error")]
+    SwapWatcher(#[from] watcher::Error),
     #[error("Utreexo error: {0}")]
     Utreexo(String),
     #[error("Verify BMM error")]
@@ -128,6 +143,7 @@ pub struct Node<MainchainTransport = Channel> {
     net_task: NetTaskHandle,
     parent_chain_client: Option<Arc<ParentChainClient>>,
     swap_manager: Arc<Mutex<SwapManager>>,
+    swap_watcher: SwapWatcher,
     state: State,
     #[cfg(feature = "zmq")]
     zmq_pub_handler: Arc<ZmqPubHandler>,
@@ -168,7 +184,8 @@ where
                     State::NUM_DBS
                         + Archive::NUM_DBS
                         + MemPool::NUM_DBS
-                        + Net::NUM_DBS,
+                        + Net::NUM_DBS
+                        + SwapWatcher::NUM_DBS,
                 );
             // Apply LMDB "fast" flags consistent with our benchmark setup:
             // - WRITE_MAP lets us write directly into the memory map instead of
@@ -195,16 +212,11 @@ where
             unsafe { Env::open(&env_open_opts, &env_path) }?
         };
         let state = State::new(&env)?;
+        let swap_watcher = SwapWatcher::new(&env)?;
         
         // Initialize swap manager and load swaps from database
         let mut swap_manager = SwapManager::new();
-        {
-            let rotxn = env.read_txn()?;
-            let swaps = state.load_all_swaps(&rotxn)?;
-            for swap in swaps {
-                swap_manager.swaps.insert(swap.id.clone(), swap);
-            }
-        }
+        swap_manager.swaps = load_working_swaps(&env, &state, &swap_watcher)?;
         let swap_manager = Arc::new(Mutex::new(swap_manager));
         
         // Wrap parent chain client in Arc if provided
@@ -237,39 +249,68 @@ where
             #[cfg(feature = "zmq")]
             zmq_pub_handler.clone(),
         );
-        // Spawn background task to update swap states periodically
+        // Spawn background task to update swap states periodically.
+        // This only tracks the L1 status of swaps for this node, and is only
+        // recorded in the swap watcher, never in state: claims carry their
+        // own L1 proofs, which are verified by every node.
         if let Some(ref parent_chain_client) = parent_chain_client {
             let swap_manager_clone = swap_manager.clone();
+            let swap_watcher_clone = swap_watcher.clone();
             let state_clone = state.clone();
             let env_clone = env.clone();
+            let parent_chain_client = parent_chain_client.clone();
             runtime.spawn(async move {
                 let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+                // Polling may take longer than the interval if a parent chain
+                // node is slow to respond
+                interval.set_missed_tick_behavior(
+                    tokio::time::MissedTickBehavior::Delay,
+                );
                 loop {
                     interval.tick().await;
-                    if let Ok(mut sm) = swap_manager_clone.lock() {
-                        if let Ok(rotxn) = env_clone.read_txn() {
-                            if let Ok(current_height) = state_clone.try_get_height(&rotxn) {
-                                if let Some(height) = current_height {
-                                    if let Err(e) = sm.update_all_swaps(
-                                        parent_chain_client.as_ref(),
-                                        height,
-                                    ) {
-                                        tracing::warn!("Error updating swaps: {}", e);
-                                    } else {
-                                        // Save updated swaps to database
-                                        if let Ok(mut rwtxn) = env_clone.write_txn() {
-                                            for swap in sm.swaps.values() {
-                                                if let Err(e) = state_clone.save_swap(&mut rwtxn, swap) {
-                                                    tracing::warn!("Error saving swap: {}", e);
-                                                }
-                                            }
-                                            let _ = rwtxn.commit();
-                                        }
-                                    }
-                                }
-                            }
+                    let current_height = match env_clone.read_txn() {
+                        Ok(rotxn) => state_clone.try_get_height(&rotxn).ok().flatten(),
+                        Err(_) => None,
+                    };
+                    let Some(height) = current_height else {
+                        continue;
+                    };
+                    // Pick up swaps that were created, filled or completed
+                    // since the last update
+                    match load_working_swaps(
+                        &env_clone,
+                        &state_clone,
+                        &swap_watcher_clone,
+                    ) {
+                        Ok(swaps) => {
+                            swap_manager_clone.lock().await.swaps = swaps;
+                        }
+                        Err(err) => {
+                            let err = anyhow::Error::from(err);
+                            tracing::error!("Failed to load swaps: {err:#}");
+                            continue;
                         }
                     }
+                    // Locks the swap manager only briefly, so that RPC
+                    // requests are not blocked on parent chain nodes
+                    let updated = SwapManager::update_all_swaps(
+                        &swap_manager_clone,
+                        &parent_chain_client,
+                        height,
+                    )
+                    .await;
+                    let swap_manager = swap_manager_clone.lock().await;
+                    if let Err(err) = record_swap_updates(
+                        &env_clone,
+                        &swap_watcher_clone,
+                        &swap_manager,
+                        &updated,
+                    ) {
+                        let err = anyhow::Error::from(err);
+                        tracing::error!(
+                            "Failed to record swap updates: {err:#}"
+                        );
+                    }
                 }
             });
         }
@@ -285,6 +326,7 @@ where
             net_task,
             parent_chain_client,
             swap_manager,
+            swap_watcher,
             state,
             #[cfg(feature = "zmq")]
             zmq_pub_handler: zmq_pub_handler.clone(),
@@ -526,6 +568,18 @@ where
             .map_err(state::Error::BitAsset)?)
     }
 
+    /// Get the addresses whose keys must authorize each input of a tx, in
+    /// input order
+    pub fn get_authorizers(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Vec<Address>, Error> {
+        let rotxn = self.env.read_txn()?;
+        let filled_tx = self.state.fill_transaction(&rotxn, transaction)?;
+        let authorizers = self.state.get_authorizers(&rotxn, &filled_tx)?;
+        Ok(authorizers)
+    }
+
     pub fn submit_transaction(
         &self,
         transaction: AuthorizedTransaction,
@@ -1025,8 +1079,135 @@ where
         &self.env
     }
 
+    /// Get reference to the parent chain client, if configured
+    pub fn parent_chain_client(&self) -> Option<&Arc<ParentChainClient>> {
+        self.parent_chain_client.as_ref()
+    }
+
     /// Get reference to swap manager
     pub fn swap_manager(&self) -> Option<&Arc<Mutex<SwapManager>>> {
         Some(&self.swap_manager)
     }
+
+    /// Get this node's local data about a swap
+    pub fn get_swap_watch(
+        &self,
+        swap_id: &SwapId,
+    ) -> Result<Option<SwapWatch>, Error> {
+        let rotxn = self.env.read_txn()?;
+        Ok(self.swap_watcher.get(&rotxn, swap_id)?)
+    }
+
+    /// Get a swap from state, with this node's local data applied
+    pub fn get_local_swap(
+        &self,
+        swap_id: &SwapId,
+    ) -> Result<Option<Swap>, Error> {
+        let rotxn = self.env.read_txn()?;
+        let Some(mut swap) = self.state.get_swap(&rotxn, swap_id)? else {
+            return Ok(None);
+        };
+        if let Some(watch) = self.swap_watcher.get(&rotxn, swap_id)? {
+            watch.apply(&mut swap);
+        }
+        Ok(Some(swap))
+    }
+
+    /// Set the L1 transaction that this node expects to pay an L2 → L1
+    /// swap. This is only recorded locally, and does not change the swap in
+    /// state.
+    /// Payment proofs are rejected, since they are only used for Monero
+    /// swaps, and Monero swaps cannot be created.
+    pub async fn set_swap_l1_txid(
+        &self,
+        swap_id: &SwapId,
+        l1_txid: TxId,
+        l1_payment_proof: Option<String>,
+    ) -> Result<(), Error> {
+        if l1_payment_proof.is_some() {
+            return Err(Error::L1PaymentProofUnsupported);
+        }
+        let mut rwtxn = self.env.write_txn()?;
+        let mut swap = self
+            .state
+            .get_swap(&rwtxn, swap_id)?
+            .ok_or_else(|| Error::SwapNotFound(swap_id.clone()))?;
+        swap.set_l1_txid(l1_txid.clone(), l1_payment_proof.clone())?;
+        let mut watch = self
+            .swap_watcher
+            .get(&rwtxn, swap_id)?
+            .unwrap_or_default();
+        watch.l1_txid = Some(l1_txid);
+        watch.l1_payment_proof = l1_payment_proof;
+        self.swap_watcher.put(&mut rwtxn, swap_id, &watch)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        watch.apply(&mut swap);
+        self.swap_manager
+            .lock()
+            .await
+            .swaps
+            .insert(swap_id.clone(), swap);
+        Ok(())
+    }
+
+    /// Set or clear this node's note on a swap
+    pub fn set_swap_note(
+        &self,
+        swap_id: &SwapId,
+        note: Option<String>,
+    ) -> Result<(), Error> {
+        let mut rwtxn = self.env.write_txn()?;
+        if self.state.get_swap(&rwtxn, swap_id)?.is_none() {
+            return Err(Error::SwapNotFound(swap_id.clone()));
+        }
+        let mut watch = self
+            .swap_watcher
+            .get(&rwtxn, swap_id)?
+            .unwrap_or_default();
+        watch.note = note;
+        self.swap_watcher.put(&mut rwtxn, swap_id, &watch)?;
+        rwtxn.commit().map_err(RwTxnError::from)?;
+        Ok(())
+    }
+}
+
+/// Load the swaps from state, with this node's local data applied
+fn load_working_swaps(
+    env: &Env,
+    state: &State,
+    swap_watcher: &SwapWatcher,
+) -> Result<HashMap<SwapId, Swap>, Error> {
+    let rotxn = env.read_txn()?;
+    let mut swaps = HashMap::new();
+    for mut swap in state.load_all_swaps(&rotxn)? {
+        if let Some(watch) = swap_watcher.get(&rotxn, &swap.id)? {
+            watch.apply(&mut swap);
+        }
+        swaps.insert(swap.id.clone(), swap);
+    }
+    Ok(swaps)
+}
+
+/// Record the L1 status of swaps updated by the swap monitor
+fn record_swap_updates(
+    env: &Env,
+    swap_watcher: &SwapWatcher,
+    swap_manager: &SwapManager,
+    swap_ids: &[SwapId],
+) -> Result<(), Error> {
+    if swap_ids.is_empty() {
+        return Ok(());
+    }
+    let mut rwtxn = env.write_txn()?;
+    for swap_id in swap_ids {
+        let Some(swap) = swap_manager.get_swap(swap_id) else {
+            continue;
+        };
+        let mut watch =
+            swap_watcher.get(&rwtxn, swap_id)?.unwrap_or_default();
+        watch.record(swap);
+        swap_watcher.put(&mut rwtxn, swap_id, &watch)?;
+    }
+    rwtxn.commit().map_err(RwTxnError::from)?;
+    Ok(())
 }
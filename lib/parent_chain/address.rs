@@ -0,0 +1,387 @@
+//! Parent chain address decoding
+//!
+//! Swap claims must pay the L1 recipient address, so validation needs the
+//! script that an address corresponds to on each BTC-family chain.
+//! Addresses are decoded without checking their network, since the script
+//! does not depend on it.
+//! On account-based chains, payments are matched by address instead, so
+//! addresses are normalized.
+
+use bitcoin::{
+    PubkeyHash, ScriptBuf, ScriptHash, WitnessProgram, WitnessVersion,
+    address::NetworkUnchecked, bech32, hashes::Hash as _,
+};
+use thiserror::Error;
+
+use crate::parent_chain::config::ParentChainType;
+
+/// Bech32 HRPs for Litecoin mainnet, testnet, and regtest
+const LTC_SEGWIT_HRPS: [&str; 3] = ["ltc", "tltc", "rltc"];
+
+/// Base58 version bytes for Litecoin P2PKH addresses
+const LTC_P2PKH_VERSIONS: [u8; 2] = [0x30, 0x6f];
+
+/// Base58 version bytes for Litecoin P2SH addresses, including the legacy
+/// `3`-prefixed and `2`-prefixed versions shared with Bitcoin
+const LTC_P2SH_VERSIONS: [u8; 4] = [0x32, 0x3a, 0x05, 0xc4];
+
+/// Base58 version bytes for legacy Bitcoin Cash P2PKH addresses
+const BCH_P2PKH_VERSIONS: [u8; 2] = [0x00, 0x6f];
+
+/// Base58 version bytes for legacy Bitcoin Cash P2SH addresses
+const BCH_P2SH_VERSIONS: [u8; 2] = [0x05, 0xc4];
+
+/// CashAddr prefixes for Bitcoin Cash mainnet, testnet, and regtest
+const CASHADDR_PREFIXES: [&str; 3] = ["bitcoincash", "bchtest", "bchreg"];
+
+/// Version byte of Tron addresses, which gives base58 addresses their `T`
+/// prefix
+const TRON_ADDRESS_VERSION: u8 = 0x41;
+
+const CASHADDR_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Number of 5-bit characters in a CashAddr checksum
+const CASHADDR_CHECKSUM_LEN: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid {parent_chain} address `{address}`")]
+    Invalid {
+        parent_chain: ParentChainType,
+        address: String,
+    },
+    #[error("address decoding is not supported for {0}")]
+    UnsupportedChain(ParentChainType),
+}
+
+/// Decode a base58check address with a 1-byte version and a 20-byte hash
+fn decode_base58(
+    address: &str,
+    p2pkh_versions: &[u8],
+    p2sh_versions: &[u8],
+) -> Option<ScriptBuf> {
+    let payload = bitcoin::base58::decode_check(address).ok()?;
+    let (&version, hash) = payload.split_first()?;
+    let hash: [u8; 20] = hash.try_into().ok()?;
+    if p2pkh_versions.contains(&version) {
+        Some(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash)))
+    } else if p2sh_versions.contains(&version) {
+        Some(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash)))
+    } else {
+        None
+    }
+}
+
+/// Decode a segwit address with one of the specified HRPs
+fn decode_segwit(address: &str, hrps: &[&str]) -> Option<ScriptBuf> {
+    let (hrp, version, program) = bech32::segwit::decode(address).ok()?;
+    if !hrps.contains(&hrp.to_lowercase().as_str()) {
+        return None;
+    }
+    let version = WitnessVersion::try_from(version).ok()?;
+    let program = WitnessProgram::new(version, &program).ok()?;
+    Some(ScriptBuf::new_witness_program(&program))
+}
+
+fn cashaddr_polymod(values: impl IntoIterator<Item = u8>) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0x98f2bc8e61,
+        0x79b76d99e2,
+        0xf33e5fb3c4,
+        0xae2eabe2a8,
+        0x1e4f43e470,
+    ];
+    let mut checksum: u64 = 1;
+    for value in values {
+        let top = checksum >> 35;
+        checksum = ((checksum & 0x07_ffff_ffff) << 5) ^ u64::from(value);
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum ^ 1
+}
+
+/// Decode a CashAddr address, with or without its prefix
+fn decode_cashaddr(address: &str) -> Option<ScriptBuf> {
+    let address = address.to_lowercase();
+    let (prefixes, payload): (Vec<&str>, &str) = match address.split_once(':')
+    {
+        Some((prefix, payload)) => (vec![prefix], payload),
+        None => (CASHADDR_PREFIXES.to_vec(), address.as_str()),
+    };
+    let payload: Vec<u8> = payload
+        .bytes()
+        .map(|c| {
+            CASHADDR_CHARSET
+                .iter()
+                .position(|&charset_c| charset_c == c)
+                .map(|value| value as u8)
+        })
+        .collect::<Option<_>>()?;
+    if payload.len() <= CASHADDR_CHECKSUM_LEN {
+        return None;
+    }
+    let checksum_valid = prefixes.into_iter().any(|prefix| {
+        CASHADDR_PREFIXES.contains(&prefix)
+            && cashaddr_polymod(
+                prefix
+                    .bytes()
+                    .map(|c| c & 0x1f)
+                    .chain(std::iter::once(0))
+                    .chain(payload.iter().copied()),
+            ) == 0
+    });
+    if !checksum_valid {
+        return None;
+    }
+    // Convert from 5-bit groups to bytes, dropping the checksum and padding
+    let data = &payload[..payload.len() - CASHADDR_CHECKSUM_LEN];
+    let mut bytes = Vec::with_capacity(data.len() * 5 / 8);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for value in data {
+        acc = (acc << 5) | u32::from(*value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc & ((1 << bits) - 1)) != 0 {
+        return None;
+    }
+    let (&version, hash) = bytes.split_first()?;
+    // Only 160-bit hashes are in use
+    if version & 0x07 != 0 {
+        return None;
+    }
+    let hash: [u8; 20] = hash.try_into().ok()?;
+    match version >> 3 {
+        0 => Some(ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash))),
+        1 => Some(ScriptBuf::new_p2sh(&ScriptHash::from_byte_array(hash))),
+        _ => None,
+    }
+}
+
+/// Decode an address into the script that payments to it must use
+pub fn script_pubkey(
+    parent_chain: &ParentChainType,
+    address: &str,
+) -> Result<ScriptBuf, Error> {
+    let script_pubkey = match parent_chain {
+        ParentChainType::Btc => address
+            .parse::<bitcoin::Address<NetworkUnchecked>>()
+            .ok()
+            .map(|address| address.assume_checked().script_pubkey()),
+        ParentChainType::Bch => decode_cashaddr(address).or_else(|| {
+            decode_base58(address, &BCH_P2PKH_VERSIONS, &BCH_P2SH_VERSIONS)
+        }),
+        ParentChainType::Ltc => decode_segwit(address, &LTC_SEGWIT_HRPS)
+            .or_else(|| {
+                decode_base58(address, &LTC_P2PKH_VERSIONS, &LTC_P2SH_VERSIONS)
+            }),
+        ParentChainType::Xmr | ParentChainType::Eth | ParentChainType::Tron => {
+            return Err(Error::UnsupportedChain(*parent_chain));
+        }
+    };
+    script_pubkey.ok_or_else(|| Error::Invalid {
+        parent_chain: *parent_chain,
+        address: address.to_owned(),
+    })
+}
+
+/// Decode an `0x`-prefixed Ethereum address. Addresses are matched
+/// case-insensitively, so EIP-55 checksums are not checked.
+fn decode_eth(address: &str) -> Option<[u8; 20]> {
+    let hex_address = address.strip_prefix("0x")?;
+    if hex_address.len() != 40 {
+        return None;
+    }
+    let mut bytes = [0u8; 20];
+    hex::decode_to_slice(hex_address, &mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Decode a Tron address, in base58 (`T`-prefixed) or hex (`41`-prefixed)
+/// form, into its version byte and hash
+fn decode_tron(address: &str) -> Option<[u8; 21]> {
+    let bytes = if address.len() == 42 {
+        hex::decode(address).ok()?
+    } else {
+        bitcoin::base58::decode_check(address).ok()?
+    };
+    let bytes: [u8; 21] = bytes.try_into().ok()?;
+    (bytes[0] == TRON_ADDRESS_VERSION).then_some(bytes)
+}
+
+/// Normalize an address on an account-based chain, such as an account or a
+/// token contract, so that payments to it can be matched by address
+pub fn account(
+    parent_chain: &ParentChainType,
+    address: &str,
+) -> Result<String, Error> {
+    let account = match parent_chain {
+        ParentChainType::Eth => {
+            decode_eth(address).map(|bytes| format!("0x{}", hex::encode(bytes)))
+        }
+        ParentChainType::Tron => decode_tron(address)
+            .map(|bytes| bitcoin::base58::encode_check(&bytes)),
+        ParentChainType::Btc
+        | ParentChainType::Bch
+        | ParentChainType::Ltc
+        | ParentChainType::Xmr => {
+            return Err(Error::UnsupportedChain(*parent_chain));
+        }
+    };
+    account.ok_or_else(|| Error::Invalid {
+        parent_chain: *parent_chain,
+        address: address.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::bech32::{Fe32, Hrp};
+
+    use super::*;
+
+    #[test]
+    fn test_btc_address() {
+        let script = script_pubkey(
+            &ParentChainType::Btc,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        )
+        .unwrap();
+        assert!(script.is_p2wpkh());
+        assert!(script_pubkey(&ParentChainType::Btc, "not an address").is_err());
+    }
+
+    #[test]
+    fn test_bch_cashaddr_matches_legacy() {
+        let legacy =
+            script_pubkey(&ParentChainType::Bch, "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu")
+                .unwrap();
+        let cashaddr = script_pubkey(
+            &ParentChainType::Bch,
+            "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a",
+        )
+        .unwrap();
+        let cashaddr_no_prefix = script_pubkey(
+            &ParentChainType::Bch,
+            "qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a",
+        )
+        .unwrap();
+        assert!(legacy.is_p2pkh());
+        assert_eq!(legacy, cashaddr);
+        assert_eq!(legacy, cashaddr_no_prefix);
+
+        let legacy_p2sh =
+            script_pubkey(&ParentChainType::Bch, "3CWFddi6m4ndiGyKqzYvsFYagqDLPVMTzC")
+                .unwrap();
+        let cashaddr_p2sh = script_pubkey(
+            &ParentChainType::Bch,
+            "bitcoincash:ppm2qsznhks23z7629mms6s4cwef74vcwvn0h829pq",
+        )
+        .unwrap();
+        assert!(legacy_p2sh.is_p2sh());
+        assert_eq!(legacy_p2sh, cashaddr_p2sh);
+
+        // Corrupted checksum
+        assert!(
+            script_pubkey(
+                &ParentChainType::Bch,
+                "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6b",
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_eth_account() {
+        let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let normalized = account(&ParentChainType::Eth, checksummed).unwrap();
+        assert_eq!(normalized, checksummed.to_lowercase());
+        assert_eq!(
+            account(&ParentChainType::Eth, &normalized).unwrap(),
+            normalized
+        );
+        assert!(account(&ParentChainType::Eth, &checksummed[2..]).is_err());
+        assert!(account(&ParentChainType::Eth, "0x1234").is_err());
+        assert!(matches!(
+            account(&ParentChainType::Btc, checksummed),
+            Err(Error::UnsupportedChain(ParentChainType::Btc))
+        ));
+    }
+
+    #[test]
+    fn test_tron_account() {
+        let mut payload = vec![TRON_ADDRESS_VERSION];
+        payload.extend_from_slice(&[7u8; 20]);
+        let base58 = bitcoin::base58::encode_check(&payload);
+        assert!(base58.starts_with('T'));
+        // Tron APIs report addresses in hex
+        let hex_address = hex::encode(&payload);
+        assert_eq!(account(&ParentChainType::Tron, &base58).unwrap(), base58);
+        assert_eq!(
+            account(&ParentChainType::Tron, &hex_address).unwrap(),
+            base58
+        );
+
+        // Other version bytes are not Tron addresses
+        payload[0] = 0x00;
+        let bitcoin_address = bitcoin::base58::encode_check(&payload);
+        assert!(account(&ParentChainType::Tron, &bitcoin_address).is_err());
+        assert!(
+            account(&ParentChainType::Tron, &hex::encode(&payload)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_ltc_addresses() {
+        let hash = [7u8; 20];
+        let segwit = bech32::segwit::encode(
+            Hrp::parse("ltc").unwrap(),
+            Fe32::Q,
+            &hash,
+        )
+        .unwrap();
+        let script = script_pubkey(&ParentChainType::Ltc, &segwit).unwrap();
+        assert!(script.is_p2wpkh());
+
+        let mut payload = vec![LTC_P2PKH_VERSIONS[0]];
+        payload.extend_from_slice(&hash);
+        let p2pkh = bitcoin::base58::encode_check(&payload);
+        assert!(p2pkh.starts_with('L'));
+        let p2pkh_script = script_pubkey(&ParentChainType::Ltc, &p2pkh).unwrap();
+        assert_eq!(
+            p2pkh_script,
+            ScriptBuf::new_p2pkh(&PubkeyHash::from_byte_array(hash))
+        );
+
+        let mut payload = vec![LTC_P2SH_VERSIONS[0]];
+        payload.extend_from_slice(&hash);
+        let p2sh = bitcoin::base58::encode_check(&payload);
+        assert!(p2sh.starts_with('M'));
+        assert!(script_pubkey(&ParentChainType::Ltc, &p2sh).unwrap().is_p2sh());
+
+        // Bitcoin segwit addresses are not valid on Litecoin
+        assert!(
+            script_pubkey(
+                &ParentChainType::Ltc,
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_chain() {
+        assert!(matches!(
+            script_pubkey(&ParentChainType::Eth, "0x00"),
+            Err(Error::UnsupportedChain(ParentChainType::Eth))
+        ));
+    }
+}
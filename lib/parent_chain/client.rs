@@ -2,11 +2,20 @@
 //!
 //! Provides a unified interface for interacting with different parent chains
 
+use futures::future::BoxFuture;
 use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 
-use crate::parent_chain::config::{ChainAuth, ParentChainConfig, ParentChainNodeConfig, ParentChainType};
+use crate::parent_chain::{
+    address,
+    config::{ChainAuth, ParentChainConfig, ParentChainNodeConfig, ParentChainType},
+    electrum::{self, ElectrumClient},
+    esplora::{self, EsploraClient},
+    mock::{self, MockParentChainClient},
+    proof::{self, L1TxProof},
+    quorum::{L1Disagreement, QuorumClient},
+};
 
 /// Transaction ID type (varies by chain)
 #[derive(Clone, Debug, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
@@ -17,6 +26,51 @@ pub enum TxId {
     Hash(Vec<u8>),
 }
 
+/// Recipient of a parent chain transaction output
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum L1Destination {
+    /// Output script, on BTC-family chains
+    Script(bitcoin::ScriptBuf),
+    /// Address, on chains without output scripts. Addresses on
+    /// account-based chains are normalized by [`address::account`].
+    Address(String),
+}
+
+impl L1Destination {
+    /// Destination of payments to `address` on `parent_chain`, if the
+    /// address can be decoded
+    pub(crate) fn parse(
+        parent_chain: &ParentChainType,
+        address: &str,
+    ) -> Option<Self> {
+        match address::script_pubkey(parent_chain, address) {
+            Ok(script_pubkey) => Some(Self::Script(script_pubkey)),
+            Err(address::Error::UnsupportedChain(_)) => {
+                match address::account(parent_chain, address) {
+                    Ok(account) => Some(Self::Address(account)),
+                    Err(address::Error::UnsupportedChain(_)) => {
+                        Some(Self::Address(address.to_owned()))
+                    }
+                    Err(address::Error::Invalid { .. }) => None,
+                }
+            }
+            Err(address::Error::Invalid { .. }) => None,
+        }
+    }
+}
+
+/// Decoded output of a parent chain transaction
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParentChainTxOutput {
+    pub destination: L1Destination,
+    /// Token contract that the output pays, normalized by
+    /// [`address::account`]. `None` for the parent chain's native asset.
+    pub token: Option<String>,
+    /// Amount in base units of the paid asset. Native ETH amounts are in
+    /// gwei. `None` if the amount does not fit in 64 bits.
+    pub amount: Option<bitcoin::Amount>,
+}
+
 /// Transaction information from a parent chain
 #[derive(Clone, Debug)]
 pub struct ParentChainTx {
@@ -24,6 +78,42 @@ pub struct ParentChainTx {
     pub confirmations: u32,
     pub block_hash: Option<String>,
     pub block_height: Option<u64>,
+    /// Decoded outputs of the transaction
+    pub outputs: Vec<ParentChainTxOutput>,
+}
+
+impl ParentChainTx {
+    /// Total amount that the transaction pays to `address` on
+    /// `parent_chain`, in `token` if set, or else in the chain's native
+    /// asset. Addresses that cannot be decoded are never paid.
+    /// Returns `None` if the amount of a paying output, or the total, does
+    /// not fit in 64 bits.
+    pub fn amount_paid_to(
+        &self,
+        parent_chain: &ParentChainType,
+        token: Option<&str>,
+        address: &str,
+    ) -> Option<bitcoin::Amount> {
+        let Some(destination) = L1Destination::parse(parent_chain, address)
+        else {
+            return Some(bitcoin::Amount::ZERO);
+        };
+        let token = match token
+            .map(|token| address::account(parent_chain, token))
+            .transpose()
+        {
+            Ok(token) => token,
+            Err(_) => return Some(bitcoin::Amount::ZERO),
+        };
+        self.outputs
+            .iter()
+            .filter(|output| {
+                output.destination == destination && output.token == token
+            })
+            .try_fold(bitcoin::Amount::ZERO, |total, output| {
+                total.checked_add(output.amount?)
+            })
+    }
 }
 
 /// Trait for parent chain clients
@@ -31,20 +121,76 @@ pub trait ParentChainClientTrait: Send + Sync {
     /// Get the chain type this client handles
     fn chain_type(&self) -> ParentChainType;
 
-    /// Get transaction information
-    async fn get_transaction(&self, txid: &TxId) -> Result<Option<ParentChainTx>, Error>;
+    /// Get transaction information, including the decoded outputs of the
+    /// transaction
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>>;
+
+    /// Get transaction information, using a payment proof from the payer to
+    /// decode the payment to `address`. Proofs are only needed on chains
+    /// with private outputs, so they are ignored by default.
+    fn get_transaction_with_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        address: &'a str,
+        payment_proof: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let _ = (address, payment_proof);
+            self.get_transaction(txid).await
+        })
+    }
 
     /// Get current block height
-    async fn get_block_height(&self) -> Result<u64, Error>;
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>>;
 
     /// Verify a transaction exists and has sufficient confirmations
-    async fn verify_transaction(
-        &self,
-        txid: &TxId,
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
         min_confirmations: u32,
-    ) -> Result<bool, Error>;
+    ) -> BoxFuture<'a, Result<bool, Error>>;
+
+    /// Get a recent block header to use as a swap checkpoint. The header is
+    /// buried [`CHECKPOINT_DEPTH`] blocks deep, so that it is unlikely to be
+    /// reorged out.
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>>;
+
+    /// Build a proof that a transaction is buried under at least
+    /// `confirmations` blocks, on a header chain that builds on
+    /// `checkpoint`, for inclusion in a swap claim
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>>;
+
+    /// Decode an address on this chain into the script that payments to it
+    /// must use
+    fn script_pubkey(
+        &self,
+        address: &str,
+    ) -> Result<bitcoin::ScriptBuf, Error> {
+        Ok(address::script_pubkey(&self.chain_type(), address)?)
+    }
+
+    /// Last observed disagreement between the backends of this client about
+    /// `txid`, if any. Only clients that query several backends can
+    /// disagree.
+    fn l1_disagreement(&self, txid: &TxId) -> Option<L1Disagreement> {
+        let _ = txid;
+        None
+    }
 }
 
+/// Depth below the tip at which swap checkpoints are taken
+pub const CHECKPOINT_DEPTH: u64 = 6;
+
 /// Client manager for multiple parent chains
 pub struct ParentChainClient {
     clients: HashMap<ParentChainType, Box<dyn ParentChainClientTrait>>,
@@ -52,14 +198,21 @@ pub struct ParentChainClient {
 
 impl ParentChainClient {
     pub fn new(config: &ParentChainConfig) -> Result<Self, Error> {
-        let mut clients = HashMap::new();
-
-        // Initialize BTC client if configured
-        if let Some(btc_config) = config.get_chain(&ParentChainType::Btc) {
-            let btc_client = BtcClient::new(btc_config)?;
-            clients.insert(ParentChainType::Btc, Box::new(btc_client));
+        let mut clients: HashMap<_, Box<dyn ParentChainClientTrait>> =
+            HashMap::new();
+        for (chain, node_config) in &config.chains {
+            let backends = node_config
+                .backend_configs()
+                .into_iter()
+                .map(|backend_config| {
+                    let client = new_backend(*chain, &backend_config)?;
+                    Ok((backend_config.node_url, client))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let quorum_client =
+                QuorumClient::new(*chain, backends, node_config.quorum)?;
+            clients.insert(*chain, quorum_client.into_client());
         }
-        
         Ok(Self { clients })
     }
 
@@ -67,21 +220,58 @@ impl ParentChainClient {
         self.clients.get(chain).map(|c| c.as_ref())
     }
 
-    pub fn verify_transaction(
+    /// Verify that a transaction exists on `chain` and has at least
+    /// `min_confirmations` confirmations
+    pub async fn verify_transaction(
         &self,
         chain: &ParentChainType,
         txid: &TxId,
         min_confirmations: u32,
     ) -> Result<bool, Error> {
         let client = self
-            .clients
-            .get(chain)
+            .get_client(chain)
             .ok_or(Error::ChainNotConfigured(chain.clone()))?;
-        
-        // This would need to be async, but for now showing the structure
-        // In practice, this would need to be called from an async context
-        Ok(false) // Placeholder
+        client.verify_transaction(txid, min_confirmations).await
+    }
+
+    /// Last observed disagreement between the backends for `chain` about
+    /// `txid`, if any
+    pub fn l1_disagreement(
+        &self,
+        chain: &ParentChainType,
+        txid: &TxId,
+    ) -> Option<L1Disagreement> {
+        self.get_client(chain)?.l1_disagreement(txid)
+    }
+}
+
+/// Client for a single parent chain node. Mock parent chains can stand in
+/// for any chain, and are selected by URL scheme, as are Esplora and
+/// Electrum servers for BTC-family chains.
+fn new_backend(
+    chain: ParentChainType,
+    node_config: &ParentChainNodeConfig,
+) -> Result<Box<dyn ParentChainClientTrait>, Error> {
+    if node_config.node_url.scheme() == mock::URL_SCHEME {
+        let mock_client =
+            MockParentChainClient::from_url(chain, &node_config.node_url)?;
+        return Ok(Box::new(mock_client));
+    }
+    if esplora::is_esplora_url(&node_config.node_url) {
+        return Ok(Box::new(EsploraClient::new(chain, node_config)?));
+    }
+    if node_config.node_url.scheme() == electrum::URL_SCHEME {
+        return Ok(Box::new(ElectrumClient::new(chain, node_config)?));
     }
+    let client: Box<dyn ParentChainClientTrait> = match chain {
+        ParentChainType::Btc => Box::new(BtcClient::new(node_config)?),
+        ParentChainType::Ltc => Box::new(LtcClient::new(node_config)?),
+        ParentChainType::Bch => Box::new(BchClient::new(node_config)?),
+        ParentChainType::Eth => Box::new(EthClient::new(node_config)?),
+        ParentChainType::Xmr => Box::new(XmrClient::new(node_config)?),
+        ParentChainType::Tron => Box::new(TronClient::new(node_config)?),
+    };
+    Ok(client)
 }
 
 /// Bitcoin Core JSON-RPC client
@@ -109,6 +299,21 @@ impl BtcClient {
         })
     }
 
+    /// Get the header of the block at `height` in the active chain
+    async fn get_block_header_at(
+        &self,
+        height: u64,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let params = serde_json::json!([height]);
+        let block_hash: String = self.rpc_call("getblockhash", params).await?;
+        let params = serde_json::json!([block_hash, false]);
+        let header_hex: String = self.rpc_call("getblockheader", params).await?;
+        let header_bytes = hex::decode(&header_hex)
+            .map_err(|e| Error::Rpc(format!("Invalid block header hex: {}", e)))?;
+        bitcoin::consensus::deserialize(&header_bytes)
+            .map_err(|e| Error::Rpc(format!("Invalid block header: {}", e)))
+    }
+
     /// Make a JSON-RPC call to Bitcoin Core
     async fn rpc_call<T: serde::de::DeserializeOwned>(
         &self,
@@ -180,60 +385,1063 @@ impl ParentChainClientTrait for BtcClient {
         ParentChainType::Btc
     }
 
-    async fn get_transaction(&self, txid: &TxId) -> Result<Option<ParentChainTx>, Error> {
-        // Extract hex string from TxId
-        let txid_hex = match txid {
-            TxId::Hash32(hash) => hex::encode(hash),
-            TxId::Hash(_) => return Err(Error::InvalidTxId),
-        };
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            // Extract hex string from TxId
+            let txid_hex = match txid {
+                TxId::Hash32(hash) => hex::encode(hash),
+                TxId::Hash(_) => return Err(Error::InvalidTxId),
+            };
         
-        // Call getrawtransaction with verbose=true to get block info
-        let params = serde_json::json!([txid_hex, true]);
+            // Call getrawtransaction with verbose=true to get block info
+            let params = serde_json::json!([txid_hex, true]);
         
-        // Bitcoin Core returns null if transaction not found
-        let tx_info: Option<BtcTxInfo> = self.rpc_call("getrawtransaction", params).await?;
+            // Bitcoin Core returns null if transaction not found
+            let tx_info: Option<BtcTxInfo> = self.rpc_call("getrawtransaction", params).await?;
         
-        if let Some(tx_info) = tx_info {
-            // Confirmations can be negative for unconfirmed transactions
-            let confirmations = tx_info.confirmations.unwrap_or(0).max(0) as u32;
-            let block_hash = tx_info.blockhash;
-            let block_height = tx_info.blockheight;
+            if let Some(tx_info) = tx_info {
+                // Confirmations can be negative for unconfirmed transactions
+                let confirmations = tx_info.confirmations.unwrap_or(0).max(0) as u32;
+                let block_hash = tx_info.blockhash;
+                let block_height = tx_info.blockheight;
+                let outputs = tx_info
+                    .decode()?
+                    .output
+                    .into_iter()
+                    .map(|output| ParentChainTxOutput {
+                        destination: L1Destination::Script(
+                            output.script_pubkey,
+                        ),
+                        token: None,
+                        amount: Some(output.value),
+                    })
+                    .collect();
             
+                Ok(Some(ParentChainTx {
+                    txid: txid.clone(),
+                    confirmations,
+                    block_hash,
+                    block_height,
+                    outputs,
+                }))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let params = serde_json::json!([]);
+            let height: u64 = self.rpc_call("getblockcount", params).await?;
+            Ok(height)
+        })
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+        
+            if let Some(tx) = tx {
+                Ok(tx.confirmations >= min_confirmations)
+            } else {
+                Ok(false)
+            }
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            let tip_height = self.get_block_height().await?;
+            self.get_block_header_at(
+                tip_height.saturating_sub(CHECKPOINT_DEPTH),
+            )
+            .await
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            let txid_hex = match txid {
+                TxId::Hash32(hash) => hex::encode(hash),
+                TxId::Hash(_) => return Err(Error::InvalidTxId),
+            };
+            let l1_txid: bitcoin::Txid =
+                txid_hex.parse().map_err(|_| Error::InvalidTxId)?;
+
+            // Find the checkpoint in the active chain
+            let params =
+                serde_json::json!([checkpoint.block_hash().to_string(), true]);
+            let checkpoint_info: BtcHeaderInfo =
+                self.rpc_call("getblockheader", params).await?;
+            if checkpoint_info.confirmations < 1 {
+                return Err(Error::CheckpointNotInChain);
+            }
+
+            // Find the block that includes the transaction
+            let params = serde_json::json!([txid_hex, true]);
+            let tx_info: Option<BtcTxInfo> =
+                self.rpc_call("getrawtransaction", params).await?;
+            let tx_info = tx_info.ok_or(Error::TxNotFound)?;
+            let block_hash =
+                tx_info.blockhash.ok_or(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current: 0,
+                })?;
+            let tx = tx_info.decode()?;
+            let params = serde_json::json!([block_hash, 1]);
+            let block: BtcBlockInfo = self.rpc_call("getblock", params).await?;
+            if block.height <= checkpoint_info.height {
+                return Err(Error::TxBeforeCheckpoint);
+            }
+
+            // Check that the transaction is buried deep enough
+            let tip_height = self.get_block_height().await?;
+            let current = (tip_height + 1).saturating_sub(block.height) as u32;
+            if current < confirmations {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current,
+                });
+            }
+
+            // Compute the merkle branch from the block's txids
+            let txids = block
+                .tx
+                .iter()
+                .map(|txid| txid.parse::<bitcoin::Txid>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    Error::Rpc(format!("Invalid txid in block: {}", e))
+                })?;
+            let tx_index = txids
+                .iter()
+                .position(|txid| *txid == l1_txid)
+                .ok_or(Error::TxNotFound)?;
+            let merkle_branch = proof::merkle_branch(&txids, tx_index)
+                .ok_or(Error::TxNotFound)?;
+
+            // Collect headers from the checkpoint to the confirmation depth
+            let last_height =
+                block.height + u64::from(confirmations.max(1)) - 1;
+            let mut headers = Vec::new();
+            for height in (checkpoint_info.height + 1)..=last_height {
+                headers.push(self.get_block_header_at(height).await?);
+            }
+
+            Ok(L1TxProof {
+                tx,
+                tx_index: tx_index as u32,
+                merkle_branch,
+                headers,
+                block_index: (block.height - checkpoint_info.height - 1) as u32,
+            })
+        })
+    }
+}
+
+/// Litecoin Core JSON-RPC client
+///
+/// Litecoin Core serves the same JSON-RPC API as Bitcoin Core, so requests
+/// are made with [`BtcClient`].
+pub struct LtcClient(BtcClient);
+
+impl LtcClient {
+    pub fn new(config: &ParentChainNodeConfig) -> Result<Self, Error> {
+        BtcClient::new(config).map(Self)
+    }
+}
+
+impl ParentChainClientTrait for LtcClient {
+    fn chain_type(&self) -> ParentChainType {
+        ParentChainType::Ltc
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        self.0.get_transaction(txid)
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        self.0.get_block_height()
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        self.0.verify_transaction(txid, min_confirmations)
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        self.0.get_checkpoint()
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        self.0.get_tx_proof(txid, checkpoint, confirmations)
+    }
+}
+
+/// Bitcoin Cash Node JSON-RPC client
+///
+/// Bitcoin Cash Node serves the same JSON-RPC API as Bitcoin Core, so
+/// requests are made with [`BtcClient`]. Addresses can be CashAddr or legacy
+/// base58 addresses.
+pub struct BchClient(BtcClient);
+
+impl BchClient {
+    pub fn new(config: &ParentChainNodeConfig) -> Result<Self, Error> {
+        BtcClient::new(config).map(Self)
+    }
+}
+
+impl ParentChainClientTrait for BchClient {
+    fn chain_type(&self) -> ParentChainType {
+        ParentChainType::Bch
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        self.0.get_transaction(txid)
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        self.0.get_block_height()
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        self.0.verify_transaction(txid, min_confirmations)
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        self.0.get_checkpoint()
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        self.0.get_tx_proof(txid, checkpoint, confirmations)
+    }
+}
+
+/// JSON-RPC 2.0 client, for parent chains outside the Bitcoin family
+struct JsonRpc2Client {
+    rpc_url: String,
+    http_client: Arc<reqwest::Client>,
+    auth: Option<ChainAuth>,
+}
+
+impl JsonRpc2Client {
+    fn new(rpc_url: String, auth: Option<ChainAuth>) -> Result<Self, Error> {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| {
+                Error::Network(format!("Failed to create HTTP client: {}", e))
+            })?;
+        Ok(Self {
+            rpc_url,
+            http_client: Arc::new(http_client),
+            auth,
+        })
+    }
+
+    /// Make a JSON-RPC 2.0 call
+    async fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, Error> {
+        let mut request = self.http_client.post(&self.rpc_url);
+        match &self.auth {
+            Some(ChainAuth::Basic { username, password }) => {
+                request = request.basic_auth(username, Some(password));
+            }
+            Some(ChainAuth::ApiKey(token) | ChainAuth::Token(token)) => {
+                request = request.bearer_auth(token);
+            }
+            None => (),
+        }
+        let jsonrpc_request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params
+        });
+        let response =
+            request.json(&jsonrpc_request).send().await.map_err(|e| {
+                Error::Network(format!("HTTP request failed: {}", e))
+            })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::Rpc(format!("HTTP error {}: {}", status, text)));
+        }
+        let json_response: serde_json::Value =
+            response.json().await.map_err(|e| {
+                Error::Network(format!("Failed to parse JSON response: {}", e))
+            })?;
+        if let Some(error) = json_response.get("error")
+            && !error.is_null()
+        {
+            let error_msg = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("Unknown RPC error");
+            return Err(Error::Rpc(error_msg.to_string()));
+        }
+        let result = json_response.get("result").ok_or_else(|| {
+            Error::Rpc("Missing 'result' field in RPC response".to_string())
+        })?;
+        serde_json::from_value(result.clone()).map_err(|e| {
+            Error::Rpc(format!("Failed to deserialize RPC result: {}", e))
+        })
+    }
+}
+
+/// Keccak-256 hash of `Transfer(address,address,uint256)`, the first topic
+/// of ERC-20 transfer logs
+const ERC20_TRANSFER_TOPIC: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// Wei per gwei, the unit of native ETH amounts
+const WEI_PER_GWEI: u128 = 1_000_000_000;
+
+/// Ethereum JSON-RPC client
+///
+/// Transactions are reported once they are mined. Payments are native ETH
+/// transfers, in gwei, and ERC-20 `Transfer` logs, in base units of the
+/// token.
+pub struct EthClient {
+    rpc: JsonRpc2Client,
+}
+
+impl EthClient {
+    pub fn new(config: &ParentChainNodeConfig) -> Result<Self, Error> {
+        let rpc = JsonRpc2Client::new(
+            config.node_url.to_string(),
+            config.auth.clone(),
+        )?;
+        Ok(Self { rpc })
+    }
+
+    /// Hash of the block at `height` in the canonical chain
+    async fn get_block_hash_at(&self, height: u64) -> Result<String, Error> {
+        let params = serde_json::json!([format!("{height:#x}"), false]);
+        let block: Option<EthBlockInfo> =
+            self.rpc.call("eth_getBlockByNumber", params).await?;
+        block
+            .map(|block| block.hash)
+            .ok_or_else(|| Error::Rpc(format!("Block {height} not found")))
+    }
+}
+
+impl ParentChainClientTrait for EthClient {
+    fn chain_type(&self) -> ParentChainType {
+        ParentChainType::Eth
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let tx_hash = match txid {
+                TxId::Hash32(hash) => hash.as_slice(),
+                TxId::Hash(hash) if hash.len() == 32 => hash.as_slice(),
+                TxId::Hash(_) => return Err(Error::InvalidTxId),
+            };
+            let params =
+                serde_json::json!([format!("0x{}", hex::encode(tx_hash))]);
+            let Some(receipt): Option<EthReceipt> = self
+                .rpc
+                .call("eth_getTransactionReceipt", params.clone())
+                .await?
+            else {
+                return Ok(None);
+            };
+            let block_height = eth_quantity(&receipt.block_number)?;
+            // Receipts of transactions in orphaned blocks can still be served
+            let canonical_hash = self.get_block_hash_at(block_height).await?;
+            if !canonical_hash.eq_ignore_ascii_case(&receipt.block_hash) {
+                return Ok(None);
+            }
+            let tip = self.get_block_height().await?;
+            let confirmations = tip.saturating_sub(block_height) + 1;
+            // Reverted transactions pay nothing
+            let mut outputs = Vec::new();
+            if receipt.status.as_deref() == Some("0x1") {
+                let tx: EthTxInfo = self
+                    .rpc
+                    .call::<Option<EthTxInfo>>(
+                        "eth_getTransactionByHash",
+                        params,
+                    )
+                    .await?
+                    .ok_or(Error::TxNotFound)?;
+                let value =
+                    hex_u128(&tx.value)?.map(|value| value / WEI_PER_GWEI);
+                if let Some(to) = tx.to
+                    && value != Some(0)
+                {
+                    outputs.push(ParentChainTxOutput {
+                        destination: eth_destination(&to)?,
+                        token: None,
+                        amount: value.and_then(amount_from_base_units),
+                    });
+                }
+                for log in receipt.logs {
+                    // ERC-721 transfers have a fourth topic, for the token ID
+                    if log.removed
+                        || log.topics.len() != 3
+                        || !log.topics[0]
+                            .eq_ignore_ascii_case(ERC20_TRANSFER_TOPIC)
+                    {
+                        continue;
+                    }
+                    let to = log.topics[2]
+                        .strip_prefix("0x")
+                        .and_then(|topic| topic.get(24..))
+                        .ok_or_else(|| {
+                            Error::Rpc(format!(
+                                "Invalid log topic: {}",
+                                log.topics[2]
+                            ))
+                        })?;
+                    // Transfers that do not fit in 64 bits are kept, so that
+                    // they cannot be mistaken for smaller payments, but do
+                    // not affect other outputs
+                    let amount =
+                        hex_u128(&log.data)?.and_then(amount_from_base_units);
+                    outputs.push(ParentChainTxOutput {
+                        destination: eth_destination(&format!("0x{to}"))?,
+                        token: Some(address::account(
+                            &ParentChainType::Eth,
+                            &log.address,
+                        )?),
+                        amount,
+                    });
+                }
+            }
             Ok(Some(ParentChainTx {
                 txid: txid.clone(),
-                confirmations,
-                block_hash,
-                block_height,
+                confirmations: confirmations.try_into().unwrap_or(u32::MAX),
+                block_hash: Some(receipt.block_hash),
+                block_height: Some(block_height),
+                outputs,
             }))
-        } else {
-            Ok(None)
-        }
+        })
     }
 
-    async fn get_block_height(&self) -> Result<u64, Error> {
-        let params = serde_json::json!([]);
-        let height: u64 = self.rpc_call("getblockcount", params).await?;
-        Ok(height)
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let height: String = self
+                .rpc
+                .call("eth_blockNumber", serde_json::json!([]))
+                .await?;
+            eth_quantity(&height)
+        })
     }
 
-    async fn verify_transaction(
-        &self,
-        txid: &TxId,
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
         min_confirmations: u32,
-    ) -> Result<bool, Error> {
-        let tx = self.get_transaction(txid).await?;
-        
-        if let Some(tx) = tx {
-            Ok(tx.confirmations >= min_confirmations)
-        } else {
-            Ok(false)
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            Ok(tx.is_some_and(|tx| tx.confirmations >= min_confirmations))
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Eth))
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        _txid: &'a TxId,
+        _checkpoint: &'a bitcoin::block::Header,
+        _confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Eth))
+        })
+    }
+}
+
+/// Parse a hex-encoded Ethereum quantity
+fn eth_quantity(quantity: &str) -> Result<u64, Error> {
+    quantity
+        .strip_prefix("0x")
+        .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| Error::Rpc(format!("Invalid quantity: {quantity}")))
+}
+
+/// Parse a hex-encoded quantity or ABI-encoded `uint256`, with or without a
+/// `0x` prefix. Returns `None` for amounts that do not fit in a `u128`.
+fn hex_u128(value: &str) -> Result<Option<u128>, Error> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::Rpc(format!("Invalid amount: {value}")));
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > 32 {
+        return Ok(None);
+    }
+    if digits.is_empty() {
+        return Ok(Some(0));
+    }
+    u128::from_str_radix(digits, 16)
+        .map(Some)
+        .map_err(|_| Error::Rpc(format!("Invalid amount: {value}")))
+}
+
+/// Amount of an output in base units of its asset. Amounts are compared
+/// with swap amounts, so amounts that do not fit in a `u64` are `None`
+/// rather than clamped.
+fn amount_from_base_units(amount: u128) -> Option<bitcoin::Amount> {
+    u64::try_from(amount).ok().map(bitcoin::Amount::from_sat)
+}
+
+/// Destination of a payment to an Ethereum address reported by the node
+fn eth_destination(address: &str) -> Result<L1Destination, Error> {
+    Ok(L1Destination::Address(address::account(
+        &ParentChainType::Eth,
+        address,
+    )?))
+}
+
+/// Ethereum block info from eth_getBlockByNumber
+#[derive(Debug, serde::Deserialize)]
+struct EthBlockInfo {
+    hash: String,
+}
+
+/// Ethereum transaction info from eth_getTransactionByHash
+#[derive(Debug, serde::Deserialize)]
+struct EthTxInfo {
+    /// `None` for contract creations
+    #[serde(default)]
+    to: Option<String>,
+    value: String,
+}
+
+/// Ethereum transaction receipt from eth_getTransactionReceipt
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EthReceipt {
+    block_hash: String,
+    block_number: String,
+    /// `0x1` for success, or `0x0` for failure. Absent before Byzantium.
+    #[serde(default)]
+    status: Option<String>,
+    logs: Vec<EthLog>,
+}
+
+/// Ethereum log from a transaction receipt
+#[derive(Debug, serde::Deserialize)]
+struct EthLog {
+    address: String,
+    topics: Vec<String>,
+    data: String,
+    #[serde(default)]
+    removed: bool,
+}
+
+/// Monero wallet RPC client
+///
+/// Monero outputs are private, so payments can only be checked with a tx key
+/// or tx proof from the payer. Proofs are checked by `monero-wallet-rpc`,
+/// which must be connected to a trusted monerod. The wallet RPC server
+/// uses digest authentication by default, so it must run with
+/// `--disable-rpc-login` or behind a proxy. Amounts are in piconero.
+pub struct XmrClient {
+    rpc: JsonRpc2Client,
+}
+
+impl XmrClient {
+    pub fn new(config: &ParentChainNodeConfig) -> Result<Self, Error> {
+        let rpc_url = format!(
+            "{}/json_rpc",
+            config.node_url.as_str().trim_end_matches('/')
+        );
+        let rpc = JsonRpc2Client::new(rpc_url, config.auth.clone())?;
+        Ok(Self { rpc })
+    }
+}
+
+impl ParentChainClientTrait for XmrClient {
+    fn chain_type(&self) -> ParentChainType {
+        ParentChainType::Xmr
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        _txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            Err(Error::PaymentProofRequired(ParentChainType::Xmr))
+        })
+    }
+
+    /// `payment_proof` is either a tx key, from `get_tx_key`, or an
+    /// `OutProof` or `InProof` signature, from `get_tx_proof`
+    fn get_transaction_with_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        address: &'a str,
+        payment_proof: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let TxId::Hash32(tx_hash) = txid else {
+                return Err(Error::InvalidTxId);
+            };
+            let txid_hex = hex::encode(tx_hash);
+            let check: XmrPaymentCheck = if payment_proof
+                .starts_with("OutProof")
+                || payment_proof.starts_with("InProof")
+            {
+                let params = serde_json::json!({
+                    "txid": txid_hex,
+                    "address": address,
+                    "signature": payment_proof,
+                });
+                self.rpc.call("check_tx_proof", params).await?
+            } else {
+                let params = serde_json::json!({
+                    "txid": txid_hex,
+                    "tx_key": payment_proof,
+                    "address": address,
+                });
+                self.rpc.call("check_tx_key", params).await?
+            };
+            // An invalid proof shows no payment
+            let mut outputs = Vec::new();
+            if check.good != Some(false) && check.received != 0 {
+                outputs.push(ParentChainTxOutput {
+                    destination: L1Destination::Address(address.to_owned()),
+                    token: None,
+                    amount: Some(bitcoin::Amount::from_sat(check.received)),
+                });
+            }
+            let confirmations = if check.in_pool {
+                0
+            } else {
+                check.confirmations
+            };
+            Ok(Some(ParentChainTx {
+                txid: txid.clone(),
+                confirmations: confirmations.try_into().unwrap_or(u32::MAX),
+                block_hash: None,
+                block_height: None,
+                outputs,
+            }))
+        })
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let info: XmrHeightInfo =
+                self.rpc.call("get_height", serde_json::json!({})).await?;
+            Ok(info.height)
+        })
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        _txid: &'a TxId,
+        _min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            Err(Error::PaymentProofRequired(ParentChainType::Xmr))
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Xmr))
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        _txid: &'a TxId,
+        _checkpoint: &'a bitcoin::block::Header,
+        _confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Xmr))
+        })
+    }
+}
+
+/// Monero wallet height from get_height
+#[derive(Debug, serde::Deserialize)]
+struct XmrHeightInfo {
+    height: u64,
+}
+
+/// Result of check_tx_key or check_tx_proof
+#[derive(Debug, serde::Deserialize)]
+struct XmrPaymentCheck {
+    #[serde(default)]
+    confirmations: u64,
+    #[serde(default)]
+    in_pool: bool,
+    /// Amount received by the checked address, in piconero
+    received: u64,
+    /// Whether the tx proof is valid. Absent for tx keys, which fail the
+    /// call if invalid.
+    #[serde(default)]
+    good: Option<bool>,
+}
+
+/// Tron HTTP API client, for TronGrid or a java-tron full node
+///
+/// Transactions are reported once they are included in a block. Payments
+/// are TRX transfers, in sun, and TRC-20 `Transfer` logs, in base units of
+/// the token. With [`ChainAuth::ApiKey`], the key is sent in the
+/// `TRON-PRO-API-KEY` header that TronGrid expects.
+pub struct TronClient {
+    api_url: String,
+    http_client: Arc<reqwest::Client>,
+    auth: Option<ChainAuth>,
+}
+
+impl TronClient {
+    pub fn new(config: &ParentChainNodeConfig) -> Result<Self, Error> {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| {
+                Error::Network(format!("Failed to create HTTP client: {}", e))
+            })?;
+        Ok(Self {
+            api_url: config.node_url.as_str().trim_end_matches('/').to_owned(),
+            http_client: Arc::new(http_client),
+            auth: config.auth.clone(),
+        })
+    }
+
+    /// Call a `/wallet/` API endpoint
+    async fn api_call<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> Result<T, Error> {
+        let url = format!("{}/wallet/{endpoint}", self.api_url);
+        let mut request = self.http_client.post(url);
+        match &self.auth {
+            Some(ChainAuth::Basic { username, password }) => {
+                request = request.basic_auth(username, Some(password));
+            }
+            Some(ChainAuth::ApiKey(api_key)) => {
+                request = request.header("TRON-PRO-API-KEY", api_key);
+            }
+            Some(ChainAuth::Token(token)) => {
+                request = request.bearer_auth(token);
+            }
+            None => (),
+        }
+        let response = request.json(&body).send().await.map_err(|e| {
+            Error::Network(format!("HTTP request failed: {}", e))
+        })?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::Rpc(format!("HTTP error {}: {}", status, text)));
+        }
+        let json_response: serde_json::Value =
+            response.json().await.map_err(|e| {
+                Error::Network(format!("Failed to parse JSON response: {}", e))
+            })?;
+        // Errors are reported in the response body
+        if let Some(error) = json_response.get("Error") {
+            return Err(Error::Rpc(error.to_string()));
         }
+        serde_json::from_value(json_response).map_err(|e| {
+            Error::Rpc(format!("Failed to deserialize API response: {}", e))
+        })
+    }
+}
+
+impl ParentChainClientTrait for TronClient {
+    fn chain_type(&self) -> ParentChainType {
+        ParentChainType::Tron
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let tx_hash = match txid {
+                TxId::Hash32(hash) => hash.as_slice(),
+                TxId::Hash(hash) if hash.len() == 32 => hash.as_slice(),
+                TxId::Hash(_) => return Err(Error::InvalidTxId),
+            };
+            let body = serde_json::json!({ "value": hex::encode(tx_hash) });
+            // Unknown and unconfirmed transactions have no block number
+            let info: TronTxInfo = self
+                .api_call("gettransactioninfobyid", body.clone())
+                .await?;
+            let Some(block_height) = info.block_number else {
+                return Ok(None);
+            };
+            let tip = self.get_block_height().await?;
+            let confirmations = tip.saturating_sub(block_height) + 1;
+            let tx: TronTx = self.api_call("gettransactionbyid", body).await?;
+            // Failed transactions pay nothing
+            let mut outputs = Vec::new();
+            if tx.ret.first().and_then(|ret| ret.contract_ret.as_deref())
+                == Some("SUCCESS")
+            {
+                for contract in tx.raw_data.contract {
+                    if contract.contract_type != "TransferContract" {
+                        continue;
+                    }
+                    let transfer: TronTransfer =
+                        serde_json::from_value(contract.parameter.value)
+                            .map_err(|e| {
+                                Error::Rpc(format!(
+                                    "Invalid TRX transfer: {}",
+                                    e
+                                ))
+                            })?;
+                    outputs.push(ParentChainTxOutput {
+                        destination: tron_destination(&transfer.to_address)?,
+                        token: None,
+                        amount: Some(bitcoin::Amount::from_sat(
+                            transfer.amount,
+                        )),
+                    });
+                }
+                for log in info.log {
+                    let is_transfer = log.topics.len() == 3
+                        && log.topics[0].eq_ignore_ascii_case(
+                            ERC20_TRANSFER_TOPIC.trim_start_matches("0x"),
+                        );
+                    if !is_transfer {
+                        continue;
+                    }
+                    let to = log.topics[2].get(24..).ok_or_else(|| {
+                        Error::Rpc(format!(
+                            "Invalid log topic: {}",
+                            log.topics[2]
+                        ))
+                    })?;
+                    // As for ERC-20 transfers, transfers that do not fit in
+                    // 64 bits are kept without affecting other outputs
+                    let amount =
+                        hex_u128(&log.data)?.and_then(amount_from_base_units);
+                    outputs.push(ParentChainTxOutput {
+                        destination: tron_destination(&format!("41{to}"))?,
+                        token: Some(address::account(
+                            &ParentChainType::Tron,
+                            &format!("41{}", log.address),
+                        )?),
+                        amount,
+                    });
+                }
+            }
+            Ok(Some(ParentChainTx {
+                txid: txid.clone(),
+                confirmations: confirmations.try_into().unwrap_or(u32::MAX),
+                block_hash: None,
+                block_height: Some(block_height),
+                outputs,
+            }))
+        })
     }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let block: TronBlock =
+                self.api_call("getnowblock", serde_json::json!({})).await?;
+            Ok(block.block_header.raw_data.number)
+        })
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            Ok(tx.is_some_and(|tx| tx.confirmations >= min_confirmations))
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Tron))
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        _txid: &'a TxId,
+        _checkpoint: &'a bitcoin::block::Header,
+        _confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            Err(Error::ProofsNotSupported(ParentChainType::Tron))
+        })
+    }
+}
+
+/// Destination of a payment to a hex-encoded Tron address reported by the
+/// API
+fn tron_destination(address: &str) -> Result<L1Destination, Error> {
+    Ok(L1Destination::Address(address::account(
+        &ParentChainType::Tron,
+        address,
+    )?))
+}
+
+/// Tron block from getnowblock
+#[derive(Debug, serde::Deserialize)]
+struct TronBlock {
+    block_header: TronBlockHeader,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronBlockHeader {
+    raw_data: TronBlockRawData,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronBlockRawData {
+    number: u64,
+}
+
+/// Tron transaction info from gettransactioninfobyid. The API returns an
+/// empty object for unknown transactions.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TronTxInfo {
+    #[serde(default)]
+    block_number: Option<u64>,
+    #[serde(default)]
+    log: Vec<TronLog>,
+}
+
+/// Tron event log, with hex fields that have no `0x` prefix, and contract
+/// addresses without the `41` version byte
+#[derive(Debug, serde::Deserialize)]
+struct TronLog {
+    address: String,
+    #[serde(default)]
+    topics: Vec<String>,
+    #[serde(default)]
+    data: String,
+}
+
+/// Tron transaction from gettransactionbyid
+#[derive(Debug, serde::Deserialize)]
+struct TronTx {
+    #[serde(default)]
+    ret: Vec<TronTxResult>,
+    raw_data: TronTxRawData,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronTxResult {
+    #[serde(default, rename = "contractRet")]
+    contract_ret: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronTxRawData {
+    #[serde(default)]
+    contract: Vec<TronContract>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronContract {
+    #[serde(rename = "type")]
+    contract_type: String,
+    parameter: TronContractParameter,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TronContractParameter {
+    value: serde_json::Value,
+}
+
+/// Parameters of a `TransferContract`, which transfers TRX
+#[derive(Debug, serde::Deserialize)]
+struct TronTransfer {
+    /// Amount in sun
+    amount: u64,
+    /// Hex-encoded recipient address
+    to_address: String,
+}
+
+/// Bitcoin block info from getblock with verbosity 1
+#[derive(Debug, serde::Deserialize)]
+struct BtcBlockInfo {
+    height: u64,
+    tx: Vec<String>,
+}
+
+/// Bitcoin block header info from getblockheader with verbose=true
+#[derive(Debug, serde::Deserialize)]
+struct BtcHeaderInfo {
+    /// `-1` if the block is not in the active chain
+    confirmations: i64,
+    height: u64,
 }
 
 /// Bitcoin transaction info from getrawtransaction
 #[derive(Debug, serde::Deserialize)]
 struct BtcTxInfo {
+    hex: String,
     #[serde(default)]
     confirmations: Option<i64>,
     #[serde(default)]
@@ -242,18 +1450,54 @@ struct BtcTxInfo {
     blockheight: Option<u64>,
 }
 
+impl BtcTxInfo {
+    /// Decode the raw transaction
+    fn decode(&self) -> Result<bitcoin::Transaction, Error> {
+        let tx_bytes = hex::decode(&self.hex).map_err(|e| {
+            Error::Rpc(format!("Invalid transaction hex: {}", e))
+        })?;
+        bitcoin::consensus::deserialize(&tx_bytes)
+            .map_err(|e| Error::Rpc(format!("Invalid transaction: {}", e)))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("Invalid address: {0}")]
+    Address(#[from] address::Error),
+    #[error("{backend} backends do not support {parent_chain}")]
+    BackendNotSupported {
+        backend: &'static str,
+        parent_chain: ParentChainType,
+    },
     #[error("Chain not configured: {0}")]
     ChainNotConfigured(ParentChainType),
+    #[error("Swap checkpoint is not in the active chain")]
+    CheckpointNotInChain,
     #[error("RPC error: {0}")]
     Rpc(String),
     #[error("Network error: {0}")]
     Network(String),
     #[error("Invalid transaction ID")]
     InvalidTxId,
+    #[error("Invalid mock parent chain URL: {0}")]
+    InvalidMockUrl(String),
+    #[error("Invalid parent chain node URL: {0}")]
+    InvalidNodeUrl(String),
+    #[error("Quorum of {required} is not possible with {backends} backends")]
+    InvalidQuorum { required: usize, backends: usize },
+    #[error("Transaction has {current} confirmations, but {required} are required")]
+    NotEnoughConfirmations { required: u32, current: u32 },
+    #[error("Only {agreeing} backends agree, but a quorum of {required} is required")]
+    QuorumNotReached { agreeing: usize, required: usize },
     #[error("Transaction not found")]
     TxNotFound,
+    #[error("Transaction was confirmed before the swap checkpoint")]
+    TxBeforeCheckpoint,
+    #[error("L1 proofs are not supported on {0}")]
+    ProofsNotSupported(ParentChainType),
+    #[error("Payments on {0} can only be checked with a payment proof")]
+    PaymentProofRequired(ParentChainType),
 }
 
 #[cfg(test)]
@@ -274,12 +1518,698 @@ mod tests {
     //! ```
     //!
     //! Tests will be skipped if `BTC_RPC_URL` is not set.
+    //!
+    //! The LTC and BCH client tests run against a local mock JSON-RPC server.
 
     use super::*;
-    use crate::parent_chain::config::{ChainAuth, ParentChainNodeConfig};
+    use crate::parent_chain::config::{
+        ChainAuth, ParentChainNodeConfig, QuorumPolicy,
+    };
+    use bitcoin::hashes::Hash as _;
     use std::env;
     use url::Url;
 
+    /// HTTP request received by a mock server
+    struct MockRequest {
+        path: String,
+        /// Header values, by lowercase header name
+        headers: HashMap<String, String>,
+        body: serde_json::Value,
+    }
+
+    /// Serve JSON responses from `handler` on a local port, returning the
+    /// node config for a client of the mock server
+    async fn mock_http_server<F>(handler: F) -> ParentChainNodeConfig
+    where
+        F: Fn(&MockRequest) -> serde_json::Value + Send + Sync + 'static,
+    {
+        use tokio::io::{
+            AsyncBufReadExt as _, AsyncReadExt as _, AsyncWriteExt as _,
+            BufReader,
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let addr = listener.local_addr().unwrap();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    // Serve requests until the client closes the connection
+                    loop {
+                        let mut request_line = String::new();
+                        match stream.read_line(&mut request_line).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(_) => (),
+                        }
+                        let path = request_line
+                            .split_whitespace()
+                            .nth(1)
+                            .unwrap_or_default()
+                            .to_owned();
+                        let mut headers = HashMap::new();
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            match stream.read_line(&mut line).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(_) => (),
+                            }
+                            let header = line.trim_end();
+                            if header.is_empty() {
+                                break;
+                            }
+                            if let Some((name, value)) = header.split_once(':')
+                            {
+                                headers.insert(
+                                    name.to_ascii_lowercase(),
+                                    value.trim().to_owned(),
+                                );
+                            }
+                        }
+                        let content_length = headers
+                            .get("content-length")
+                            .map_or(0, |value| value.parse().unwrap());
+                        let mut body = vec![0; content_length];
+                        if stream.read_exact(&mut body).await.is_err() {
+                            return;
+                        }
+                        let request = MockRequest {
+                            path,
+                            headers,
+                            body: serde_json::from_slice(&body).unwrap(),
+                        };
+                        let body = handler(&request).to_string();
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\n\
+                             Content-Type: application/json\r\n\
+                             Content-Length: {}\r\n\r\n{body}",
+                            body.len()
+                        );
+                        if stream
+                            .get_mut()
+                            .write_all(response.as_bytes())
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        ParentChainNodeConfig {
+            node_url: Url::parse(&format!("http://{addr}")).unwrap(),
+            auth: None,
+            confirmation_count: None,
+            backends: Vec::new(),
+            quorum: QuorumPolicy::All,
+        }
+    }
+
+    /// Serve JSON-RPC responses from `handler` on a local port, returning
+    /// the node config for a client of the mock server
+    async fn mock_rpc_server<F>(handler: F) -> ParentChainNodeConfig
+    where
+        F: Fn(&str, &serde_json::Value) -> serde_json::Value
+            + Send
+            + Sync
+            + 'static,
+    {
+        mock_http_server(move |request| {
+            let result = handler(
+                request.body["method"].as_str().unwrap(),
+                &request.body["params"],
+            );
+            serde_json::json!({
+                "result": result,
+                "error": null,
+                "id": request.body["id"],
+            })
+        })
+        .await
+    }
+
+    /// Mock node that knows a single transaction paying `outputs`, at a tip
+    /// height of 100
+    async fn mock_node_with_tx(
+        outputs: Vec<bitcoin::TxOut>,
+    ) -> (ParentChainNodeConfig, TxId) {
+        let tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn::default()],
+            output: outputs,
+        };
+        let txid = tx.compute_txid();
+        let tx_hex = bitcoin::consensus::encode::serialize_hex(&tx);
+        let txid_hex = txid.to_string();
+        let config = mock_rpc_server(move |method, params| match method {
+            "getblockcount" => serde_json::json!(100),
+            "getrawtransaction" if params[0] == txid_hex.as_str() => {
+                serde_json::json!({
+                    "hex": tx_hex,
+                    "confirmations": 6,
+                    "blockhash": "00".repeat(32),
+                    "blockheight": 95,
+                })
+            }
+            _ => serde_json::Value::Null,
+        })
+        .await;
+        // Swaps store L1 txids in the byte order used by RPC
+        let mut txid_bytes = txid.to_byte_array();
+        txid_bytes.reverse();
+        (config, TxId::Hash32(txid_bytes))
+    }
+
+    #[tokio::test]
+    async fn test_ltc_client_mock_rpc() {
+        let hash = [7u8; 20];
+        let segwit = bitcoin::bech32::segwit::encode(
+            bitcoin::bech32::Hrp::parse("ltc").unwrap(),
+            bitcoin::bech32::Fe32::Q,
+            &hash,
+        )
+        .unwrap();
+        // 'M'-prefixed P2SH address
+        let mut payload = vec![0x32];
+        payload.extend_from_slice(&hash);
+        let p2sh = bitcoin::base58::encode_check(&payload);
+        assert!(p2sh.starts_with('M'));
+
+        let (config, txid) = mock_node_with_tx(vec![
+            bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(50_000),
+                script_pubkey: address::script_pubkey(
+                    &ParentChainType::Ltc,
+                    &segwit,
+                )
+                .unwrap(),
+            },
+            bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(20_000),
+                script_pubkey: address::script_pubkey(
+                    &ParentChainType::Ltc,
+                    &p2sh,
+                )
+                .unwrap(),
+            },
+        ])
+        .await;
+        let client = LtcClient::new(&config).unwrap();
+        assert_eq!(client.chain_type(), ParentChainType::Ltc);
+        assert_eq!(client.get_block_height().await.unwrap(), 100);
+        assert!(client.script_pubkey(&segwit).unwrap().is_p2wpkh());
+        assert!(client.script_pubkey(&p2sh).unwrap().is_p2sh());
+        assert!(
+            client
+                .script_pubkey("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+                .is_err()
+        );
+
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 6);
+        assert_eq!(tx.block_height, Some(95));
+        assert_eq!(tx.outputs.len(), 2);
+        let paid = |address: &str| {
+            tx.amount_paid_to(&ParentChainType::Ltc, None, address)
+        };
+        assert_eq!(paid(&segwit), Some(bitcoin::Amount::from_sat(50_000)));
+        assert_eq!(paid(&p2sh), Some(bitcoin::Amount::from_sat(20_000)));
+        assert!(client.verify_transaction(&txid, 6).await.unwrap());
+        assert!(!client.verify_transaction(&txid, 7).await.unwrap());
+
+        let unknown = TxId::Hash32([0; 32]);
+        assert!(client.get_transaction(&unknown).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bch_client_mock_rpc() {
+        let cashaddr = "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a";
+        let legacy = "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu";
+
+        let (config, txid) = mock_node_with_tx(vec![bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(75_000),
+            script_pubkey: address::script_pubkey(
+                &ParentChainType::Bch,
+                cashaddr,
+            )
+            .unwrap(),
+        }])
+        .await;
+        let client = BchClient::new(&config).unwrap();
+        assert_eq!(client.chain_type(), ParentChainType::Bch);
+        assert_eq!(client.get_block_height().await.unwrap(), 100);
+        assert_eq!(
+            client.script_pubkey(cashaddr).unwrap(),
+            client.script_pubkey(legacy).unwrap()
+        );
+
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 6);
+        // CashAddr and legacy forms of an address are paid the same amount
+        for address in [cashaddr, legacy] {
+            assert_eq!(
+                tx.amount_paid_to(&ParentChainType::Bch, None, address),
+                Some(bitcoin::Amount::from_sat(75_000))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_eth_client_mock_rpc() {
+        const ALICE: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+        let alice_topic = format!("0x{:0>64}", &ALICE[2..]);
+        let block_hash = format!("0x{}", "ab".repeat(32));
+        let orphaned_hash = format!("0x{}", "cd".repeat(32));
+        let tx_hash = format!("0x{}", "11".repeat(32));
+        let orphaned_tx_hash = format!("0x{}", "22".repeat(32));
+        let overflow_tx_hash = format!("0x{}", "44".repeat(32));
+        let wide_tx_hash = format!("0x{}", "55".repeat(32));
+        let receipt = {
+            let block_hash = block_hash.clone();
+            move |block_hash_override: Option<&str>, usdc_data: String| {
+                serde_json::json!({
+                    "blockHash": block_hash_override.unwrap_or(&block_hash),
+                    "blockNumber": "0x5f",
+                    "status": "0x1",
+                    "logs": [
+                        // USDC to Alice
+                        {
+                            "address": USDC.to_lowercase(),
+                            "topics": [
+                                ERC20_TRANSFER_TOPIC,
+                                format!("0x{}", "00".repeat(32)),
+                                alice_topic,
+                            ],
+                            "data": usdc_data,
+                        },
+                        // An NFT to Alice does not count as a payment
+                        {
+                            "address": USDC,
+                            "topics": [
+                                ERC20_TRANSFER_TOPIC,
+                                format!("0x{}", "00".repeat(32)),
+                                alice_topic,
+                                format!("0x{:064x}", 1),
+                            ],
+                            "data": "0x",
+                        },
+                    ],
+                })
+            }
+        };
+        let config = mock_rpc_server({
+            let tx_hash = tx_hash.clone();
+            let orphaned_tx_hash = orphaned_tx_hash.clone();
+            let overflow_tx_hash = overflow_tx_hash.clone();
+            let wide_tx_hash = wide_tx_hash.clone();
+            let usdc_data = |amount: u128| format!("0x{amount:064x}");
+            move |method, params| match method {
+                "eth_blockNumber" => serde_json::json!("0x64"),
+                "eth_getBlockByNumber" if params[0] == "0x5f" => {
+                    serde_json::json!({ "hash": block_hash.to_uppercase() })
+                }
+                "eth_getTransactionReceipt"
+                    if params[0] == tx_hash.as_str() =>
+                {
+                    receipt(None, usdc_data(50_000_000))
+                }
+                "eth_getTransactionReceipt"
+                    if params[0] == orphaned_tx_hash.as_str() =>
+                {
+                    receipt(Some(&orphaned_hash), usdc_data(50_000_000))
+                }
+                // 2^64 base units of USDC to Alice
+                "eth_getTransactionReceipt"
+                    if params[0] == overflow_tx_hash.as_str() =>
+                {
+                    receipt(None, usdc_data(1 << 64))
+                }
+                // A `uint256` of USDC to Alice that does not fit in 128 bits
+                "eth_getTransactionReceipt"
+                    if params[0] == wide_tx_hash.as_str() =>
+                {
+                    receipt(None, format!("0x{}", "ff".repeat(32)))
+                }
+                "eth_getTransactionByHash"
+                    if params[0] == tx_hash.as_str()
+                        || params[0] == overflow_tx_hash.as_str()
+                        || params[0] == wide_tx_hash.as_str() =>
+                {
+                    // 0.1 ETH to Alice
+                    serde_json::json!({
+                        "to": ALICE.to_lowercase(),
+                        "value": format!("{:#x}", 100_000_000_000_000_000u128),
+                    })
+                }
+                _ => serde_json::Value::Null,
+            }
+        })
+        .await;
+        let client = EthClient::new(&config).unwrap();
+        assert_eq!(client.chain_type(), ParentChainType::Eth);
+        assert_eq!(client.get_block_height().await.unwrap(), 100);
+        assert!(matches!(
+            client.get_checkpoint().await,
+            Err(Error::ProofsNotSupported(ParentChainType::Eth))
+        ));
+
+        let txid = |tx_hash: &str| {
+            TxId::Hash32(
+                hex::decode(&tx_hash[2..]).unwrap().try_into().unwrap(),
+            )
+        };
+        let tx = client
+            .get_transaction(&txid(&tx_hash))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.confirmations, 6);
+        assert_eq!(tx.block_height, Some(95));
+        let paid =
+            |token| tx.amount_paid_to(&ParentChainType::Eth, token, ALICE);
+        // Native amounts are in gwei
+        assert_eq!(paid(None), Some(bitcoin::Amount::from_sat(100_000_000)));
+        assert_eq!(
+            paid(Some(USDC)),
+            Some(bitcoin::Amount::from_sat(50_000_000))
+        );
+        assert_eq!(
+            paid(Some("0x0000000000000000000000000000000000000001")),
+            Some(bitcoin::Amount::ZERO)
+        );
+
+        // Transfers that do not fit in 64 bits are rejected, not clamped,
+        // without affecting the other outputs of their tx
+        for tx_hash in [&overflow_tx_hash, &wide_tx_hash] {
+            let tx = client
+                .get_transaction(&txid(tx_hash))
+                .await
+                .unwrap()
+                .unwrap();
+            let paid =
+                |token| tx.amount_paid_to(&ParentChainType::Eth, token, ALICE);
+            assert_eq!(
+                paid(None),
+                Some(bitcoin::Amount::from_sat(100_000_000))
+            );
+            assert_eq!(paid(Some(USDC)), None);
+        }
+
+        // Receipts from orphaned blocks are ignored
+        let orphaned = client.get_transaction(&txid(&orphaned_tx_hash)).await;
+        assert!(orphaned.unwrap().is_none());
+        let unknown = TxId::Hash32([0x33; 32]);
+        assert!(client.get_transaction(&unknown).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_xmr_client_mock_rpc() {
+        const ALICE: &str = "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A";
+        const TX_KEY: &str =
+            "1111111111111111111111111111111111111111111111111111111111111111";
+        let txid_hex = "22".repeat(32);
+        let config = mock_rpc_server(move |method, params| {
+            match method {
+                "get_height" => serde_json::json!({ "height": 3_000_000 }),
+                // 1 XMR to Alice
+                "check_tx_key"
+                    if params["txid"] == txid_hex.as_str()
+                        && params["tx_key"] == TX_KEY
+                        && params["address"] == ALICE =>
+                {
+                    serde_json::json!({
+                        "confirmations": 12,
+                        "in_pool": false,
+                        "received": 1_000_000_000_000u64,
+                    })
+                }
+                "check_tx_proof" => serde_json::json!({
+                    "confirmations": 12,
+                    "good": false,
+                    "in_pool": false,
+                    "received": 1_000_000_000_000u64,
+                }),
+                _ => serde_json::Value::Null,
+            }
+        })
+        .await;
+        let client = XmrClient::new(&config).unwrap();
+        assert_eq!(client.chain_type(), ParentChainType::Xmr);
+        assert_eq!(client.get_block_height().await.unwrap(), 3_000_000);
+
+        // Payments cannot be checked from a txid alone
+        let txid = TxId::Hash32([0x22; 32]);
+        assert!(matches!(
+            client.get_transaction(&txid).await,
+            Err(Error::PaymentProofRequired(ParentChainType::Xmr))
+        ));
+
+        let tx = client
+            .get_transaction_with_proof(&txid, ALICE, TX_KEY)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tx.confirmations, 12);
+        assert_eq!(
+            tx.amount_paid_to(&ParentChainType::Xmr, None, ALICE),
+            Some(bitcoin::Amount::from_sat(1_000_000_000_000))
+        );
+
+        // An invalid tx proof shows no payment
+        let tx = client
+            .get_transaction_with_proof(&txid, ALICE, "OutProofV2invalid")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(tx.outputs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tron_client_mock_api() {
+        const API_KEY: &str = "test-api-key";
+        let tron_address = |hash: [u8; 20]| {
+            let mut payload = vec![0x41];
+            payload.extend_from_slice(&hash);
+            (
+                bitcoin::base58::encode_check(&payload),
+                hex::encode(payload),
+            )
+        };
+        let (alice, alice_hex) = tron_address([1; 20]);
+        let (usdt, usdt_hex) = tron_address([2; 20]);
+        let txid = TxId::Hash32([0x44; 32]);
+        let txid_hex = "44".repeat(32);
+        let overflow_txid = TxId::Hash32([0x66; 32]);
+        let overflow_txid_hex = "66".repeat(32);
+        let mut config = mock_http_server(move |request| {
+            if request.headers.get("tron-pro-api-key").map(String::as_str)
+                != Some(API_KEY)
+            {
+                return serde_json::json!({ "Error": "missing API key" });
+            }
+            // The overflowing tx pays 2^64 base units of USDT instead
+            let overflow = request.body["value"] == overflow_txid_hex.as_str();
+            let known = overflow || request.body["value"] == txid_hex.as_str();
+            let usdt_amount: u128 = if overflow { 1 << 64 } else { 25_000_000 };
+            match request.path.as_str() {
+                "/wallet/getnowblock" => serde_json::json!({
+                    "block_header": { "raw_data": { "number": 1000 } },
+                }),
+                "/wallet/gettransactioninfobyid" if known => {
+                    // 25 USDT to Alice
+                    serde_json::json!({
+                        "blockNumber": 101,
+                        "log": [{
+                            "address": &usdt_hex[2..],
+                            "topics": [
+                                ERC20_TRANSFER_TOPIC.trim_start_matches("0x"),
+                                "00".repeat(32),
+                                format!("{:0>64}", &alice_hex[2..]),
+                            ],
+                            "data": format!("{:064x}", usdt_amount),
+                        }],
+                    })
+                }
+                "/wallet/gettransactionbyid" if known => {
+                    // 5 TRX to Alice
+                    serde_json::json!({
+                        "ret": [{ "contractRet": "SUCCESS" }],
+                        "raw_data": {
+                            "contract": [{
+                                "type": "TransferContract",
+                                "parameter": {
+                                    "value": {
+                                        "amount": 5_000_000,
+                                        "owner_address": "41".repeat(21),
+                                        "to_address": alice_hex,
+                                    },
+                                },
+                            }],
+                        },
+                    })
+                }
+                _ => serde_json::json!({}),
+            }
+        })
+        .await;
+        let client = TronClient::new(&config).unwrap();
+        assert!(client.get_block_height().await.is_err());
+        config.auth = Some(ChainAuth::ApiKey(API_KEY.to_owned()));
+        let client = TronClient::new(&config).unwrap();
+        assert_eq!(client.chain_type(), ParentChainType::Tron);
+        assert_eq!(client.get_block_height().await.unwrap(), 1000);
+
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 900);
+        assert_eq!(tx.block_height, Some(101));
+        let paid =
+            |token| tx.amount_paid_to(&ParentChainType::Tron, token, &alice);
+        // TRX amounts are in sun
+        assert_eq!(paid(None), Some(bitcoin::Amount::from_sat(5_000_000)));
+        assert_eq!(
+            paid(Some(&usdt)),
+            Some(bitcoin::Amount::from_sat(25_000_000))
+        );
+        assert!(client.verify_transaction(&txid, 900).await.unwrap());
+
+        // Transfers that do not fit in 64 bits are rejected, not clamped,
+        // without affecting the other outputs of their tx
+        let tx = client
+            .get_transaction(&overflow_txid)
+            .await
+            .unwrap()
+            .unwrap();
+        let paid =
+            |token| tx.amount_paid_to(&ParentChainType::Tron, token, &alice);
+        assert_eq!(paid(None), Some(bitcoin::Amount::from_sat(5_000_000)));
+        assert_eq!(paid(Some(&usdt)), None);
+
+        let unknown = TxId::Hash32([0x55; 32]);
+        assert!(client.get_transaction(&unknown).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parent_chain_client_registers_ltc_and_bch() {
+        let mut config = ParentChainConfig::new();
+        for chain in [ParentChainType::Ltc, ParentChainType::Bch] {
+            config.set_chain(
+                chain,
+                Url::parse("http://127.0.0.1:1").unwrap(),
+                None,
+                None,
+            );
+        }
+        let client = ParentChainClient::new(&config).unwrap();
+        assert!(client.get_client(&ParentChainType::Ltc).is_some());
+        assert!(client.get_client(&ParentChainType::Bch).is_some());
+        assert!(client.get_client(&ParentChainType::Btc).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parent_chain_client_verify_transaction() {
+        let (node_config, txid) = mock_node_with_tx(vec![bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(10_000),
+            script_pubkey: bitcoin::ScriptBuf::new(),
+        }])
+        .await;
+        let mut config = ParentChainConfig::new();
+        config.set_chain(
+            ParentChainType::Ltc,
+            node_config.node_url,
+            None,
+            None,
+        );
+        let client = ParentChainClient::new(&config).unwrap();
+        assert!(
+            client
+                .verify_transaction(&ParentChainType::Ltc, &txid, 6)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !client
+                .verify_transaction(&ParentChainType::Ltc, &txid, 7)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            client
+                .verify_transaction(&ParentChainType::Btc, &txid, 1)
+                .await,
+            Err(Error::ChainNotConfigured(ParentChainType::Btc))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_parent_chain_client_quorum() {
+        let lagging = mock::MockChain::shared(ParentChainType::Btc, "lagging");
+        let synced = mock::MockChain::shared(ParentChainType::Btc, "synced");
+        let mut txid = None;
+        for (chain, blocks) in [(&lagging, 1), (&synced, 3)] {
+            let mut chain = chain.lock().unwrap();
+            txid = Some(chain.broadcast(mock::MockTx::Payment {
+                txid: [0x17; 32],
+                outputs: Vec::new(),
+            }));
+            chain.mine(blocks);
+        }
+        let txid = txid.unwrap();
+
+        let mut config = ParentChainConfig::new();
+        config.add_node(
+            ParentChainType::Btc,
+            Url::parse("mock://lagging").unwrap(),
+            None,
+        );
+        config.add_node(
+            ParentChainType::Btc,
+            Url::parse("mock://synced").unwrap(),
+            None,
+        );
+        let client = ParentChainClient::new(&config).unwrap();
+        assert!(
+            !client
+                .verify_transaction(&ParentChainType::Btc, &txid, 3)
+                .await
+                .unwrap()
+        );
+        let disagreement = client
+            .l1_disagreement(&ParentChainType::Btc, &txid)
+            .unwrap();
+        assert_eq!(disagreement.quorum, QuorumPolicy::All);
+        assert_eq!(disagreement.reports.len(), 2);
+
+        // One of the two backends is enough to advance the swap
+        config
+            .set_quorum(ParentChainType::Btc, QuorumPolicy::Threshold(1))
+            .unwrap();
+        let client = ParentChainClient::new(&config).unwrap();
+        assert!(
+            client
+                .verify_transaction(&ParentChainType::Btc, &txid, 3)
+                .await
+                .unwrap()
+        );
+
+        config
+            .set_quorum(ParentChainType::Btc, QuorumPolicy::Threshold(3))
+            .unwrap();
+        assert!(matches!(
+            ParentChainClient::new(&config),
+            Err(Error::InvalidQuorum {
+                required: 3,
+                backends: 2
+            })
+        ));
+    }
+
     /// Helper to create a BTC client from environment variables
     /// Returns None if BTC_RPC_URL is not set
     fn create_test_btc_client() -> Option<BtcClient> {
@@ -299,6 +2229,8 @@ mod tests {
             node_url: url,
             auth,
             confirmation_count: None,
+            backends: Vec::new(),
+            quorum: QuorumPolicy::All,
         };
 
         BtcClient::new(&config).ok()
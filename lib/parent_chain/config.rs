@@ -1,12 +1,28 @@
 //! Configuration for parent chain connections
 
+use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use strum::{Display, EnumString};
+use strum::{Display, EnumIter, EnumString};
 use thiserror::Error;
 use url::Url;
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Display, EnumString)]
+#[derive(
+    BorshSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumIter,
+    EnumString,
+    ToSchema,
+)]
 #[strum(serialize_all = "UPPERCASE")]
 pub enum ParentChainType {
     Btc,
@@ -17,6 +33,61 @@ pub enum ParentChainType {
     Tron,
 }
 
+/// How many of the backends for a parent chain must agree on an
+/// observation before it is accepted
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuorumPolicy {
+    /// Every backend must agree
+    #[default]
+    All,
+    /// At least this many backends must agree
+    Threshold(usize),
+}
+
+impl QuorumPolicy {
+    /// Number of backends that must agree, out of `backends`
+    pub fn required(&self, backends: usize) -> usize {
+        match self {
+            Self::All => backends,
+            Self::Threshold(required) => *required,
+        }
+    }
+}
+
+impl std::fmt::Display for QuorumPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => write!(f, "all"),
+            Self::Threshold(required) => write!(f, "{required}"),
+        }
+    }
+}
+
+impl std::str::FromStr for QuorumPolicy {
+    type Err = Error;
+
+    /// Parse `all`, or the number of backends that must agree
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        match s.parse() {
+            Ok(required) if required > 0 => Ok(Self::Threshold(required)),
+            _ => Err(Error::InvalidQuorum(s.to_owned())),
+        }
+    }
+}
+
+/// Additional backend for a parent chain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParentChainBackend {
+    /// RPC/API endpoint URL for the parent chain node
+    pub node_url: Url,
+    /// Optional authentication credentials
+    pub auth: Option<ChainAuth>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ParentChainNodeConfig {
     /// RPC/API endpoint URL for the parent chain node
@@ -25,6 +96,34 @@ pub struct ParentChainNodeConfig {
     pub auth: Option<ChainAuth>,
     /// Custom confirmation count (overrides default)
     pub confirmation_count: Option<u32>,
+    /// Additional backends, which are queried together with `node_url`
+    #[serde(default)]
+    pub backends: Vec<ParentChainBackend>,
+    /// How many backends, including `node_url`, must agree on an
+    /// observation
+    #[serde(default)]
+    pub quorum: QuorumPolicy,
+}
+
+impl ParentChainNodeConfig {
+    /// Config for each backend of the chain, starting with `node_url`.
+    /// The returned configs have no additional backends.
+    pub fn backend_configs(&self) -> Vec<Self> {
+        let primary = ParentChainBackend {
+            node_url: self.node_url.clone(),
+            auth: self.auth.clone(),
+        };
+        std::iter::once(primary)
+            .chain(self.backends.iter().cloned())
+            .map(|backend| Self {
+                node_url: backend.node_url,
+                auth: backend.auth,
+                confirmation_count: self.confirmation_count,
+                backends: Vec::new(),
+                quorum: QuorumPolicy::All,
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -64,10 +163,44 @@ impl ParentChainConfig {
                 node_url,
                 auth,
                 confirmation_count,
+                backends: Vec::new(),
+                quorum: QuorumPolicy::default(),
             },
         );
     }
 
+    /// Add a backend for a parent chain. The first backend added for a
+    /// chain is its primary node.
+    pub fn add_node(
+        &mut self,
+        chain: ParentChainType,
+        node_url: Url,
+        auth: Option<ChainAuth>,
+    ) {
+        match self.chains.get_mut(&chain) {
+            Some(node_config) => {
+                node_config
+                    .backends
+                    .push(ParentChainBackend { node_url, auth });
+            }
+            None => self.set_chain(chain, node_url, auth, None),
+        }
+    }
+
+    /// Set the quorum policy for a configured parent chain
+    pub fn set_quorum(
+        &mut self,
+        chain: ParentChainType,
+        quorum: QuorumPolicy,
+    ) -> Result<(), Error> {
+        let node_config = self
+            .chains
+            .get_mut(&chain)
+            .ok_or(Error::ChainNotConfigured(chain))?;
+        node_config.quorum = quorum;
+        Ok(())
+    }
+
     /// Get configuration for a specific chain
     pub fn get_chain(&self, chain: &ParentChainType) -> Option<&ParentChainNodeConfig> {
         self.chains.get(chain)
@@ -83,6 +216,8 @@ impl ParentChainConfig {
 pub enum Error {
     #[error("Chain not configured: {0}")]
     ChainNotConfigured(ParentChainType),
+    #[error("Invalid quorum policy: {0}. Expected `all` or a number")]
+    InvalidQuorum(String),
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
     #[error("Missing required configuration for chain: {0}")]
@@ -0,0 +1,1118 @@
+//! Electrum protocol client, for BTC-family parent chains
+//!
+//! Electrum servers, such as ElectrumX, electrs and Fulcrum, index
+//! transactions by the scripts that they pay to. Electrum backends are
+//! selected with an `electrum://host:port` node URL. Connections are plain
+//! TCP; TLS connections are not supported, so remote servers should be
+//! reached through a tunnel.
+//!
+//! The client subscribes to new headers, and to the script hash of each
+//! payment that it looks up, so that script histories are only fetched again
+//! after the server notifies the client that they changed.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use bitcoin::hashes::{Hash as _, sha256};
+use futures::future::BoxFuture;
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    net::TcpStream,
+};
+
+use crate::parent_chain::{
+    client::{
+        CHECKPOINT_DEPTH, Error, L1Destination, ParentChainClientTrait,
+        ParentChainTx, ParentChainTxOutput, TxId,
+    },
+    config::{ParentChainNodeConfig, ParentChainType},
+    proof::{self, L1TxProof},
+};
+
+/// URL scheme that selects an Electrum backend
+pub const URL_SCHEME: &str = "electrum";
+
+/// Port of Electrum servers that accept plain TCP connections
+pub const DEFAULT_PORT: u16 = 50001;
+
+/// Protocol version that the client negotiates
+const PROTOCOL_VERSION: &str = "1.4";
+
+/// Timeout for connecting to the server, and for each request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of headers that servers return per
+/// `blockchain.block.headers` request
+const MAX_HEADERS_PER_REQUEST: u64 = 2016;
+
+/// Maximum depth below the tip at which a checkpoint is searched for
+const MAX_CHECKPOINT_DEPTH: u64 = 5 * MAX_HEADERS_PER_REQUEST;
+
+/// Electrum script hash of `script`: its SHA256 hash, in reversed byte
+/// order, as hex
+fn script_hash(script: &bitcoin::Script) -> String {
+    let mut hash = sha256::Hash::hash(script.as_bytes()).to_byte_array();
+    hash.reverse();
+    hex::encode(hash)
+}
+
+/// Display hex of a BTC-family txid
+fn txid_hex(txid: &TxId) -> Result<String, Error> {
+    match txid {
+        TxId::Hash32(hash) => Ok(hex::encode(hash)),
+        TxId::Hash(_) => Err(Error::InvalidTxId),
+    }
+}
+
+fn parse_header(header_hex: &str) -> Result<bitcoin::block::Header, Error> {
+    let header_bytes = hex::decode(header_hex)
+        .map_err(|e| Error::Rpc(format!("Invalid block header hex: {}", e)))?;
+    bitcoin::consensus::deserialize(&header_bytes)
+        .map_err(|e| Error::Rpc(format!("Invalid block header: {}", e)))
+}
+
+/// Entry in a script hash history
+#[derive(Clone, Debug, Deserialize)]
+struct HistoryItem {
+    tx_hash: String,
+    /// Height of the including block. `0` for transactions in the mempool,
+    /// and `-1` for mempool transactions with unconfirmed inputs.
+    height: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeaderNotification {
+    height: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeadersResponse {
+    count: u64,
+    hex: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MerkleResponse {
+    merkle: Vec<String>,
+    pos: u32,
+}
+
+/// Message from the server: either a response to a request, or a
+/// subscription notification
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+#[derive(Debug)]
+struct ScriptHashSubscription {
+    /// Status last reported by the server
+    status: Option<String>,
+    /// History as of `status`, if fetched
+    history: Option<Vec<HistoryItem>>,
+}
+
+/// State kept up to date by subscription notifications. Subscriptions are
+/// per connection, so this is reset on reconnect.
+#[derive(Debug, Default)]
+struct Subscriptions {
+    tip_height: Option<u64>,
+    script_hashes: HashMap<String, ScriptHashSubscription>,
+}
+
+impl Subscriptions {
+    fn handle_notification(&mut self, method: &str, params: Value) {
+        match method {
+            "blockchain.headers.subscribe" => {
+                if let Ok([header]) =
+                    serde_json::from_value::<[HeaderNotification; 1]>(params)
+                {
+                    self.tip_height = Some(header.height);
+                }
+            }
+            "blockchain.scripthash.subscribe" => {
+                let Ok((script_hash, status)) =
+                    serde_json::from_value::<(String, Option<String>)>(params)
+                else {
+                    return;
+                };
+                if let Some(subscription) =
+                    self.script_hashes.get_mut(&script_hash)
+                    && subscription.status != status
+                {
+                    subscription.status = status;
+                    subscription.history = None;
+                }
+            }
+            _ => {
+                tracing::debug!(%method, "Ignoring Electrum notification");
+            }
+        }
+    }
+}
+
+/// Connection to an Electrum server
+struct Connection {
+    stream: BufReader<TcpStream>,
+    next_id: u64,
+}
+
+impl Connection {
+    /// Send a request and wait for its response, handling any notifications
+    /// received in the meantime. Server errors are returned as
+    /// [`Error::Rpc`], and connection failures as [`Error::Network`].
+    async fn request(
+        &mut self,
+        subscriptions: &Mutex<Subscriptions>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let mut request_line = request.to_string();
+        request_line.push('\n');
+        let response = async {
+            self.stream
+                .get_mut()
+                .write_all(request_line.as_bytes())
+                .await
+                .map_err(|e| Error::Network(format!("Write failed: {}", e)))?;
+            loop {
+                let mut line = String::new();
+                let read =
+                    self.stream.read_line(&mut line).await.map_err(|e| {
+                        Error::Network(format!("Read failed: {}", e))
+                    })?;
+                if read == 0 {
+                    return Err(Error::Network(
+                        "Electrum server closed the connection".to_owned(),
+                    ));
+                }
+                let message: Message =
+                    serde_json::from_str(&line).map_err(|e| {
+                        Error::Network(format!("Invalid message: {}", e))
+                    })?;
+                if message.id == Some(id) {
+                    break Ok(message);
+                }
+                if message.id.is_none()
+                    && let Some(method) = message.method
+                {
+                    subscriptions
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .handle_notification(&method, message.params);
+                }
+            }
+        };
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, response)
+            .await
+            .map_err(|_| {
+            Error::Network(format!("Request timed out: {method}"))
+        })??;
+        match response.error {
+            Some(error) => Err(Error::Rpc(format!("{method}: {error}"))),
+            None => Ok(response.result),
+        }
+    }
+}
+
+/// Electrum protocol client
+pub struct ElectrumClient {
+    parent_chain: ParentChainType,
+    /// `host:port` of the server
+    server_addr: String,
+    /// Connection to the server, established on first use. Requests are
+    /// sent one at a time.
+    connection: tokio::sync::Mutex<Option<Connection>>,
+    subscriptions: Mutex<Subscriptions>,
+}
+
+impl ElectrumClient {
+    pub fn new(
+        parent_chain: ParentChainType,
+        config: &ParentChainNodeConfig,
+    ) -> Result<Self, Error> {
+        if !proof::is_supported(&parent_chain) {
+            return Err(Error::BackendNotSupported {
+                backend: "Electrum",
+                parent_chain,
+            });
+        }
+        let url = &config.node_url;
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| Error::InvalidNodeUrl(url.to_string()))?;
+        let port = url.port().unwrap_or(DEFAULT_PORT);
+        Ok(Self {
+            parent_chain,
+            server_addr: format!("{host}:{port}"),
+            connection: tokio::sync::Mutex::new(None),
+            subscriptions: Mutex::new(Subscriptions::default()),
+        })
+    }
+
+    fn subscriptions(&self) -> std::sync::MutexGuard<'_, Subscriptions> {
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Connect to the server, and subscribe to new headers
+    async fn connect(&self) -> Result<Connection, Error> {
+        let stream = tokio::time::timeout(
+            REQUEST_TIMEOUT,
+            TcpStream::connect(&self.server_addr),
+        )
+        .await
+        .map_err(|_| {
+            Error::Network(format!(
+                "Timed out connecting to {}",
+                self.server_addr
+            ))
+        })?
+        .map_err(|e| {
+            Error::Network(format!(
+                "Failed to connect to {}: {}",
+                self.server_addr, e
+            ))
+        })?;
+        let mut connection = Connection {
+            stream: BufReader::new(stream),
+            next_id: 0,
+        };
+        *self.subscriptions() = Subscriptions::default();
+        let _: Value = connection
+            .request(
+                &self.subscriptions,
+                "server.version",
+                serde_json::json!(["coinshift", PROTOCOL_VERSION]),
+            )
+            .await?;
+        let tip = connection
+            .request(
+                &self.subscriptions,
+                "blockchain.headers.subscribe",
+                serde_json::json!([]),
+            )
+            .await?;
+        let tip: HeaderNotification = serde_json::from_value(tip)
+            .map_err(|e| Error::Rpc(format!("Invalid header: {}", e)))?;
+        self.subscriptions().tip_height = Some(tip.height);
+        Ok(connection)
+    }
+
+    /// Send a request, connecting first if necessary. The connection is
+    /// dropped if it fails, so that the next request reconnects.
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, Error> {
+        let mut connection_slot = self.connection.lock().await;
+        let mut connection = match connection_slot.take() {
+            Some(connection) => connection,
+            None => self.connect().await?,
+        };
+        let res = connection
+            .request(&self.subscriptions, method, params)
+            .await;
+        if !matches!(res, Err(Error::Network(_))) {
+            *connection_slot = Some(connection);
+        }
+        serde_json::from_value(res?).map_err(|e| {
+            Error::Rpc(format!("Failed to deserialize {method} response: {e}"))
+        })
+    }
+
+    /// Handle any pending notifications, and return the tip height
+    async fn sync(&self) -> Result<u64, Error> {
+        let _: Value = self.call("server.ping", serde_json::json!([])).await?;
+        self.subscriptions().tip_height.ok_or_else(|| {
+            Error::Rpc("Electrum server did not send a tip".to_owned())
+        })
+    }
+
+    /// History of `script`. The script hash is subscribed to on first use,
+    /// and the history is cached until the server reports a new status.
+    async fn history(
+        &self,
+        script: &bitcoin::Script,
+    ) -> Result<Vec<HistoryItem>, Error> {
+        let script_hash = script_hash(script);
+        let cached = self
+            .subscriptions()
+            .script_hashes
+            .get(&script_hash)
+            .and_then(|subscription| subscription.history.clone());
+        if let Some(history) = cached {
+            return Ok(history);
+        }
+        if !self
+            .subscriptions()
+            .script_hashes
+            .contains_key(&script_hash)
+        {
+            let status: Option<String> = self
+                .call(
+                    "blockchain.scripthash.subscribe",
+                    serde_json::json!([script_hash]),
+                )
+                .await?;
+            self.subscriptions().script_hashes.insert(
+                script_hash.clone(),
+                ScriptHashSubscription {
+                    status,
+                    history: None,
+                },
+            );
+        }
+        let history: Vec<HistoryItem> = self
+            .call(
+                "blockchain.scripthash.get_history",
+                serde_json::json!([script_hash]),
+            )
+            .await?;
+        if let Some(subscription) =
+            self.subscriptions().script_hashes.get_mut(&script_hash)
+        {
+            subscription.history = Some(history.clone());
+        }
+        Ok(history)
+    }
+
+    /// Fetch a transaction, returning `None` if the server does not know it
+    async fn get_raw_transaction(
+        &self,
+        txid_hex: &str,
+    ) -> Result<Option<bitcoin::Transaction>, Error> {
+        let tx_hex: String = match self
+            .call("blockchain.transaction.get", serde_json::json!([txid_hex]))
+            .await
+        {
+            Ok(tx_hex) => tx_hex,
+            Err(Error::Rpc(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let tx_bytes = hex::decode(tx_hex).map_err(|e| {
+            Error::Rpc(format!("Invalid transaction hex: {}", e))
+        })?;
+        let tx: bitcoin::Transaction =
+            bitcoin::consensus::deserialize(&tx_bytes).map_err(|e| {
+                Error::Rpc(format!("Invalid transaction: {}", e))
+            })?;
+        if tx.compute_txid().to_string() != txid_hex {
+            return Err(Error::Rpc(
+                "Transaction does not match its txid".to_owned(),
+            ));
+        }
+        Ok(Some(tx))
+    }
+
+    /// Height of the block that includes `tx`, or `None` if it is
+    /// unconfirmed. Electrum servers index transactions by script, so the
+    /// height is looked up in the history of one of its outputs.
+    async fn tx_height(
+        &self,
+        tx: &bitcoin::Transaction,
+        txid_hex: &str,
+    ) -> Result<Option<u64>, Error> {
+        let script = tx
+            .output
+            .iter()
+            .map(|output| output.script_pubkey.as_script())
+            .find(|script| !script.is_op_return())
+            .ok_or_else(|| {
+                Error::Rpc("Transaction has no indexed outputs".to_owned())
+            })?;
+        let history = self.history(script).await?;
+        let height = history
+            .iter()
+            .find(|item| item.tx_hash == txid_hex)
+            .map_or(0, |item| item.height);
+        Ok(u64::try_from(height).ok().filter(|height| *height > 0))
+    }
+
+    /// Header of the block at `height` in the active chain
+    async fn get_block_header_at(
+        &self,
+        height: u64,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let header_hex: String = self
+            .call("blockchain.block.header", serde_json::json!([height]))
+            .await?;
+        parse_header(&header_hex)
+    }
+
+    /// Headers of up to `count` consecutive blocks, starting at `start`
+    async fn get_block_headers(
+        &self,
+        start: u64,
+        count: u64,
+    ) -> Result<Vec<bitcoin::block::Header>, Error> {
+        let response: HeadersResponse = self
+            .call(
+                "blockchain.block.headers",
+                serde_json::json!([start, count]),
+            )
+            .await?;
+        let headers_bytes = hex::decode(&response.hex).map_err(|e| {
+            Error::Rpc(format!("Invalid block headers hex: {}", e))
+        })?;
+        let headers: Vec<bitcoin::block::Header> = headers_bytes
+            .chunks(bitcoin::block::Header::SIZE)
+            .map(|header_bytes| {
+                bitcoin::consensus::deserialize(header_bytes).map_err(|e| {
+                    Error::Rpc(format!("Invalid block header: {}", e))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        if headers.len() as u64 != response.count {
+            return Err(Error::Rpc(format!(
+                "Expected {} headers, received {}",
+                response.count,
+                headers.len()
+            )));
+        }
+        Ok(headers)
+    }
+
+    /// Find `checkpoint` in the active chain, searching down from the tip.
+    /// Returns the checkpoint height, and the headers that extend it up to
+    /// and including `last_height`.
+    async fn headers_since_checkpoint(
+        &self,
+        checkpoint: &bitcoin::block::Header,
+        tip_height: u64,
+        last_height: u64,
+    ) -> Result<(u64, Vec<bitcoin::block::Header>), Error> {
+        let checkpoint_hash = checkpoint.block_hash();
+        let min_height = tip_height.saturating_sub(MAX_CHECKPOINT_DEPTH);
+        // Chunks of headers, from the tip down
+        let mut chunks = Vec::new();
+        let mut end = tip_height + 1;
+        let checkpoint_height = loop {
+            if end <= min_height {
+                return Err(Error::CheckpointNotInChain);
+            }
+            let start = end.saturating_sub(MAX_HEADERS_PER_REQUEST);
+            let mut chunk = self.get_block_headers(start, end - start).await?;
+            if let Some(index) = chunk
+                .iter()
+                .position(|header| header.block_hash() == checkpoint_hash)
+            {
+                chunks.push(chunk.split_off(index + 1));
+                break start + index as u64;
+            }
+            chunks.push(chunk);
+            end = start;
+        };
+        let headers: Vec<_> = chunks.into_iter().rev().flatten().collect();
+        let len = last_height.saturating_sub(checkpoint_height) as usize;
+        let headers = headers.into_iter().take(len).collect();
+        Ok((checkpoint_height, headers))
+    }
+}
+
+impl ParentChainClientTrait for ElectrumClient {
+    fn chain_type(&self) -> ParentChainType {
+        self.parent_chain
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let txid_hex = txid_hex(txid)?;
+            let tip_height = self.sync().await?;
+            let Some(tx) = self.get_raw_transaction(&txid_hex).await? else {
+                return Ok(None);
+            };
+            let block_height = self.tx_height(&tx, &txid_hex).await?;
+            let (confirmations, block_hash) = match block_height {
+                Some(block_height) => {
+                    let header = self.get_block_header_at(block_height).await?;
+                    let confirmations =
+                        (tip_height + 1).saturating_sub(block_height) as u32;
+                    (confirmations, Some(header.block_hash().to_string()))
+                }
+                None => (0, None),
+            };
+            let outputs = tx
+                .output
+                .into_iter()
+                .map(|output| ParentChainTxOutput {
+                    destination: L1Destination::Script(output.script_pubkey),
+                    token: None,
+                    amount: Some(output.value),
+                })
+                .collect();
+            Ok(Some(ParentChainTx {
+                txid: txid.clone(),
+                confirmations,
+                block_hash,
+                block_height,
+                outputs,
+            }))
+        })
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(self.sync())
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            Ok(tx.is_some_and(|tx| tx.confirmations >= min_confirmations))
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            let tip_height = self.sync().await?;
+            self.get_block_header_at(
+                tip_height.saturating_sub(CHECKPOINT_DEPTH),
+            )
+            .await
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            let txid_hex = txid_hex(txid)?;
+            let tip_height = self.sync().await?;
+            let tx = self
+                .get_raw_transaction(&txid_hex)
+                .await?
+                .ok_or(Error::TxNotFound)?;
+            let Some(block_height) = self.tx_height(&tx, &txid_hex).await?
+            else {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current: 0,
+                });
+            };
+            let current = (tip_height + 1).saturating_sub(block_height) as u32;
+            if current < confirmations {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current,
+                });
+            }
+
+            let last_height =
+                block_height + u64::from(confirmations.max(1)) - 1;
+            let (checkpoint_height, headers) = self
+                .headers_since_checkpoint(checkpoint, tip_height, last_height)
+                .await?;
+            if block_height <= checkpoint_height {
+                return Err(Error::TxBeforeCheckpoint);
+            }
+            // Headers are fetched in chunks, so check that they were not
+            // reorged while they were fetched
+            if headers.first().map(|header| header.prev_blockhash)
+                != Some(checkpoint.block_hash())
+                || headers
+                    .windows(2)
+                    .any(|pair| pair[1].prev_blockhash != pair[0].block_hash())
+            {
+                return Err(Error::Rpc(
+                    "Parent chain reorged while building the proof".to_owned(),
+                ));
+            }
+            let block_index = (block_height - checkpoint_height - 1) as u32;
+
+            let merkle: MerkleResponse = self
+                .call(
+                    "blockchain.transaction.get_merkle",
+                    serde_json::json!([txid_hex, block_height]),
+                )
+                .await?;
+            let merkle_branch: Vec<bitcoin::TxMerkleNode> = merkle
+                .merkle
+                .iter()
+                .map(|node| node.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e| {
+                    Error::Rpc(format!("Invalid merkle branch: {}", e))
+                })?;
+            let merkle_root = proof::merkle_root_from_branch(
+                tx.compute_txid(),
+                merkle.pos,
+                &merkle_branch,
+            )
+            .map_err(|e| Error::Rpc(format!("Invalid merkle branch: {}", e)))?;
+            let including_header = headers
+                .get(block_index as usize)
+                .ok_or(Error::CheckpointNotInChain)?;
+            if merkle_root != including_header.merkle_root {
+                return Err(Error::Rpc(
+                    "Merkle branch does not match the block header".to_owned(),
+                ));
+            }
+
+            Ok(L1TxProof {
+                tx,
+                tx_index: merkle.pos,
+                merkle_branch,
+                headers,
+                block_index,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use bitcoin::{Amount, ScriptBuf, Transaction, TxIn, TxOut};
+    use tokio::{net::TcpListener, sync::broadcast};
+    use url::Url;
+
+    use super::*;
+    use crate::parent_chain::{
+        MockChain, MockTx, address, config::QuorumPolicy,
+    };
+
+    const ALICE: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    /// Transactions known to a stub Electrum server, by txid
+    type StubTxs = HashMap<bitcoin::Txid, Transaction>;
+
+    fn payment_tx(script_pubkey: ScriptBuf, value: Amount) -> Transaction {
+        Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value,
+                script_pubkey,
+            }],
+        }
+    }
+
+    fn stub_history(
+        chain: &MockChain,
+        txs: &StubTxs,
+        script_hash: &str,
+    ) -> Vec<Value> {
+        txs.iter()
+            .filter(|(_, tx)| {
+                tx.output.iter().any(|output| {
+                    super::script_hash(&output.script_pubkey) == script_hash
+                })
+            })
+            .filter_map(|(txid, tx)| {
+                let tx_info = chain
+                    .get_transaction(&MockTx::Bitcoin(tx.clone()).txid())?;
+                Some(serde_json::json!({
+                    "tx_hash": txid,
+                    "height": tx_info.block_height.unwrap_or(0),
+                }))
+            })
+            .collect()
+    }
+
+    /// Script hash status, as defined by the Electrum protocol
+    fn stub_status(
+        chain: &MockChain,
+        txs: &StubTxs,
+        script_hash: &str,
+    ) -> Option<String> {
+        let history = stub_history(chain, txs, script_hash);
+        if history.is_empty() {
+            return None;
+        }
+        let preimage: String = history
+            .iter()
+            .map(|item| {
+                format!(
+                    "{}:{}:",
+                    item["tx_hash"].as_str().unwrap(),
+                    item["height"]
+                )
+            })
+            .collect();
+        Some(sha256::Hash::hash(preimage.as_bytes()).to_string())
+    }
+
+    fn stub_result(
+        chain: &MockChain,
+        txs: &StubTxs,
+        method: &str,
+        params: &Value,
+    ) -> Result<Value, String> {
+        let header_hex = |height: u64| {
+            chain
+                .header_at(height)
+                .map(|header| {
+                    bitcoin::consensus::encode::serialize_hex(&header)
+                })
+                .ok_or_else(|| format!("No block at height {height}"))
+        };
+        match method {
+            "server.version" => Ok(serde_json::json!(["stub", "1.4"])),
+            "server.ping" => Ok(Value::Null),
+            "blockchain.headers.subscribe" => {
+                let height = chain.tip_height();
+                Ok(serde_json::json!({
+                    "height": height,
+                    "hex": header_hex(height)?,
+                }))
+            }
+            "blockchain.block.header" => {
+                Ok(Value::String(header_hex(params[0].as_u64().unwrap())?))
+            }
+            "blockchain.block.headers" => {
+                let start = params[0].as_u64().unwrap();
+                let count = params[1].as_u64().unwrap();
+                let end = (start + count).min(chain.tip_height() + 1);
+                let hex: String =
+                    (start..end).map(header_hex).collect::<Result<_, _>>()?;
+                Ok(serde_json::json!({
+                    "count": end.saturating_sub(start),
+                    "hex": hex,
+                    "max": MAX_HEADERS_PER_REQUEST,
+                }))
+            }
+            "blockchain.scripthash.subscribe" => Ok(serde_json::json!(
+                stub_status(chain, txs, params[0].as_str().unwrap())
+            )),
+            "blockchain.scripthash.get_history" => Ok(Value::Array(
+                stub_history(chain, txs, params[0].as_str().unwrap()),
+            )),
+            "blockchain.transaction.get" => {
+                let txid: bitcoin::Txid =
+                    params[0].as_str().unwrap().parse().unwrap();
+                txs.get(&txid)
+                    .filter(|tx| {
+                        chain
+                            .get_transaction(
+                                &MockTx::Bitcoin((*tx).clone()).txid(),
+                            )
+                            .is_some()
+                    })
+                    .map(|tx| {
+                        Value::String(
+                            bitcoin::consensus::encode::serialize_hex(tx),
+                        )
+                    })
+                    .ok_or_else(|| "No such transaction".to_owned())
+            }
+            "blockchain.transaction.get_merkle" => {
+                let txid: bitcoin::Txid =
+                    params[0].as_str().unwrap().parse().unwrap();
+                let height = params[1].as_u64().unwrap();
+                let txids = chain
+                    .block_txids(height)
+                    .ok_or_else(|| format!("No block at height {height}"))?;
+                let pos = txids
+                    .iter()
+                    .position(|block_txid| *block_txid == txid)
+                    .ok_or_else(|| "Transaction not in block".to_owned())?;
+                let merkle: Vec<String> = proof::merkle_branch(&txids, pos)
+                    .unwrap()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                Ok(serde_json::json!({
+                    "block_height": height,
+                    "merkle": merkle,
+                    "pos": pos,
+                }))
+            }
+            _ => Err(format!("Unknown method: {method}")),
+        }
+    }
+
+    /// Stub Electrum server, serving a mock chain
+    struct StubServer {
+        config: ParentChainNodeConfig,
+        /// Notify subscribers that the mock chain changed
+        chain_updated: broadcast::Sender<()>,
+        /// Number of `blockchain.scripthash.get_history` requests served
+        history_requests: Arc<AtomicUsize>,
+    }
+
+    async fn handle_connection(
+        stream: TcpStream,
+        chain: Arc<Mutex<MockChain>>,
+        txs: Arc<StubTxs>,
+        mut chain_updated: broadcast::Receiver<()>,
+        history_requests: Arc<AtomicUsize>,
+    ) {
+        let mut stream = BufReader::new(stream);
+        let mut tip_height = None;
+        // Subscribed script hashes, with their last reported status
+        let mut script_hashes = HashMap::<String, Option<String>>::new();
+        // Reads are cancelled by notifications, and resume into the same
+        // buffer
+        let mut line = String::new();
+        loop {
+            let mut messages = Vec::new();
+            tokio::select! {
+                // Notifications are sent before any pending responses
+                biased;
+                updated = chain_updated.recv() => {
+                    if updated.is_err() {
+                        return;
+                    }
+                    let chain = chain.lock().unwrap();
+                    let height = chain.tip_height();
+                    if tip_height.is_some_and(|tip| tip != height) {
+                        tip_height = Some(height);
+                        messages.push(serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "method": "blockchain.headers.subscribe",
+                            "params": [{"height": height, "hex": ""}],
+                        }));
+                    }
+                    for (script_hash, status) in &mut script_hashes {
+                        let new_status =
+                            stub_status(&chain, &txs, script_hash);
+                        if *status != new_status {
+                            *status = new_status.clone();
+                            messages.push(serde_json::json!({
+                                "jsonrpc": "2.0",
+                                "method": "blockchain.scripthash.subscribe",
+                                "params": [script_hash, new_status],
+                            }));
+                        }
+                    }
+                }
+                read = stream.read_line(&mut line) => {
+                    if !matches!(read, Ok(1..)) {
+                        return;
+                    }
+                    let request: Value = serde_json::from_str(&line).unwrap();
+                    line.clear();
+                    let method = request["method"].as_str().unwrap();
+                    let params = &request["params"];
+                    let chain = chain.lock().unwrap();
+                    let result = stub_result(&chain, &txs, method, params);
+                    match method {
+                        "blockchain.headers.subscribe" => {
+                            tip_height = Some(chain.tip_height());
+                        }
+                        "blockchain.scripthash.subscribe" => {
+                            script_hashes.insert(
+                                params[0].as_str().unwrap().to_owned(),
+                                result.clone().ok().and_then(|status| {
+                                    status.as_str().map(ToOwned::to_owned)
+                                }),
+                            );
+                        }
+                        "blockchain.scripthash.get_history" => {
+                            history_requests.fetch_add(1, Ordering::SeqCst);
+                        }
+                        _ => (),
+                    }
+                    messages.push(match result {
+                        Ok(result) => serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "result": result,
+                        }),
+                        Err(message) => serde_json::json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": {"code": 1, "message": message},
+                        }),
+                    });
+                }
+            }
+            for message in messages {
+                let mut line = message.to_string();
+                line.push('\n');
+                if stream.get_mut().write_all(line.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Serve `chain` with the Electrum protocol on a local port
+    async fn stub_electrum_server(
+        chain: Arc<Mutex<MockChain>>,
+        txs: StubTxs,
+    ) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (chain_updated, _) = broadcast::channel(16);
+        let history_requests = Arc::new(AtomicUsize::new(0));
+        let txs = Arc::new(txs);
+        tokio::spawn({
+            let chain_updated = chain_updated.clone();
+            let history_requests = history_requests.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(handle_connection(
+                        stream,
+                        chain.clone(),
+                        txs.clone(),
+                        chain_updated.subscribe(),
+                        history_requests.clone(),
+                    ));
+                }
+            }
+        });
+        StubServer {
+            config: ParentChainNodeConfig {
+                node_url: Url::parse(&format!("electrum://{addr}")).unwrap(),
+                auth: None,
+                confirmation_count: None,
+                backends: Vec::new(),
+                quorum: QuorumPolicy::All,
+            },
+            chain_updated,
+            history_requests,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_electrum_client() {
+        let chain = MockChain::shared(ParentChainType::Btc, "electrum");
+        chain.lock().unwrap().mine(CHECKPOINT_DEPTH as u32);
+        let script =
+            address::script_pubkey(&ParentChainType::Btc, ALICE).unwrap();
+        let tx = payment_tx(script.clone(), Amount::from_sat(50_000));
+        let txs = HashMap::from([(tx.compute_txid(), tx.clone())]);
+        let server = stub_electrum_server(chain.clone(), txs).await;
+        let update_chain = |f: &dyn Fn(&mut MockChain)| {
+            f(&mut chain.lock().unwrap());
+            server.chain_updated.send(()).unwrap();
+        };
+        let client =
+            ElectrumClient::new(ParentChainType::Btc, &server.config).unwrap();
+
+        assert_eq!(client.get_block_height().await.unwrap(), CHECKPOINT_DEPTH);
+        let checkpoint = client.get_checkpoint().await.unwrap();
+        assert_eq!(checkpoint, chain.lock().unwrap().header_at(0).unwrap());
+
+        let txid = MockTx::Bitcoin(tx.clone()).txid();
+        assert!(client.get_transaction(&txid).await.unwrap().is_none());
+        update_chain(&|chain| {
+            let _: TxId = chain.broadcast(MockTx::Bitcoin(tx.clone()));
+        });
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 0);
+        assert_eq!(
+            tx_info.amount_paid_to(&ParentChainType::Btc, None, ALICE),
+            Some(Amount::from_sat(50_000))
+        );
+        assert_eq!(server.history_requests.load(Ordering::SeqCst), 1);
+
+        // Confirming the transaction changes the script hash status, so
+        // the history is fetched again
+        update_chain(&|chain| chain.mine(1));
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 1);
+        assert_eq!(tx_info.block_height, Some(CHECKPOINT_DEPTH + 1));
+        assert_eq!(server.history_requests.load(Ordering::SeqCst), 2);
+
+        // New blocks are only announced with header notifications, so the
+        // cached history is used
+        update_chain(&|chain| chain.mine(1));
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 2);
+        assert!(client.verify_transaction(&txid, 2).await.unwrap());
+        assert!(!client.verify_transaction(&txid, 3).await.unwrap());
+        assert_eq!(server.history_requests.load(Ordering::SeqCst), 2);
+
+        assert!(matches!(
+            client.get_tx_proof(&txid, &checkpoint, 3).await,
+            Err(Error::NotEnoughConfirmations {
+                required: 3,
+                current: 2
+            })
+        ));
+        let tx_proof =
+            client.get_tx_proof(&txid, &checkpoint, 2).await.unwrap();
+        tx_proof
+            .verify(&ParentChainType::Btc, &checkpoint, 2)
+            .unwrap();
+        tx_proof
+            .verify_payment(&script, Amount::from_sat(50_000))
+            .unwrap();
+
+        // A checkpoint that is not in the active chain is rejected
+        let mut orphan = checkpoint;
+        orphan.nonce ^= 1;
+        assert!(matches!(
+            client.get_tx_proof(&txid, &orphan, 2).await,
+            Err(Error::CheckpointNotInChain)
+        ));
+    }
+
+    #[test]
+    fn test_electrum_client_url() {
+        let config = |url: &str| ParentChainNodeConfig {
+            node_url: Url::parse(url).unwrap(),
+            auth: None,
+            confirmation_count: None,
+            backends: Vec::new(),
+            quorum: QuorumPolicy::All,
+        };
+        let client = ElectrumClient::new(
+            ParentChainType::Btc,
+            &config("electrum://localhost"),
+        )
+        .unwrap();
+        assert_eq!(client.server_addr, "localhost:50001");
+        assert!(matches!(
+            ElectrumClient::new(
+                ParentChainType::Xmr,
+                &config("electrum://localhost:50001")
+            ),
+            Err(Error::BackendNotSupported {
+                backend: "Electrum",
+                parent_chain: ParentChainType::Xmr
+            })
+        ));
+        assert!(matches!(
+            ElectrumClient::new(ParentChainType::Btc, &config("electrum:")),
+            Err(Error::InvalidNodeUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,620 @@
+//! Esplora REST API client, for BTC-family parent chains
+//!
+//! Esplora servers, such as blockstream.info and mempool.space, index
+//! transactions by txid, so that swap payments can be watched without
+//! running a full node. Esplora backends are selected with an Esplora node
+//! URL: the base URL of the API, with its scheme prefixed by `esplora+`,
+//! e.g. `esplora+https://blockstream.info/api`.
+
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use url::Url;
+
+use crate::parent_chain::{
+    client::{
+        CHECKPOINT_DEPTH, Error, L1Destination, ParentChainClientTrait,
+        ParentChainTx, ParentChainTxOutput, TxId,
+    },
+    config::{ChainAuth, ParentChainNodeConfig, ParentChainType},
+    proof::{self, L1TxProof},
+};
+
+/// Prefix of the URL schemes that select an Esplora backend
+pub const URL_SCHEME_PREFIX: &str = "esplora+";
+
+/// Whether `url` selects an Esplora backend
+pub fn is_esplora_url(url: &Url) -> bool {
+    url.scheme().starts_with(URL_SCHEME_PREFIX)
+}
+
+/// Transaction from `GET /tx/:txid`
+#[derive(Debug, Deserialize)]
+struct EsploraTx {
+    vout: Vec<EsploraTxOut>,
+    status: EsploraTxStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraTxOut {
+    #[serde(with = "hex::serde")]
+    scriptpubkey: Vec<u8>,
+    /// Amount in base units
+    value: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    #[serde(default)]
+    block_height: Option<u64>,
+    #[serde(default)]
+    block_hash: Option<String>,
+}
+
+/// Block status from `GET /block/:hash/status`
+#[derive(Debug, Deserialize)]
+struct EsploraBlockStatus {
+    in_best_chain: bool,
+    #[serde(default)]
+    height: Option<u64>,
+}
+
+/// Esplora REST API client
+pub struct EsploraClient {
+    parent_chain: ParentChainType,
+    /// Base URL of the API, without a trailing slash
+    api_url: String,
+    http_client: Arc<reqwest::Client>,
+    auth: Option<ChainAuth>,
+}
+
+impl EsploraClient {
+    pub fn new(
+        parent_chain: ParentChainType,
+        config: &ParentChainNodeConfig,
+    ) -> Result<Self, Error> {
+        if !proof::is_supported(&parent_chain) {
+            return Err(Error::BackendNotSupported {
+                backend: "Esplora",
+                parent_chain,
+            });
+        }
+        // `Url::set_scheme` cannot turn a non-special scheme into `http`, so
+        // the API URL is parsed again without the prefix
+        let api_url = config
+            .node_url
+            .as_str()
+            .strip_prefix(URL_SCHEME_PREFIX)
+            .and_then(|api_url| Url::parse(api_url).ok())
+            .filter(|api_url| matches!(api_url.scheme(), "http" | "https"))
+            .ok_or_else(|| Error::InvalidNodeUrl(config.node_url.to_string()))?
+            .as_str()
+            .trim_end_matches('/')
+            .to_owned();
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| {
+                Error::Network(format!("Failed to create HTTP client: {}", e))
+            })?;
+        Ok(Self {
+            parent_chain,
+            api_url,
+            http_client: Arc::new(http_client),
+            auth: config.auth.clone(),
+        })
+    }
+
+    /// `GET` an API path, returning `None` if the resource is not found
+    async fn get(&self, path: &str) -> Result<Option<String>, Error> {
+        let url = format!("{}/{path}", self.api_url);
+        let mut request = self.http_client.get(url);
+        match &self.auth {
+            Some(ChainAuth::Basic { username, password }) => {
+                request = request.basic_auth(username, Some(password));
+            }
+            Some(ChainAuth::ApiKey(token) | ChainAuth::Token(token)) => {
+                request = request.bearer_auth(token);
+            }
+            None => (),
+        }
+        let response = request.send().await.map_err(|e| {
+            Error::Network(format!("HTTP request failed: {}", e))
+        })?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(Error::Rpc(format!("HTTP error {}: {}", status, text)));
+        }
+        let text = response.text().await.map_err(|e| {
+            Error::Network(format!("Failed to read response: {}", e))
+        })?;
+        Ok(Some(text))
+    }
+
+    /// `GET` an API path that must exist
+    async fn get_required(&self, path: &str) -> Result<String, Error> {
+        self.get(path)
+            .await?
+            .ok_or_else(|| Error::Rpc(format!("Not found: {path}")))
+    }
+
+    /// `GET` a JSON API path, returning `None` if the resource is not found
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<Option<T>, Error> {
+        let Some(text) = self.get(path).await? else {
+            return Ok(None);
+        };
+        serde_json::from_str(&text).map(Some).map_err(|e| {
+            Error::Rpc(format!("Failed to deserialize API response: {}", e))
+        })
+    }
+
+    /// Header of the block at `height` in the active chain
+    async fn get_block_header_at(
+        &self,
+        height: u64,
+    ) -> Result<bitcoin::block::Header, Error> {
+        let block_hash =
+            self.get_required(&format!("block-height/{height}")).await?;
+        let header_hex = self
+            .get_required(&format!("block/{}/header", block_hash.trim()))
+            .await?;
+        let header_bytes = hex::decode(header_hex.trim()).map_err(|e| {
+            Error::Rpc(format!("Invalid block header hex: {}", e))
+        })?;
+        bitcoin::consensus::deserialize(&header_bytes)
+            .map_err(|e| Error::Rpc(format!("Invalid block header: {}", e)))
+    }
+}
+
+/// Display hex of a BTC-family txid
+fn txid_hex(txid: &TxId) -> Result<String, Error> {
+    match txid {
+        TxId::Hash32(hash) => Ok(hex::encode(hash)),
+        TxId::Hash(_) => Err(Error::InvalidTxId),
+    }
+}
+
+impl ParentChainClientTrait for EsploraClient {
+    fn chain_type(&self) -> ParentChainType {
+        self.parent_chain
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let txid_hex = txid_hex(txid)?;
+            let Some(tx) = self
+                .get_json::<EsploraTx>(&format!("tx/{txid_hex}"))
+                .await?
+            else {
+                return Ok(None);
+            };
+            let (confirmations, block_height) = match tx.status {
+                EsploraTxStatus {
+                    confirmed: true,
+                    block_height: Some(block_height),
+                    ..
+                } => {
+                    let tip_height = self.get_block_height().await?;
+                    let confirmations =
+                        (tip_height + 1).saturating_sub(block_height) as u32;
+                    (confirmations, Some(block_height))
+                }
+                _ => (0, None),
+            };
+            let outputs = tx
+                .vout
+                .into_iter()
+                .map(|output| ParentChainTxOutput {
+                    destination: L1Destination::Script(
+                        bitcoin::ScriptBuf::from_bytes(output.scriptpubkey),
+                    ),
+                    token: None,
+                    amount: Some(bitcoin::Amount::from_sat(output.value)),
+                })
+                .collect();
+            Ok(Some(ParentChainTx {
+                txid: txid.clone(),
+                confirmations,
+                block_hash: tx.status.block_hash,
+                block_height,
+                outputs,
+            }))
+        })
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let height = self.get_required("blocks/tip/height").await?;
+            height
+                .trim()
+                .parse()
+                .map_err(|e| Error::Rpc(format!("Invalid block height: {}", e)))
+        })
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            Ok(tx.is_some_and(|tx| tx.confirmations >= min_confirmations))
+        })
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            let tip_height = self.get_block_height().await?;
+            self.get_block_header_at(
+                tip_height.saturating_sub(CHECKPOINT_DEPTH),
+            )
+            .await
+        })
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            let txid_hex = txid_hex(txid)?;
+            let l1_txid: bitcoin::Txid =
+                txid_hex.parse().map_err(|_| Error::InvalidTxId)?;
+
+            // Find the checkpoint in the active chain
+            let checkpoint_status: Option<EsploraBlockStatus> = self
+                .get_json(&format!("block/{}/status", checkpoint.block_hash()))
+                .await?;
+            let checkpoint_height = match checkpoint_status {
+                Some(EsploraBlockStatus {
+                    in_best_chain: true,
+                    height: Some(height),
+                }) => height,
+                _ => return Err(Error::CheckpointNotInChain),
+            };
+
+            // Find the block that includes the transaction
+            let tx: EsploraTx = self
+                .get_json(&format!("tx/{txid_hex}"))
+                .await?
+                .ok_or(Error::TxNotFound)?;
+            let (Some(block_height), Some(block_hash)) =
+                (tx.status.block_height, tx.status.block_hash)
+            else {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current: 0,
+                });
+            };
+            if block_height <= checkpoint_height {
+                return Err(Error::TxBeforeCheckpoint);
+            }
+
+            // Check that the transaction is buried deep enough
+            let tip_height = self.get_block_height().await?;
+            let current = (tip_height + 1).saturating_sub(block_height) as u32;
+            if current < confirmations {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current,
+                });
+            }
+
+            let tx_hex =
+                self.get_required(&format!("tx/{txid_hex}/hex")).await?;
+            let tx_bytes = hex::decode(tx_hex.trim()).map_err(|e| {
+                Error::Rpc(format!("Invalid transaction hex: {}", e))
+            })?;
+            let tx: bitcoin::Transaction =
+                bitcoin::consensus::deserialize(&tx_bytes).map_err(|e| {
+                    Error::Rpc(format!("Invalid transaction: {}", e))
+                })?;
+            if tx.compute_txid() != l1_txid {
+                return Err(Error::Rpc(
+                    "Transaction does not match its txid".to_owned(),
+                ));
+            }
+
+            // Compute the merkle branch from the block's txids
+            let txids: Vec<bitcoin::Txid> = self
+                .get_json(&format!("block/{block_hash}/txids"))
+                .await?
+                .ok_or_else(|| {
+                    Error::Rpc(format!("Block {block_hash} not found"))
+                })?;
+            let tx_index = txids
+                .iter()
+                .position(|txid| *txid == l1_txid)
+                .ok_or(Error::TxNotFound)?;
+            let merkle_branch = proof::merkle_branch(&txids, tx_index)
+                .ok_or(Error::TxNotFound)?;
+
+            // Collect headers from the checkpoint to the confirmation depth
+            let last_height =
+                block_height + u64::from(confirmations.max(1)) - 1;
+            let mut headers = Vec::new();
+            for height in (checkpoint_height + 1)..=last_height {
+                headers.push(self.get_block_header_at(height).await?);
+            }
+            // Headers are fetched by height, so check that they were not
+            // reorged while they were fetched
+            if headers.first().map(|header| header.prev_blockhash)
+                != Some(checkpoint.block_hash())
+                || headers
+                    .windows(2)
+                    .any(|pair| pair[1].prev_blockhash != pair[0].block_hash())
+            {
+                return Err(Error::Rpc(
+                    "Parent chain reorged while building the proof".to_owned(),
+                ));
+            }
+
+            Ok(L1TxProof {
+                tx,
+                tx_index: tx_index as u32,
+                merkle_branch,
+                headers,
+                block_index: (block_height - checkpoint_height - 1) as u32,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use bitcoin::{Amount, ScriptBuf, Transaction, TxIn, TxOut};
+    use tokio::{
+        io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::parent_chain::{
+        MockChain, MockTx, address, config::QuorumPolicy,
+    };
+
+    const ALICE: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn payment_tx(script_pubkey: ScriptBuf, value: Amount) -> Transaction {
+        Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value,
+                script_pubkey,
+            }],
+        }
+    }
+
+    /// Response of the stub Esplora server to `GET path`, or `None` for a
+    /// 404
+    fn esplora_response(
+        chain: &MockChain,
+        txs: &HashMap<bitcoin::Txid, Transaction>,
+        path: &str,
+    ) -> Option<String> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let height_of = |block_hash: &str| {
+            (0..=chain.tip_height()).find(|height| {
+                chain.header_at(*height).unwrap().block_hash().to_string()
+                    == block_hash
+            })
+        };
+        match segments.as_slice() {
+            ["blocks", "tip", "height"] => Some(chain.tip_height().to_string()),
+            ["block-height", height] => chain
+                .header_at(height.parse().ok()?)
+                .map(|header| header.block_hash().to_string()),
+            ["block", block_hash, "header"] => {
+                let header = chain.header_at(height_of(block_hash)?)?;
+                Some(bitcoin::consensus::encode::serialize_hex(&header))
+            }
+            ["block", block_hash, "status"] => {
+                let height = height_of(block_hash);
+                Some(
+                    serde_json::json!({
+                        "in_best_chain": height.is_some(),
+                        "height": height,
+                    })
+                    .to_string(),
+                )
+            }
+            ["block", block_hash, "txids"] => {
+                let txids = chain.block_txids(height_of(block_hash)?)?;
+                Some(serde_json::to_string(&txids).unwrap())
+            }
+            ["tx", txid] => {
+                let txid: bitcoin::Txid = txid.parse().ok()?;
+                let tx = txs.get(&txid)?;
+                let tx_info = chain
+                    .get_transaction(&MockTx::Bitcoin(tx.clone()).txid())?;
+                let vout: Vec<_> = tx
+                    .output
+                    .iter()
+                    .map(|output| {
+                        serde_json::json!({
+                            "scriptpubkey": hex::encode(
+                                output.script_pubkey.as_bytes()
+                            ),
+                            "value": output.value.to_sat(),
+                        })
+                    })
+                    .collect();
+                Some(
+                    serde_json::json!({
+                        "txid": txid,
+                        "vout": vout,
+                        "status": {
+                            "confirmed": tx_info.block_height.is_some(),
+                            "block_height": tx_info.block_height,
+                            "block_hash": tx_info.block_hash,
+                        },
+                    })
+                    .to_string(),
+                )
+            }
+            ["tx", txid, "hex"] => {
+                let tx = txs.get(&txid.parse().ok()?)?;
+                Some(bitcoin::consensus::encode::serialize_hex(tx))
+            }
+            _ => None,
+        }
+    }
+
+    /// Serve `chain` with the Esplora API under `/api` on a local port,
+    /// returning the node config for a client of the stub server
+    async fn stub_esplora_server(
+        chain: Arc<Mutex<MockChain>>,
+        txs: HashMap<bitcoin::Txid, Transaction>,
+    ) -> ParentChainNodeConfig {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let txs = Arc::new(txs);
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let chain = chain.clone();
+                let txs = txs.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let mut request_line = String::new();
+                    if stream.read_line(&mut request_line).await.is_err() {
+                        return;
+                    }
+                    // Skip the request headers
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        match stream.read_line(&mut line).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(_) if line.trim_end().is_empty() => break,
+                            Ok(_) => (),
+                        }
+                    }
+                    let path = request_line
+                        .split_whitespace()
+                        .nth(1)
+                        .and_then(|path| path.strip_prefix("/api/"))
+                        .unwrap_or_default();
+                    let body = {
+                        let chain = chain.lock().unwrap();
+                        esplora_response(&chain, &txs, path)
+                    };
+                    let (status, body) = match body {
+                        Some(body) => ("200 OK", body),
+                        None => ("404 Not Found", "Not found".to_owned()),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {status}\r\n\
+                         Content-Length: {}\r\n\
+                         Connection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _: Result<(), _> =
+                        stream.get_mut().write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        ParentChainNodeConfig {
+            node_url: Url::parse(&format!("esplora+http://{addr}/api/"))
+                .unwrap(),
+            auth: None,
+            confirmation_count: None,
+            backends: Vec::new(),
+            quorum: QuorumPolicy::All,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_esplora_client() {
+        let chain = MockChain::shared(ParentChainType::Btc, "esplora");
+        chain.lock().unwrap().mine(CHECKPOINT_DEPTH as u32);
+        let script =
+            address::script_pubkey(&ParentChainType::Btc, ALICE).unwrap();
+        let tx = payment_tx(script.clone(), Amount::from_sat(50_000));
+        let txs = HashMap::from([(tx.compute_txid(), tx.clone())]);
+        let config = stub_esplora_server(chain.clone(), txs).await;
+        let client = EsploraClient::new(ParentChainType::Btc, &config).unwrap();
+
+        assert_eq!(client.get_block_height().await.unwrap(), CHECKPOINT_DEPTH);
+        let checkpoint = client.get_checkpoint().await.unwrap();
+        assert_eq!(checkpoint, chain.lock().unwrap().header_at(0).unwrap());
+
+        let txid = chain.lock().unwrap().broadcast(MockTx::Bitcoin(tx));
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 0);
+        assert_eq!(
+            tx_info.amount_paid_to(&ParentChainType::Btc, None, ALICE),
+            Some(Amount::from_sat(50_000))
+        );
+
+        chain.lock().unwrap().mine(2);
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 2);
+        assert_eq!(tx_info.block_height, Some(CHECKPOINT_DEPTH + 1));
+        assert!(client.verify_transaction(&txid, 2).await.unwrap());
+        assert!(!client.verify_transaction(&txid, 3).await.unwrap());
+
+        assert!(matches!(
+            client.get_tx_proof(&txid, &checkpoint, 3).await,
+            Err(Error::NotEnoughConfirmations {
+                required: 3,
+                current: 2
+            })
+        ));
+        let tx_proof =
+            client.get_tx_proof(&txid, &checkpoint, 2).await.unwrap();
+        tx_proof
+            .verify(&ParentChainType::Btc, &checkpoint, 2)
+            .unwrap();
+        tx_proof
+            .verify_payment(&script, Amount::from_sat(50_000))
+            .unwrap();
+
+        let unknown = TxId::Hash32([0; 32]);
+        assert!(client.get_transaction(&unknown).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_esplora_client_unsupported_chain() {
+        let config = ParentChainNodeConfig {
+            node_url: Url::parse("esplora+https://example.com/api").unwrap(),
+            auth: None,
+            confirmation_count: None,
+            backends: Vec::new(),
+            quorum: QuorumPolicy::All,
+        };
+        assert!(matches!(
+            EsploraClient::new(ParentChainType::Eth, &config),
+            Err(Error::BackendNotSupported {
+                backend: "Esplora",
+                parent_chain: ParentChainType::Eth
+            })
+        ));
+    }
+}
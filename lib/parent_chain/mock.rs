@@ -0,0 +1,747 @@
+//! Mock parent chain, for tests and demos
+//!
+//! [`MockParentChainClient`] serves a scripted [`MockChain`] instead of
+//! querying a parent chain node, so that swaps can be run end-to-end without
+//! one. Tests script the chain by broadcasting transactions, mining blocks,
+//! reorging blocks out, and taking the chain offline.
+//!
+//! Mock chains are selected with a `mock://` node URL:
+//! - `mock://NAME` serves the chain named `NAME` in this process, which can
+//!   be scripted with [`MockChain::shared`]
+//! - `mock:///PATH/TO/CHAIN.json` serves the chain stored as JSON at
+//!   `/PATH/TO/CHAIN.json`, which can be scripted from another process with
+//!   [`MockChain::update_file`]
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+use bitcoin::{
+    BlockHash, CompactTarget, Transaction, TxIn, Txid,
+    absolute::LockTime,
+    block::{Header as BlockHeader, Version},
+    hashes::Hash as _,
+    transaction,
+};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::parent_chain::{
+    address,
+    client::{
+        CHECKPOINT_DEPTH, Error, L1Destination, ParentChainClientTrait,
+        ParentChainTx, ParentChainTxOutput, TxId,
+    },
+    config::ParentChainType,
+    proof::{self, L1TxProof},
+};
+
+/// URL scheme that selects a mock parent chain
+pub const URL_SCHEME: &str = "mock";
+
+/// Regtest proof-of-work limit, so that mock blocks are cheap to mine
+const MOCK_BITS: u32 = 0x207fffff;
+
+/// Timestamp of the mock genesis block
+const GENESIS_TIME: u32 = 1_231_006_505;
+
+/// Seconds between the timestamps of consecutive mock blocks
+const BLOCK_INTERVAL: u32 = 600;
+
+type SharedChains = HashMap<(ParentChainType, String), Arc<Mutex<MockChain>>>;
+
+/// Mock chains shared within this process, by parent chain and name
+static SHARED_CHAINS: LazyLock<Mutex<SharedChains>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Output of a [`MockTx::Payment`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MockPayment {
+    pub address: String,
+    /// Token contract that the output pays. `None` for the parent chain's
+    /// native asset.
+    pub token: Option<String>,
+    /// Amount in base units of the paid asset
+    pub amount: bitcoin::Amount,
+}
+
+/// Transaction on a mock parent chain
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum MockTx {
+    /// BTC-family transaction, which can be proven to swap claims
+    Bitcoin(Transaction),
+    /// Payment on a chain without BTC-family transactions
+    Payment {
+        txid: [u8; 32],
+        outputs: Vec<MockPayment>,
+    },
+}
+
+impl MockTx {
+    /// Txid, in the byte order used by swaps
+    pub fn txid(&self) -> TxId {
+        match self {
+            Self::Bitcoin(tx) => {
+                // Swaps store L1 txids in the byte order used by RPC and
+                // explorers
+                let mut bytes = tx.compute_txid().to_byte_array();
+                bytes.reverse();
+                TxId::Hash32(bytes)
+            }
+            Self::Payment { txid, .. } => TxId::Hash32(*txid),
+        }
+    }
+
+    /// Leaf that commits to the transaction in its block's merkle tree
+    fn merkle_leaf(&self) -> Txid {
+        match self {
+            Self::Bitcoin(tx) => tx.compute_txid(),
+            Self::Payment { txid, .. } => Txid::from_byte_array(*txid),
+        }
+    }
+
+    /// Decoded outputs. Payments to addresses or tokens that do not decode
+    /// on `parent_chain` are dropped.
+    fn outputs(
+        &self,
+        parent_chain: &ParentChainType,
+    ) -> Vec<ParentChainTxOutput> {
+        match self {
+            Self::Bitcoin(tx) => tx
+                .output
+                .iter()
+                .map(|output| ParentChainTxOutput {
+                    destination: L1Destination::Script(
+                        output.script_pubkey.clone(),
+                    ),
+                    token: None,
+                    amount: Some(output.value),
+                })
+                .collect(),
+            Self::Payment { outputs, .. } => outputs
+                .iter()
+                .filter_map(|payment| {
+                    let destination =
+                        L1Destination::parse(parent_chain, &payment.address)?;
+                    let token = match &payment.token {
+                        Some(token) => {
+                            Some(address::account(parent_chain, token).ok()?)
+                        }
+                        None => None,
+                    };
+                    Some(ParentChainTxOutput {
+                        destination,
+                        token,
+                        amount: Some(payment.amount),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Block on a mock parent chain
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MockBlock {
+    pub header: BlockHeader,
+    /// Transactions in the block. The first transaction is a coinbase, so
+    /// that every mined block has a unique hash.
+    pub txs: Vec<MockTx>,
+}
+
+/// Scripted parent chain, served by [`MockParentChainClient`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MockChain {
+    parent_chain: ParentChainType,
+    /// Blocks in the active chain, starting at the genesis block
+    blocks: Vec<MockBlock>,
+    /// Unconfirmed transactions
+    mempool: Vec<MockTx>,
+    /// Number of blocks ever mined, including blocks that were reorged out.
+    /// Committed to by coinbases, so that replacement blocks have new
+    /// hashes.
+    blocks_mined: u32,
+    /// If set, requests to the chain fail as if the node were unreachable
+    offline: bool,
+}
+
+impl MockChain {
+    /// Mock chain with only a genesis block
+    pub fn new(parent_chain: ParentChainType) -> Self {
+        let mut chain = Self {
+            parent_chain,
+            blocks: Vec::new(),
+            mempool: Vec::new(),
+            blocks_mined: 0,
+            offline: false,
+        };
+        chain.mine_block(Vec::new());
+        chain
+    }
+
+    /// Mock chain named `name` that is shared within this process. The
+    /// chain is created if it does not exist yet.
+    pub fn shared(
+        parent_chain: ParentChainType,
+        name: &str,
+    ) -> Arc<Mutex<Self>> {
+        SHARED_CHAINS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((parent_chain, name.to_owned()))
+            .or_insert_with(|| Arc::new(Mutex::new(Self::new(parent_chain))))
+            .clone()
+    }
+
+    /// Load a mock chain stored as JSON at `path`, or a new chain if none is
+    /// stored there yet
+    pub fn load(
+        parent_chain: ParentChainType,
+        path: &Path,
+    ) -> io::Result<Self> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::new(parent_chain))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Store the chain as JSON at `path`. The file is replaced atomically,
+    /// so that clients never read a partially written chain.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let () = std::fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        std::fs::rename(tmp_path, path)
+    }
+
+    /// Update the mock chain stored at `path` with `f`, creating the chain
+    /// if none is stored there yet
+    pub fn update_file<F, T>(
+        parent_chain: ParentChainType,
+        path: &Path,
+        f: F,
+    ) -> io::Result<T>
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        let mut chain = Self::load(parent_chain, path)?;
+        let res = f(&mut chain);
+        let () = chain.save(path)?;
+        Ok(res)
+    }
+
+    pub fn parent_chain(&self) -> ParentChainType {
+        self.parent_chain
+    }
+
+    /// Height of the chain tip
+    pub fn tip_height(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    /// Header of the block at `height` in the active chain
+    pub fn header_at(&self, height: u64) -> Option<BlockHeader> {
+        self.blocks
+            .get(usize::try_from(height).ok()?)
+            .map(|block| block.header)
+    }
+
+    /// Add a transaction to the mempool. It is confirmed by the next mined
+    /// block.
+    pub fn broadcast(&mut self, tx: MockTx) -> TxId {
+        let txid = tx.txid();
+        self.mempool.push(tx);
+        txid
+    }
+
+    /// Drop a transaction from the mempool, as if it were double spent.
+    /// Returns `false` if the transaction is not in the mempool.
+    pub fn evict(&mut self, txid: &TxId) -> bool {
+        let len = self.mempool.len();
+        self.mempool.retain(|tx| tx.txid() != *txid);
+        self.mempool.len() != len
+    }
+
+    /// Mine `blocks` blocks. The first block confirms every transaction in
+    /// the mempool.
+    pub fn mine(&mut self, blocks: u32) {
+        for _ in 0..blocks {
+            let txs = std::mem::take(&mut self.mempool);
+            self.mine_block(txs);
+        }
+    }
+
+    /// Disconnect `depth` blocks from the tip of the chain. Their
+    /// transactions return to the mempool, ahead of the transactions that
+    /// were already there. The genesis block is never disconnected.
+    pub fn reorg(&mut self, depth: u32) {
+        let depth = (depth as usize).min(self.blocks.len() - 1);
+        let disconnected = self.blocks.split_off(self.blocks.len() - depth);
+        let mut mempool: Vec<MockTx> = disconnected
+            .into_iter()
+            .flat_map(|block| block.txs.into_iter().skip(1))
+            .collect();
+        mempool.append(&mut self.mempool);
+        self.mempool = mempool;
+    }
+
+    /// Take the chain offline, or bring it back online. Requests to an
+    /// offline chain fail with [`Error::Network`].
+    pub fn set_offline(&mut self, offline: bool) {
+        self.offline = offline;
+    }
+
+    fn mine_block(&mut self, txs: Vec<MockTx>) {
+        let height = self.blocks.len() as u32;
+        let coinbase = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::from_consensus(self.blocks_mined),
+            input: vec![TxIn::default()],
+            output: Vec::new(),
+        };
+        let txs: Vec<MockTx> = std::iter::once(MockTx::Bitcoin(coinbase))
+            .chain(txs)
+            .collect();
+        let leaves: Vec<Txid> = txs.iter().map(MockTx::merkle_leaf).collect();
+        let merkle_branch = proof::merkle_branch(&leaves, 0)
+            .expect("mock block should include a coinbase");
+        let merkle_root =
+            proof::merkle_root_from_branch(leaves[0], 0, &merkle_branch)
+                .expect("mock block merkle branch should be valid");
+        let mut header = BlockHeader {
+            version: Version::from_consensus(0x2000_0000),
+            prev_blockhash: self
+                .blocks
+                .last()
+                .map_or(BlockHash::all_zeros(), |block| {
+                    block.header.block_hash()
+                }),
+            merkle_root,
+            time: GENESIS_TIME + height * BLOCK_INTERVAL,
+            bits: CompactTarget::from_consensus(MOCK_BITS),
+            nonce: 0,
+        };
+        // Headers on chains without BTC-family proof-of-work are never
+        // checked
+        while proof::validate_header_pow(&self.parent_chain, &header)
+            .is_err_and(|err| !matches!(err, proof::Error::UnsupportedChain(_)))
+        {
+            header.nonce += 1;
+        }
+        self.blocks.push(MockBlock { header, txs });
+        self.blocks_mined += 1;
+    }
+
+    /// Height of the block with hash `block_hash`, if it is in the active
+    /// chain
+    fn height_of(&self, block_hash: &BlockHash) -> Option<u64> {
+        self.blocks
+            .iter()
+            .position(|block| block.header.block_hash() == *block_hash)
+            .map(|height| height as u64)
+    }
+
+    /// Height of the block that confirms `txid` in the active chain, and
+    /// the index of the transaction in the block
+    fn find_confirmed(&self, txid: &TxId) -> Option<(u64, usize)> {
+        self.blocks.iter().enumerate().find_map(|(height, block)| {
+            let index = block.txs.iter().position(|tx| tx.txid() == *txid)?;
+            Some((height as u64, index))
+        })
+    }
+
+    /// Txids of the transactions in the block at `height`, in block order
+    pub(crate) fn block_txids(&self, height: u64) -> Option<Vec<Txid>> {
+        let block = self.blocks.get(usize::try_from(height).ok()?)?;
+        Some(block.txs.iter().map(MockTx::merkle_leaf).collect())
+    }
+
+    pub(crate) fn get_transaction(
+        &self,
+        txid: &TxId,
+    ) -> Option<ParentChainTx> {
+        if let Some((height, index)) = self.find_confirmed(txid) {
+            let block = &self.blocks[height as usize];
+            return Some(ParentChainTx {
+                txid: txid.clone(),
+                confirmations: (self.tip_height() + 1 - height) as u32,
+                block_hash: Some(block.header.block_hash().to_string()),
+                block_height: Some(height),
+                outputs: block.txs[index].outputs(&self.parent_chain),
+            });
+        }
+        self.mempool.iter().find(|tx| tx.txid() == *txid).map(|tx| {
+            ParentChainTx {
+                txid: txid.clone(),
+                confirmations: 0,
+                block_hash: None,
+                block_height: None,
+                outputs: tx.outputs(&self.parent_chain),
+            }
+        })
+    }
+
+    fn get_checkpoint(&self) -> Result<BlockHeader, Error> {
+        if !proof::is_supported(&self.parent_chain) {
+            return Err(Error::ProofsNotSupported(self.parent_chain));
+        }
+        let height = self.tip_height().saturating_sub(CHECKPOINT_DEPTH);
+        Ok(self.blocks[height as usize].header)
+    }
+
+    fn get_tx_proof(
+        &self,
+        txid: &TxId,
+        checkpoint: &BlockHeader,
+        confirmations: u32,
+    ) -> Result<L1TxProof, Error> {
+        if !proof::is_supported(&self.parent_chain) {
+            return Err(Error::ProofsNotSupported(self.parent_chain));
+        }
+        let checkpoint_height = self
+            .height_of(&checkpoint.block_hash())
+            .ok_or(Error::CheckpointNotInChain)?;
+        let Some((height, tx_index)) = self.find_confirmed(txid) else {
+            if self.mempool.iter().any(|tx| tx.txid() == *txid) {
+                return Err(Error::NotEnoughConfirmations {
+                    required: confirmations,
+                    current: 0,
+                });
+            }
+            return Err(Error::TxNotFound);
+        };
+        let block = &self.blocks[height as usize];
+        let MockTx::Bitcoin(tx) = &block.txs[tx_index] else {
+            return Err(Error::ProofsNotSupported(self.parent_chain));
+        };
+        if height <= checkpoint_height {
+            return Err(Error::TxBeforeCheckpoint);
+        }
+        let current = (self.tip_height() + 1 - height) as u32;
+        if current < confirmations {
+            return Err(Error::NotEnoughConfirmations {
+                required: confirmations,
+                current,
+            });
+        }
+        let leaves: Vec<Txid> =
+            block.txs.iter().map(MockTx::merkle_leaf).collect();
+        let merkle_branch =
+            proof::merkle_branch(&leaves, tx_index).ok_or(Error::TxNotFound)?;
+        let last_height = height + u64::from(confirmations.max(1)) - 1;
+        let headers = self.blocks
+            [(checkpoint_height + 1) as usize..=last_height as usize]
+            .iter()
+            .map(|block| block.header)
+            .collect();
+        Ok(L1TxProof {
+            tx: tx.clone(),
+            tx_index: tx_index as u32,
+            merkle_branch,
+            headers,
+            block_index: (height - checkpoint_height - 1) as u32,
+        })
+    }
+}
+
+/// Where a [`MockParentChainClient`] reads its chain from
+#[derive(Debug)]
+enum MockChainSource {
+    Shared(Arc<Mutex<MockChain>>),
+    File(PathBuf),
+}
+
+/// Parent chain client that serves a [`MockChain`]
+#[derive(Debug)]
+pub struct MockParentChainClient {
+    parent_chain: ParentChainType,
+    source: MockChainSource,
+}
+
+impl MockParentChainClient {
+    /// Client for a mock chain shared within this process
+    pub fn new(
+        parent_chain: ParentChainType,
+        chain: Arc<Mutex<MockChain>>,
+    ) -> Self {
+        Self {
+            parent_chain,
+            source: MockChainSource::Shared(chain),
+        }
+    }
+
+    /// Client for the mock chain selected by a `mock://` URL
+    pub fn from_url(
+        parent_chain: ParentChainType,
+        url: &Url,
+    ) -> Result<Self, Error> {
+        let source = match url.host_str() {
+            Some(name) if !name.is_empty() => {
+                MockChainSource::Shared(MockChain::shared(parent_chain, name))
+            }
+            _ if url.path().starts_with('/') && url.path().len() > 1 => {
+                MockChainSource::File(PathBuf::from(url.path()))
+            }
+            _ => return Err(Error::InvalidMockUrl(url.to_string())),
+        };
+        Ok(Self {
+            parent_chain,
+            source,
+        })
+    }
+
+    /// Run `f` against the current state of the chain
+    fn with_chain<F, T>(&self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&MockChain) -> Result<T, Error>,
+    {
+        let check_online = |chain: &MockChain| {
+            if chain.offline {
+                Err(Error::Network("Mock parent chain is offline".to_owned()))
+            } else {
+                Ok(())
+            }
+        };
+        match &self.source {
+            MockChainSource::Shared(chain) => {
+                let chain =
+                    chain.lock().unwrap_or_else(PoisonError::into_inner);
+                let () = check_online(&chain)?;
+                f(&chain)
+            }
+            MockChainSource::File(path) => {
+                let chain =
+                    MockChain::load(self.parent_chain, path).map_err(|e| {
+                        Error::Rpc(format!("Failed to load mock chain: {}", e))
+                    })?;
+                let () = check_online(&chain)?;
+                f(&chain)
+            }
+        }
+    }
+}
+
+impl ParentChainClientTrait for MockParentChainClient {
+    fn chain_type(&self) -> ParentChainType {
+        self.parent_chain
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        let res = self.with_chain(|chain| Ok(chain.get_transaction(txid)));
+        Box::pin(std::future::ready(res))
+    }
+
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        let res = self.with_chain(|chain| Ok(chain.tip_height()));
+        Box::pin(std::future::ready(res))
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        let res = self.with_chain(|chain| {
+            Ok(chain
+                .get_transaction(txid)
+                .is_some_and(|tx| tx.confirmations >= min_confirmations))
+        });
+        Box::pin(std::future::ready(res))
+    }
+
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<BlockHeader, Error>> {
+        let res = self.with_chain(MockChain::get_checkpoint);
+        Box::pin(std::future::ready(res))
+    }
+
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a BlockHeader,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        let res = self.with_chain(|chain| {
+            chain.get_tx_proof(txid, checkpoint, confirmations)
+        });
+        Box::pin(std::future::ready(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Amount, ScriptBuf, TxOut};
+
+    use super::*;
+
+    fn payment_tx(script_pubkey: ScriptBuf, value: Amount) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value,
+                script_pubkey,
+            }],
+        }
+    }
+
+    fn test_script() -> ScriptBuf {
+        address::script_pubkey(
+            &ParentChainType::Btc,
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_confirmations() {
+        let chain = MockChain::shared(ParentChainType::Btc, "confirmations");
+        let client =
+            MockParentChainClient::new(ParentChainType::Btc, chain.clone());
+        let tx = payment_tx(test_script(), Amount::from_sat(50_000));
+        let txid = chain.lock().unwrap().broadcast(MockTx::Bitcoin(tx));
+
+        let mempool_tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(mempool_tx.confirmations, 0);
+        assert_eq!(mempool_tx.block_height, None);
+
+        chain.lock().unwrap().mine(3);
+        assert_eq!(client.get_block_height().await.unwrap(), 3);
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 3);
+        assert_eq!(tx_info.block_height, Some(1));
+        assert_eq!(
+            tx_info.amount_paid_to(
+                &ParentChainType::Btc,
+                None,
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            ),
+            Some(Amount::from_sat(50_000))
+        );
+        assert!(client.verify_transaction(&txid, 3).await.unwrap());
+        assert!(!client.verify_transaction(&txid, 4).await.unwrap());
+
+        let unknown = TxId::Hash32([0; 32]);
+        assert!(client.get_transaction(&unknown).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_tx_proof() {
+        let chain = MockChain::shared(ParentChainType::Btc, "tx_proof");
+        let client =
+            MockParentChainClient::new(ParentChainType::Btc, chain.clone());
+        chain.lock().unwrap().mine(CHECKPOINT_DEPTH as u32);
+        let checkpoint = client.get_checkpoint().await.unwrap();
+
+        let tx = payment_tx(test_script(), Amount::from_sat(50_000));
+        let txid = {
+            let mut chain = chain.lock().unwrap();
+            let txid = chain.broadcast(MockTx::Bitcoin(tx));
+            chain.mine(1);
+            txid
+        };
+        assert!(matches!(
+            client.get_tx_proof(&txid, &checkpoint, 2).await,
+            Err(Error::NotEnoughConfirmations {
+                required: 2,
+                current: 1
+            })
+        ));
+
+        chain.lock().unwrap().mine(1);
+        let tx_proof =
+            client.get_tx_proof(&txid, &checkpoint, 2).await.unwrap();
+        assert_eq!(tx_proof.l1_txid(), txid);
+        tx_proof
+            .verify(&ParentChainType::Btc, &checkpoint, 2)
+            .unwrap();
+        tx_proof
+            .verify_payment(&test_script(), Amount::from_sat(50_000))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_reorg() {
+        let chain = MockChain::shared(ParentChainType::Btc, "reorg");
+        let client =
+            MockParentChainClient::new(ParentChainType::Btc, chain.clone());
+        let tx = payment_tx(test_script(), Amount::from_sat(50_000));
+        let (txid, reorged_hash) = {
+            let mut chain = chain.lock().unwrap();
+            let txid = chain.broadcast(MockTx::Bitcoin(tx));
+            chain.mine(2);
+            (txid, chain.header_at(1).unwrap().block_hash())
+        };
+
+        // Reorged transactions return to the mempool
+        chain.lock().unwrap().reorg(2);
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 0);
+
+        // Replacement blocks have new hashes
+        chain.lock().unwrap().mine(1);
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 1);
+        assert_ne!(tx_info.block_hash, Some(reorged_hash.to_string()));
+
+        // Evicted transactions disappear
+        let mut chain = chain.lock().unwrap();
+        chain.reorg(1);
+        assert!(chain.evict(&txid));
+        assert!(chain.get_transaction(&txid).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_offline() {
+        let chain = MockChain::shared(ParentChainType::Btc, "offline");
+        let client =
+            MockParentChainClient::new(ParentChainType::Btc, chain.clone());
+        chain.lock().unwrap().set_offline(true);
+        assert!(matches!(
+            client.get_block_height().await,
+            Err(Error::Network(_))
+        ));
+        chain.lock().unwrap().set_offline(false);
+        assert_eq!(client.get_block_height().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_from_file_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ltc.json");
+        let url =
+            Url::parse(&format!("{URL_SCHEME}://{}", path.display())).unwrap();
+        let client =
+            MockParentChainClient::from_url(ParentChainType::Ltc, &url)
+                .unwrap();
+        assert_eq!(client.get_block_height().await.unwrap(), 0);
+
+        let tx = payment_tx(test_script(), Amount::from_sat(50_000));
+        let txid =
+            MockChain::update_file(ParentChainType::Ltc, &path, |chain| {
+                let txid = chain.broadcast(MockTx::Bitcoin(tx));
+                chain.mine(2);
+                txid
+            })
+            .unwrap();
+        let tx_info = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx_info.confirmations, 2);
+        // Litecoin headers are mined with scrypt
+        let header = MockChain::load(ParentChainType::Ltc, &path)
+            .unwrap()
+            .header_at(2)
+            .unwrap();
+        proof::validate_header_pow(&ParentChainType::Ltc, &header).unwrap();
+    }
+}
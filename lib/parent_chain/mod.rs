@@ -6,13 +6,35 @@
 use std::time::Duration;
 use thiserror::Error;
 
+pub mod address;
 pub mod config;
 pub mod client;
+pub mod electrum;
+pub mod esplora;
+pub mod mock;
+pub mod proof;
+pub mod quorum;
 pub mod swap;
+pub mod watcher;
 
-pub use config::{ParentChainConfig, ParentChainType, ChainAuth, ParentChainNodeConfig};
-pub use client::{ParentChainClient, ParentChainClientTrait, TxId, ParentChainTx};
-pub use swap::{Swap, SwapState, SwapError, SwapId, SwapManager};
+pub use config::{
+    ChainAuth, ParentChainConfig, ParentChainNodeConfig, ParentChainType,
+    QuorumPolicy,
+};
+pub use client::{
+    L1Destination, ParentChainClient, ParentChainClientTrait, ParentChainTx,
+    ParentChainTxOutput, TxId,
+};
+pub use electrum::ElectrumClient;
+pub use esplora::EsploraClient;
+pub use mock::{MockChain, MockParentChainClient, MockTx};
+pub use proof::L1TxProof;
+pub use quorum::{L1Disagreement, QuorumClient};
+pub use swap::{
+    SWAP_ID_VERSION, Swap, SwapAsset, SwapError, SwapEvent, SwapId,
+    SwapManager, SwapState, SwapStatus,
+};
+pub use watcher::{SwapWatch, SwapWatcher};
 
 /// Default confirmation time target: 45 minutes
 pub const DEFAULT_CONFIRMATION_TIME: Duration = Duration::from_secs(45 * 60);
@@ -56,5 +78,9 @@ pub enum Error {
     Swap(#[from] swap::SwapError),
     #[error("Configuration error: {0}")]
     Config(#[from] config::Error),
+    #[error("L1 proof error: {0}")]
+    Proof(#[from] proof::Error),
+    #[error("L1 address error: {0}")]
+    Address(#[from] address::Error),
 }
 
@@ -0,0 +1,743 @@
+//! L1 inclusion proofs for swap claims
+//!
+//! A proof carries an L1 transaction, a merkle branch committing it to a
+//! block header, and a run of consecutive L1 block headers that builds on
+//! the checkpoint committed to when the swap was created. Verification only
+//! uses data carried in the sidechain, so every sidechain node reaches the
+//! same result without querying a parent chain node.
+
+use bitcoin::{
+    Amount, Script, Target, Transaction, TxMerkleNode, Txid, Work,
+    block::Header as BlockHeader,
+    hashes::{Hash as _, sha256d},
+};
+use borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::{
+    parent_chain::{client::TxId, config::ParentChainType},
+    types::AmountOverflowError,
+};
+
+/// Maximum merkle branch length. No block can contain more than 2^32
+/// transactions.
+const MAX_MERKLE_BRANCH_LEN: usize = 32;
+
+/// Proof headers may have a target at most `2^MAX_TARGET_INCREASE_BITS`
+/// times the checkpoint target. This allows for one maximal Bitcoin
+/// difficulty adjustment downwards, while preventing proofs from being built
+/// on headers with trivial difficulty. Proofs must also carry as much work
+/// as their required confirmations at the checkpoint target, so a run of
+/// easier headers can not stand in for them.
+const MAX_TARGET_INCREASE_BITS: u32 = 2;
+
+/// Scrypt cost parameter used for Litecoin proof-of-work (N = 1024)
+const LTC_SCRYPT_LOG_N: u8 = 10;
+
+/// Transactions with a 64-byte serialization can be confused with inner
+/// merkle tree nodes, and cannot be proven safely.
+const AMBIGUOUS_TX_SIZE: usize = 64;
+
+fn borsh_serialize_bitcoin_tx<W>(
+    tx: &Transaction,
+    writer: &mut W,
+) -> borsh::io::Result<()>
+where
+    W: borsh::io::Write,
+{
+    let bytes = bitcoin::consensus::serialize(tx);
+    borsh::BorshSerialize::serialize(&bytes, writer)
+}
+
+fn borsh_serialize_merkle_branch<W>(
+    merkle_branch: &[TxMerkleNode],
+    writer: &mut W,
+) -> borsh::io::Result<()>
+where
+    W: borsh::io::Write,
+{
+    let nodes: Vec<&[u8; 32]> =
+        merkle_branch.iter().map(|node| node.as_ref()).collect();
+    borsh::BorshSerialize::serialize(&nodes, writer)
+}
+
+pub(crate) fn borsh_serialize_block_headers<W>(
+    headers: &[BlockHeader],
+    writer: &mut W,
+) -> borsh::io::Result<()>
+where
+    W: borsh::io::Write,
+{
+    let headers: Vec<Vec<u8>> =
+        headers.iter().map(bitcoin::consensus::serialize).collect();
+    borsh::BorshSerialize::serialize(&headers, writer)
+}
+
+pub(crate) fn borsh_serialize_optional_block_header<W>(
+    header: &Option<BlockHeader>,
+    writer: &mut W,
+) -> borsh::io::Result<()>
+where
+    W: borsh::io::Write,
+{
+    let header: Option<Vec<u8>> =
+        header.as_ref().map(bitcoin::consensus::serialize);
+    borsh::BorshSerialize::serialize(&header, writer)
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "transactions with a {AMBIGUOUS_TX_SIZE}-byte serialization cannot be proven"
+    )]
+    AmbiguousTxSize,
+    #[error(transparent)]
+    AmountOverflow(#[from] AmountOverflowError),
+    #[error(
+        "header {index} does not extend the previous header in the proof"
+    )]
+    BrokenHeaderChain { index: usize },
+    #[error("header {index} has a target too far above the checkpoint target")]
+    DifficultyTooLow { index: usize },
+    #[error(
+        "proof has {provided} confirmations, but {required} are required"
+    )]
+    InsufficientConfirmations { required: u32, provided: u32 },
+    #[error("L1 transaction pays {paid}, but {required} is required")]
+    InsufficientPayment { required: Amount, paid: Amount },
+    #[error(
+        "proof headers carry less work than {required_confirmations} headers at the checkpoint target"
+    )]
+    InsufficientWork { required_confirmations: u32 },
+    #[error("block index {block_index} is out of range for the proof headers")]
+    InvalidBlockIndex { block_index: u32 },
+    #[error("header {index} does not meet its proof-of-work target")]
+    InvalidPow { index: usize },
+    #[error(
+        "merkle branch does not commit to the including block's merkle root"
+    )]
+    MerkleRootMismatch,
+    #[error("merkle branch too long ({len} > {MAX_MERKLE_BRANCH_LEN})")]
+    MerkleBranchTooLong { len: usize },
+    #[error("tx index {tx_index} is out of range for the merkle branch")]
+    MerkleIndexOutOfRange { tx_index: u32 },
+    #[error("proof headers do not build on the swap checkpoint")]
+    NotOnCheckpoint,
+    #[error("L1 header proofs are not supported for {0}")]
+    UnsupportedChain(ParentChainType),
+}
+
+/// `true` if L1 transactions on the chain can be proven with an
+/// [`L1TxProof`]
+pub fn is_supported(parent_chain: &ParentChainType) -> bool {
+    match parent_chain {
+        ParentChainType::Btc | ParentChainType::Bch | ParentChainType::Ltc => {
+            true
+        }
+        ParentChainType::Xmr | ParentChainType::Eth | ParentChainType::Tron => {
+            false
+        }
+    }
+}
+
+/// Check that a header meets its own proof-of-work target, using the
+/// chain's proof-of-work hash function
+pub fn validate_header_pow(
+    parent_chain: &ParentChainType,
+    header: &BlockHeader,
+) -> Result<(), Error> {
+    let pow_valid = match parent_chain {
+        ParentChainType::Btc | ParentChainType::Bch => {
+            header.validate_pow(header.target()).is_ok()
+        }
+        ParentChainType::Ltc => {
+            let header_bytes = bitcoin::consensus::serialize(header);
+            let params = scrypt::Params::new(LTC_SCRYPT_LOG_N, 1, 1, 32)
+                .expect("Litecoin scrypt params should be valid");
+            let mut pow_hash = [0u8; 32];
+            let () = scrypt::scrypt(
+                &header_bytes,
+                &header_bytes,
+                &params,
+                &mut pow_hash,
+            )
+            .expect("32-byte scrypt output should be valid");
+            header
+                .target()
+                .is_met_by(bitcoin::BlockHash::from_byte_array(pow_hash))
+        }
+        ParentChainType::Xmr | ParentChainType::Eth | ParentChainType::Tron => {
+            return Err(Error::UnsupportedChain(*parent_chain));
+        }
+    };
+    if pow_valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidPow { index: 0 })
+    }
+}
+
+/// Maximum target allowed for headers that are checked against `target`,
+/// ie. `target * 2^MAX_TARGET_INCREASE_BITS`, saturating
+pub(crate) fn max_target(target: Target) -> Target {
+    let target_bytes = target.to_le_bytes();
+    let mut max_target_bytes = [0u8; 32];
+    let mut carry = 0u8;
+    for (max_byte, byte) in max_target_bytes.iter_mut().zip(target_bytes) {
+        *max_byte = (byte << MAX_TARGET_INCREASE_BITS) | carry;
+        carry = byte >> (8 - MAX_TARGET_INCREASE_BITS);
+    }
+    if carry != 0 {
+        return Target::from_le_bytes([0xff; 32]);
+    }
+    Target::from_le_bytes(max_target_bytes)
+}
+
+/// Proof that an L1 transaction is included in a block, buried under a chain
+/// of headers that builds on a swap's checkpoint.
+#[derive(
+    BorshSerialize, Clone, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema,
+)]
+pub struct L1TxProof {
+    /// The L1 transaction
+    #[borsh(serialize_with = "borsh_serialize_bitcoin_tx")]
+    #[schema(value_type = crate::types::schema::BitcoinTransaction)]
+    pub tx: Transaction,
+    /// Position of the transaction within its block
+    pub tx_index: u32,
+    /// Merkle branch from the txid to the merkle root of the including block
+    #[borsh(serialize_with = "borsh_serialize_merkle_branch")]
+    #[schema(value_type = Vec<String>)]
+    pub merkle_branch: Vec<TxMerkleNode>,
+    /// Consecutive L1 block headers. The first header extends the swap's
+    /// checkpoint, and each subsequent header extends the previous one.
+    #[borsh(serialize_with = "borsh_serialize_block_headers")]
+    #[schema(value_type = Vec<crate::types::schema::BitcoinBlockHeader>)]
+    pub headers: Vec<BlockHeader>,
+    /// Index in `headers` of the block that includes the transaction
+    pub block_index: u32,
+}
+
+impl L1TxProof {
+    /// Txid of the proven transaction
+    pub fn txid(&self) -> Txid {
+        self.tx.compute_txid()
+    }
+
+    /// Number of confirmations demonstrated by the proof
+    pub fn confirmations(&self) -> u32 {
+        (self.headers.len() as u32).saturating_sub(self.block_index)
+    }
+
+    /// The proven txid, as stored in swaps
+    pub fn l1_txid(&self) -> TxId {
+        // Swaps store L1 txids in the byte order used by RPC and explorers
+        let mut bytes = self.txid().to_byte_array();
+        bytes.reverse();
+        TxId::Hash32(bytes)
+    }
+
+    /// Header of the block that includes the transaction
+    pub fn including_header(&self) -> Result<&BlockHeader, Error> {
+        self.headers
+            .get(self.block_index as usize)
+            .ok_or(Error::InvalidBlockIndex {
+                block_index: self.block_index,
+            })
+    }
+
+    /// Drop the headers after the including block, for proofs that are
+    /// checked against the header relay rather than a checkpoint
+    pub fn including_block_only(mut self) -> Result<Self, Error> {
+        let including_header = *self.including_header()?;
+        self.headers = vec![including_header];
+        self.block_index = 0;
+        Ok(self)
+    }
+
+    /// Verify that the transaction is committed to by the including header.
+    /// The header itself is not checked.
+    pub fn verify_inclusion(
+        &self,
+        parent_chain: &ParentChainType,
+    ) -> Result<(), Error> {
+        if !is_supported(parent_chain) {
+            return Err(Error::UnsupportedChain(*parent_chain));
+        }
+        if self.tx.base_size() == AMBIGUOUS_TX_SIZE {
+            return Err(Error::AmbiguousTxSize);
+        }
+        let including_header = self.including_header()?;
+        let merkle_root = merkle_root_from_branch(
+            self.txid(),
+            self.tx_index,
+            &self.merkle_branch,
+        )?;
+        if merkle_root != including_header.merkle_root {
+            return Err(Error::MerkleRootMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verify that the transaction is included in a chain of valid headers
+    /// that builds on `checkpoint`, and that it is buried at least
+    /// `required_confirmations` deep, under at least as much work as
+    /// `required_confirmations` headers at the checkpoint target. Proofs are
+    /// only ever anchored to a checkpoint; what the transaction pays is
+    /// checked separately, with [`Self::verify_payment`].
+    pub fn verify(
+        &self,
+        parent_chain: &ParentChainType,
+        checkpoint: &BlockHeader,
+        required_confirmations: u32,
+    ) -> Result<(), Error> {
+        let () = self.verify_inclusion(parent_chain)?;
+        if self.headers[0].prev_blockhash != checkpoint.block_hash() {
+            return Err(Error::NotOnCheckpoint);
+        }
+        for (index, pair) in self.headers.windows(2).enumerate() {
+            if pair[1].prev_blockhash != pair[0].block_hash() {
+                return Err(Error::BrokenHeaderChain { index: index + 1 });
+            }
+        }
+        let max_target = max_target(checkpoint.target());
+        for (index, header) in self.headers.iter().enumerate() {
+            if header.target() > max_target {
+                return Err(Error::DifficultyTooLow { index });
+            }
+            validate_header_pow(parent_chain, header).map_err(|err| {
+                match err {
+                    Error::InvalidPow { .. } => Error::InvalidPow { index },
+                    err => err,
+                }
+            })?;
+        }
+        if self.confirmations() < required_confirmations {
+            return Err(Error::InsufficientConfirmations {
+                required: required_confirmations,
+                provided: self.confirmations(),
+            });
+        }
+        // There are at least `required_confirmations` headers, so this is
+        // bounded by the length of the proof
+        let no_work = Work::from_le_bytes([0; 32]);
+        let work = self
+            .headers
+            .iter()
+            .fold(no_work, |work, header| work + header.work());
+        let required_work = (0..required_confirmations)
+            .fold(no_work, |work, _| work + checkpoint.work());
+        if work < required_work {
+            return Err(Error::InsufficientWork {
+                required_confirmations,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verify that the transaction pays at least `amount` to
+    /// `script_pubkey`
+    pub fn verify_payment(
+        &self,
+        script_pubkey: &Script,
+        amount: Amount,
+    ) -> Result<(), Error> {
+        let paid = self
+            .tx
+            .output
+            .iter()
+            .filter(|output| output.script_pubkey.as_script() == script_pubkey)
+            .try_fold(Amount::ZERO, |total, output| {
+                total.checked_add(output.value)
+            })
+            .ok_or(AmountOverflowError)?;
+        if paid < amount {
+            return Err(Error::InsufficientPayment {
+                required: amount,
+                paid,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn hash_merkle_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(left);
+    preimage[32..].copy_from_slice(right);
+    sha256d::Hash::hash(&preimage).to_byte_array()
+}
+
+/// Compute the merkle root committed to by a merkle branch
+pub fn merkle_root_from_branch(
+    txid: Txid,
+    tx_index: u32,
+    merkle_branch: &[TxMerkleNode],
+) -> Result<TxMerkleNode, Error> {
+    if merkle_branch.len() > MAX_MERKLE_BRANCH_LEN {
+        return Err(Error::MerkleBranchTooLong {
+            len: merkle_branch.len(),
+        });
+    }
+    if merkle_branch.len() < MAX_MERKLE_BRANCH_LEN
+        && (tx_index >> merkle_branch.len()) != 0
+    {
+        return Err(Error::MerkleIndexOutOfRange { tx_index });
+    }
+    let mut node = txid.to_byte_array();
+    let mut index = tx_index;
+    for sibling in merkle_branch {
+        let sibling = sibling.to_byte_array();
+        node = if index & 1 == 0 {
+            hash_merkle_nodes(&node, &sibling)
+        } else {
+            hash_merkle_nodes(&sibling, &node)
+        };
+        index >>= 1;
+    }
+    Ok(TxMerkleNode::from_byte_array(node))
+}
+
+/// Compute the merkle branch for the tx at `tx_index`, given all txids in
+/// a block. Returns `None` if `tx_index` is out of range.
+pub fn merkle_branch(
+    txids: &[Txid],
+    tx_index: usize,
+) -> Option<Vec<TxMerkleNode>> {
+    if tx_index >= txids.len() {
+        return None;
+    }
+    let mut layer: Vec<[u8; 32]> =
+        txids.iter().map(|txid| txid.to_byte_array()).collect();
+    let mut index = tx_index;
+    let mut branch = Vec::new();
+    while layer.len() > 1 {
+        if layer.len() % 2 == 1 {
+            layer.push(layer[layer.len() - 1]);
+        }
+        branch.push(TxMerkleNode::from_byte_array(layer[index ^ 1]));
+        layer = layer
+            .chunks_exact(2)
+            .map(|pair| hash_merkle_nodes(&pair[0], &pair[1]))
+            .collect();
+        index /= 2;
+    }
+    Some(branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        BlockHash, CompactTarget, ScriptBuf, TxOut, absolute::LockTime,
+        block::Version, transaction,
+    };
+
+    use super::*;
+
+    /// Regtest proof-of-work limit, so that headers can be mined in tests
+    const EASY_BITS: u32 = 0x207fffff;
+
+    fn test_tx(value: Amount) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![TxOut {
+                value,
+                script_pubkey: test_script(),
+            }],
+        }
+    }
+
+    fn test_script() -> ScriptBuf {
+        ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_byte_array([7; 20]))
+    }
+
+    fn test_txids(n: u8) -> Vec<Txid> {
+        (0..n).map(|i| Txid::from_byte_array([i + 1; 32])).collect()
+    }
+
+    fn mine_header(
+        parent_chain: &ParentChainType,
+        prev_blockhash: BlockHash,
+        merkle_root: TxMerkleNode,
+    ) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: Version::TWO,
+            prev_blockhash,
+            merkle_root,
+            time: 1_700_000_000,
+            bits: CompactTarget::from_consensus(EASY_BITS),
+            nonce: 0,
+        };
+        while validate_header_pow(parent_chain, &header).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Returns the checkpoint and a proof with `block_index` headers before
+    /// the including block
+    fn test_proof(
+        parent_chain: &ParentChainType,
+        block_index: u32,
+        confirmations: u32,
+    ) -> (BlockHeader, L1TxProof) {
+        let tx = test_tx(Amount::from_sat(50_000));
+        let tx_index = 3;
+        let mut txids = test_txids(5);
+        txids[tx_index] = tx.compute_txid();
+        let merkle_root = bitcoin::merkle_tree::calculate_root(
+            txids
+                .iter()
+                .map(|txid| TxMerkleNode::from_raw_hash(txid.to_raw_hash())),
+        )
+        .unwrap();
+        let checkpoint = mine_header(
+            parent_chain,
+            BlockHash::all_zeros(),
+            TxMerkleNode::all_zeros(),
+        );
+        let mut headers = Vec::new();
+        while headers.len() < (block_index + confirmations) as usize {
+            let prev_blockhash = headers
+                .last()
+                .unwrap_or(&checkpoint)
+                .block_hash();
+            let header_merkle_root = if headers.len() == block_index as usize {
+                merkle_root
+            } else {
+                TxMerkleNode::all_zeros()
+            };
+            headers.push(mine_header(
+                parent_chain,
+                prev_blockhash,
+                header_merkle_root,
+            ));
+        }
+        let proof = L1TxProof {
+            tx,
+            tx_index: tx_index as u32,
+            merkle_branch: merkle_branch(&txids, tx_index).unwrap(),
+            headers,
+            block_index,
+        };
+        (checkpoint, proof)
+    }
+
+    #[test]
+    fn test_merkle_branch_matches_block_merkle_root() {
+        for n in 1..=9 {
+            let txids = test_txids(n);
+            let expected = bitcoin::merkle_tree::calculate_root(
+                txids
+                    .iter()
+                    .map(|txid| TxMerkleNode::from_raw_hash(txid.to_raw_hash())),
+            )
+            .unwrap();
+            for (tx_index, txid) in txids.iter().enumerate() {
+                let branch = merkle_branch(&txids, tx_index).unwrap();
+                let root =
+                    merkle_root_from_branch(*txid, tx_index as u32, &branch)
+                        .unwrap();
+                assert_eq!(root, expected);
+            }
+            assert!(merkle_branch(&txids, n as usize).is_none());
+        }
+    }
+
+    #[test]
+    fn test_verify_valid_proof() {
+        for parent_chain in [
+            ParentChainType::Btc,
+            ParentChainType::Bch,
+            ParentChainType::Ltc,
+        ] {
+            let (checkpoint, proof) = test_proof(&parent_chain, 2, 3);
+            assert_eq!(proof.confirmations(), 3);
+            proof.verify(&parent_chain, &checkpoint, 3).unwrap();
+            assert!(matches!(
+                proof.verify(&parent_chain, &checkpoint, 4),
+                Err(Error::InsufficientConfirmations {
+                    required: 4,
+                    provided: 3
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_including_block_only() {
+        let (_checkpoint, proof) = test_proof(&ParentChainType::Btc, 2, 3);
+        let including_header = proof.headers[2];
+        let proof = proof.including_block_only().unwrap();
+        assert_eq!(proof.headers, vec![including_header]);
+        assert_eq!(proof.block_index, 0);
+        proof.verify_inclusion(&ParentChainType::Btc).unwrap();
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_tx() {
+        let (checkpoint, mut proof) = test_proof(&ParentChainType::Btc, 0, 1);
+        proof.tx = test_tx(Amount::from_sat(1));
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &checkpoint, 1),
+            Err(Error::MerkleRootMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_other_checkpoint() {
+        let (_checkpoint, proof) = test_proof(&ParentChainType::Btc, 1, 1);
+        let other_checkpoint = proof.headers[0];
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &other_checkpoint, 1),
+            Err(Error::NotOnCheckpoint)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_broken_header_chain() {
+        let (checkpoint, mut proof) = test_proof(&ParentChainType::Btc, 0, 3);
+        proof.headers[2] = mine_header(
+            &ParentChainType::Btc,
+            BlockHash::all_zeros(),
+            TxMerkleNode::all_zeros(),
+        );
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &checkpoint, 3),
+            Err(Error::BrokenHeaderChain { index: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid_pow() {
+        let (checkpoint, mut proof) = test_proof(&ParentChainType::Btc, 0, 2);
+        let last_header = &mut proof.headers[1];
+        while validate_header_pow(&ParentChainType::Btc, last_header).is_ok() {
+            last_header.nonce += 1;
+        }
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &checkpoint, 2),
+            Err(Error::InvalidPow { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_low_difficulty() {
+        let (_checkpoint, mut proof) = test_proof(&ParentChainType::Btc, 0, 1);
+        // Proof headers use a far easier target than the checkpoint
+        let checkpoint = BlockHeader {
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            ..proof.headers[0]
+        };
+        proof.headers[0] = mine_header(
+            &ParentChainType::Btc,
+            checkpoint.block_hash(),
+            proof.headers[0].merkle_root,
+        );
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &checkpoint, 1),
+            Err(Error::DifficultyTooLow { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_insufficient_work() {
+        let (_checkpoint, mut proof) = test_proof(&ParentChainType::Btc, 0, 2);
+        // Proof headers use twice the checkpoint target, which is within the
+        // per-header bound, but carry half the work of the confirmations
+        let checkpoint = BlockHeader {
+            bits: CompactTarget::from_consensus(0x203fffff),
+            ..proof.headers[0]
+        };
+        proof.headers[0] = mine_header(
+            &ParentChainType::Btc,
+            checkpoint.block_hash(),
+            proof.headers[0].merkle_root,
+        );
+        proof.headers[1] = mine_header(
+            &ParentChainType::Btc,
+            proof.headers[0].block_hash(),
+            proof.headers[1].merkle_root,
+        );
+        proof.verify(&ParentChainType::Btc, &checkpoint, 1).unwrap();
+        assert!(matches!(
+            proof.verify(&ParentChainType::Btc, &checkpoint, 2),
+            Err(Error::InsufficientWork {
+                required_confirmations: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_unsupported_chain() {
+        let (checkpoint, proof) = test_proof(&ParentChainType::Btc, 0, 1);
+        assert!(matches!(
+            proof.verify(&ParentChainType::Eth, &checkpoint, 1),
+            Err(Error::UnsupportedChain(ParentChainType::Eth))
+        ));
+    }
+
+    #[test]
+    fn test_ltc_headers_require_scrypt_pow() {
+        // Find a header that is valid for BTC but not for LTC
+        let mut header = mine_header(
+            &ParentChainType::Btc,
+            BlockHash::all_zeros(),
+            TxMerkleNode::all_zeros(),
+        );
+        while validate_header_pow(&ParentChainType::Btc, &header).is_err()
+            || validate_header_pow(&ParentChainType::Ltc, &header).is_ok()
+        {
+            header.nonce += 1;
+        }
+        assert!(matches!(
+            validate_header_pow(&ParentChainType::Ltc, &header),
+            Err(Error::InvalidPow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_payment() {
+        let (_checkpoint, proof) = test_proof(&ParentChainType::Btc, 0, 1);
+        proof
+            .verify_payment(&test_script(), Amount::from_sat(50_000))
+            .unwrap();
+        assert!(matches!(
+            proof.verify_payment(&test_script(), Amount::from_sat(50_001)),
+            Err(Error::InsufficientPayment { .. })
+        ));
+        let other_script =
+            ScriptBuf::new_p2wpkh(&bitcoin::WPubkeyHash::from_byte_array([8; 20]));
+        assert!(matches!(
+            proof.verify_payment(&other_script, Amount::from_sat(1)),
+            Err(Error::InsufficientPayment { .. })
+        ));
+        // Payments that do not fit in an amount are rejected
+        let mut proof = proof;
+        let output = proof.tx.output[0].clone();
+        proof.tx.output = vec![
+            bitcoin::TxOut {
+                value: Amount::MAX,
+                ..output.clone()
+            },
+            output,
+        ];
+        assert!(matches!(
+            proof.verify_payment(&test_script(), Amount::from_sat(50_000)),
+            Err(Error::AmountOverflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_l1_txid_uses_rpc_byte_order() {
+        let (_checkpoint, proof) = test_proof(&ParentChainType::Btc, 0, 1);
+        let TxId::Hash32(bytes) = proof.l1_txid() else {
+            panic!("expected a 32-byte txid");
+        };
+        assert_eq!(hex::encode(bytes), proof.txid().to_string());
+    }
+}
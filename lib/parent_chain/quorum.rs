@@ -0,0 +1,534 @@
+//! Quorum of parent chain backends
+//!
+//! A [`QuorumClient`] queries several backends for the same parent chain,
+//! and only accepts observations that enough of them agree on, according to
+//! the chain's [`QuorumPolicy`]. A single lying or lagging node therefore
+//! cannot push a swap to `ReadyToClaim`.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use futures::future::{BoxFuture, join_all};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::parent_chain::{
+    client::{Error, ParentChainClientTrait, ParentChainTx, TxId},
+    config::{ParentChainType, QuorumPolicy},
+    proof::L1TxProof,
+};
+
+/// What a single backend reports about an L1 transaction
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BackendObservation {
+    /// The transaction was not found
+    NotFound,
+    /// The transaction was found, with the outputs that most backends report
+    Found {
+        confirmations: u32,
+        block_hash: Option<String>,
+    },
+    /// The transaction was found, but with different outputs than most
+    /// backends report
+    OutputsMismatch { confirmations: u32 },
+    /// The backend could not be queried
+    Error(String),
+}
+
+/// Observation of an L1 transaction by a backend
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BackendReport {
+    /// Backend URL, without credentials
+    pub backend: String,
+    pub observation: BackendObservation,
+}
+
+/// Backends of a parent chain that disagree about an L1 transaction
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct L1Disagreement {
+    pub quorum: QuorumPolicy,
+    /// Observation of each backend, in configuration order
+    pub reports: Vec<BackendReport>,
+}
+
+/// Backend of a [`QuorumClient`]
+struct Backend {
+    /// URL without credentials, to identify the backend in logs and swap
+    /// status
+    name: String,
+    client: Box<dyn ParentChainClientTrait>,
+}
+
+/// Parent chain client that combines the observations of several backends
+pub struct QuorumClient {
+    parent_chain: ParentChainType,
+    backends: Vec<Backend>,
+    quorum: QuorumPolicy,
+    /// Number of backends that must agree
+    required: usize,
+    /// Last disagreement between the backends, for each L1 transaction that
+    /// they disagree about
+    disagreements: Mutex<HashMap<TxId, L1Disagreement>>,
+}
+
+impl QuorumClient {
+    /// Combine `backends`, each with the URL that it was configured with
+    pub fn new(
+        parent_chain: ParentChainType,
+        backends: Vec<(Url, Box<dyn ParentChainClientTrait>)>,
+        quorum: QuorumPolicy,
+    ) -> Result<Self, Error> {
+        let required = quorum.required(backends.len());
+        if required == 0 || required > backends.len() {
+            return Err(Error::InvalidQuorum {
+                required,
+                backends: backends.len(),
+            });
+        }
+        let backends = backends
+            .into_iter()
+            .map(|(mut url, client)| {
+                let _: Result<(), ()> = url.set_username("");
+                let _: Result<(), ()> = url.set_password(None);
+                Backend {
+                    name: url.to_string(),
+                    client,
+                }
+            })
+            .collect();
+        Ok(Self {
+            parent_chain,
+            backends,
+            quorum,
+            required,
+            disagreements: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Client to use for the chain. A single backend is used as is, since
+    /// there is nothing for it to agree with.
+    pub fn into_client(mut self) -> Box<dyn ParentChainClientTrait> {
+        if self.backends.len() == 1
+            && let Some(backend) = self.backends.pop()
+        {
+            return backend.client;
+        }
+        Box::new(self)
+    }
+
+    /// Combine the backends' lookups of `txid`, in backend order.
+    ///
+    /// Backends agree on a transaction if they report the same outputs for
+    /// it. The transaction is accepted with the highest confirmation count
+    /// that enough of the agreeing backends reach, so that lagging backends
+    /// count towards the quorum with the confirmations that they do report.
+    fn combine(
+        &self,
+        txid: &TxId,
+        results: Vec<Result<Option<ParentChainTx>, Error>>,
+    ) -> Result<Option<ParentChainTx>, Error> {
+        let responded = results.iter().filter(|res| res.is_ok()).count();
+        // Group the backends that found the transaction by its outputs,
+        // largest group first
+        let mut groups: Vec<Vec<&ParentChainTx>> = Vec::new();
+        for tx in results.iter().filter_map(|res| res.as_ref().ok()?.as_ref()) {
+            match groups
+                .iter_mut()
+                .find(|group| group[0].outputs == tx.outputs)
+            {
+                Some(group) => group.push(tx),
+                None => groups.push(vec![tx]),
+            }
+        }
+        groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+        let majority_outputs = groups.first().map(|group| &group[0].outputs);
+        let reports: Vec<BackendReport> = self
+            .backends
+            .iter()
+            .zip(&results)
+            .map(|(backend, res)| {
+                let observation = match res {
+                    Ok(None) => BackendObservation::NotFound,
+                    Ok(Some(tx)) if Some(&tx.outputs) == majority_outputs => {
+                        BackendObservation::Found {
+                            confirmations: tx.confirmations,
+                            block_hash: tx.block_hash.clone(),
+                        }
+                    }
+                    Ok(Some(tx)) => BackendObservation::OutputsMismatch {
+                        confirmations: tx.confirmations,
+                    },
+                    Err(err) => BackendObservation::Error(err.to_string()),
+                };
+                BackendReport {
+                    backend: backend.name.clone(),
+                    observation,
+                }
+            })
+            .collect();
+        let () = self.record_disagreement(txid, reports);
+        match groups.into_iter().next() {
+            Some(mut group) if group.len() >= self.required => {
+                group.sort_by_key(|tx| std::cmp::Reverse(tx.confirmations));
+                Ok(Some(group[self.required - 1].clone()))
+            }
+            // Enough backends responded, but not enough of them found the
+            // same transaction
+            _ if responded >= self.required => Ok(None),
+            _ => Err(Error::QuorumNotReached {
+                agreeing: responded,
+                required: self.required,
+            }),
+        }
+    }
+
+    /// Record the backends' reports about `txid` if they disagree, or clear
+    /// the last disagreement if they agree
+    fn record_disagreement(&self, txid: &TxId, reports: Vec<BackendReport>) {
+        let mut disagreements = self
+            .disagreements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if reports
+            .windows(2)
+            .all(|pair| pair[0].observation == pair[1].observation)
+        {
+            disagreements.remove(txid);
+            return;
+        }
+        tracing::warn!(
+            parent_chain = %self.parent_chain,
+            ?txid,
+            ?reports,
+            "Parent chain backends disagree about transaction"
+        );
+        disagreements.insert(
+            txid.clone(),
+            L1Disagreement {
+                quorum: self.quorum,
+                reports,
+            },
+        );
+    }
+}
+
+impl ParentChainClientTrait for QuorumClient {
+    fn chain_type(&self) -> ParentChainType {
+        self.parent_chain
+    }
+
+    fn get_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let results = join_all(
+                self.backends
+                    .iter()
+                    .map(|backend| backend.client.get_transaction(txid)),
+            )
+            .await;
+            self.combine(txid, results)
+        })
+    }
+
+    fn get_transaction_with_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        address: &'a str,
+        payment_proof: &'a str,
+    ) -> BoxFuture<'a, Result<Option<ParentChainTx>, Error>> {
+        Box::pin(async move {
+            let results = join_all(self.backends.iter().map(|backend| {
+                backend.client.get_transaction_with_proof(
+                    txid,
+                    address,
+                    payment_proof,
+                )
+            }))
+            .await;
+            self.combine(txid, results)
+        })
+    }
+
+    /// Highest block height that enough backends reach
+    fn get_block_height<'a>(&'a self) -> BoxFuture<'a, Result<u64, Error>> {
+        Box::pin(async move {
+            let mut heights: Vec<u64> = join_all(
+                self.backends
+                    .iter()
+                    .map(|backend| backend.client.get_block_height()),
+            )
+            .await
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+            if heights.len() < self.required {
+                return Err(Error::QuorumNotReached {
+                    agreeing: heights.len(),
+                    required: self.required,
+                });
+            }
+            heights.sort_unstable_by_key(|height| std::cmp::Reverse(*height));
+            Ok(heights[self.required - 1])
+        })
+    }
+
+    fn verify_transaction<'a>(
+        &'a self,
+        txid: &'a TxId,
+        min_confirmations: u32,
+    ) -> BoxFuture<'a, Result<bool, Error>> {
+        Box::pin(async move {
+            let tx = self.get_transaction(txid).await?;
+            Ok(tx.is_some_and(|tx| tx.confirmations >= min_confirmations))
+        })
+    }
+
+    /// Checkpoint that enough backends agree on. Backends at different tips
+    /// take different checkpoints, so this fails while they catch up.
+    fn get_checkpoint<'a>(
+        &'a self,
+    ) -> BoxFuture<'a, Result<bitcoin::block::Header, Error>> {
+        Box::pin(async move {
+            let results = join_all(
+                self.backends
+                    .iter()
+                    .map(|backend| backend.client.get_checkpoint()),
+            )
+            .await;
+            let mut counts = Vec::<(bitcoin::block::Header, usize)>::new();
+            let mut last_err = None;
+            for res in results {
+                match res {
+                    Ok(header) => {
+                        match counts.iter_mut().find(|(h, _)| *h == header) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((header, 1)),
+                        }
+                    }
+                    Err(err) => last_err = Some(err),
+                }
+            }
+            if let Some((header, _)) =
+                counts.iter().find(|(_, count)| *count >= self.required)
+            {
+                return Ok(*header);
+            }
+            match (counts.iter().map(|(_, count)| *count).max(), last_err) {
+                // No backend responded
+                (None, Some(err)) => Err(err),
+                (agreeing, _) => Err(Error::QuorumNotReached {
+                    agreeing: agreeing.unwrap_or(0),
+                    required: self.required,
+                }),
+            }
+        })
+    }
+
+    /// Proof from the first backend that can build one. Proofs are checked
+    /// against the swap checkpoint and proof-of-work by every sidechain
+    /// node, so a lying backend cannot produce a valid one.
+    fn get_tx_proof<'a>(
+        &'a self,
+        txid: &'a TxId,
+        checkpoint: &'a bitcoin::block::Header,
+        confirmations: u32,
+    ) -> BoxFuture<'a, Result<L1TxProof, Error>> {
+        Box::pin(async move {
+            let mut last_err = None;
+            for backend in &self.backends {
+                match backend
+                    .client
+                    .get_tx_proof(txid, checkpoint, confirmations)
+                    .await
+                {
+                    Ok(tx_proof) => return Ok(tx_proof),
+                    Err(err) => {
+                        tracing::debug!(
+                            backend = %backend.name,
+                            "Failed to get L1 tx proof: {err}"
+                        );
+                        last_err = Some(err);
+                    }
+                }
+            }
+            Err(last_err.unwrap_or(Error::TxNotFound))
+        })
+    }
+
+    fn l1_disagreement(&self, txid: &TxId) -> Option<L1Disagreement> {
+        self.disagreements
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(txid)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bitcoin::Amount;
+
+    use super::*;
+    use crate::parent_chain::{
+        MockChain, MockParentChainClient, MockTx, mock::MockPayment,
+    };
+
+    const ALICE: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+
+    fn payment(amount: Amount) -> MockTx {
+        MockTx::Payment {
+            txid: [0x42; 32],
+            outputs: vec![MockPayment {
+                address: ALICE.to_owned(),
+                token: None,
+                amount,
+            }],
+        }
+    }
+
+    /// Quorum of clients for mock chains named `names`
+    fn quorum_client(
+        names: &[&str],
+        quorum: QuorumPolicy,
+    ) -> (Vec<Arc<Mutex<MockChain>>>, QuorumClient) {
+        let chains: Vec<_> = names
+            .iter()
+            .map(|name| MockChain::shared(ParentChainType::Btc, name))
+            .collect();
+        let backends = names
+            .iter()
+            .zip(&chains)
+            .map(|(name, chain)| {
+                let url = Url::parse(&format!("mock://{name}")).unwrap();
+                let client: Box<dyn ParentChainClientTrait> =
+                    Box::new(MockParentChainClient::new(
+                        ParentChainType::Btc,
+                        chain.clone(),
+                    ));
+                (url, client)
+            })
+            .collect();
+        let client =
+            QuorumClient::new(ParentChainType::Btc, backends, quorum).unwrap();
+        (chains, client)
+    }
+
+    #[test]
+    fn test_quorum_policy_parse() {
+        assert_eq!("all".parse::<QuorumPolicy>().unwrap(), QuorumPolicy::All);
+        assert_eq!(
+            "2".parse::<QuorumPolicy>().unwrap(),
+            QuorumPolicy::Threshold(2)
+        );
+        assert!("0".parse::<QuorumPolicy>().is_err());
+        assert!("most".parse::<QuorumPolicy>().is_err());
+        assert_eq!(QuorumPolicy::Threshold(2).to_string(), "2");
+    }
+
+    #[tokio::test]
+    async fn test_quorum_all_agree_on_confirmations() {
+        let (chains, client) =
+            quorum_client(&["all_a", "all_b"], QuorumPolicy::All);
+        let mut txid = None;
+        for (chain, blocks) in chains.iter().zip([3, 1]) {
+            let mut chain = chain.lock().unwrap();
+            txid = Some(chain.broadcast(payment(Amount::from_sat(50_000))));
+            chain.mine(blocks);
+        }
+        let txid = txid.unwrap();
+
+        // The lagging backend holds back the confirmation count
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 1);
+        assert!(!client.verify_transaction(&txid, 3).await.unwrap());
+        assert_eq!(client.get_block_height().await.unwrap(), 1);
+        let disagreement = client.l1_disagreement(&txid).unwrap();
+        assert_eq!(disagreement.reports.len(), 2);
+        assert!(matches!(
+            disagreement.reports[1].observation,
+            BackendObservation::Found {
+                confirmations: 1,
+                ..
+            }
+        ));
+
+        // Disagreements are cleared once the backends agree
+        chains[0].lock().unwrap().reorg(2);
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 1);
+        assert!(client.l1_disagreement(&txid).is_none());
+
+        // Every backend must respond
+        chains[1].lock().unwrap().set_offline(true);
+        assert!(matches!(
+            client.get_transaction(&txid).await,
+            Err(Error::QuorumNotReached {
+                agreeing: 1,
+                required: 2
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_quorum_k_of_n_lying_backend() {
+        let (chains, client) = quorum_client(
+            &["k_of_n_a", "k_of_n_b", "k_of_n_liar"],
+            QuorumPolicy::Threshold(2),
+        );
+        for (chain, amount, blocks) in [
+            (&chains[0], 50_000, 2),
+            (&chains[1], 50_000, 1),
+            (&chains[2], 100_000, 10),
+        ] {
+            let mut chain = chain.lock().unwrap();
+            let _: TxId = chain.broadcast(payment(Amount::from_sat(amount)));
+            chain.mine(blocks);
+        }
+        let txid = payment(Amount::ZERO).txid();
+
+        // The liar is outvoted on both the outputs and the confirmations
+        let tx = client.get_transaction(&txid).await.unwrap().unwrap();
+        assert_eq!(tx.confirmations, 1);
+        assert_eq!(
+            tx.amount_paid_to(&ParentChainType::Btc, None, ALICE),
+            Some(Amount::from_sat(50_000))
+        );
+        let disagreement = client.l1_disagreement(&txid).unwrap();
+        assert_eq!(disagreement.quorum, QuorumPolicy::Threshold(2));
+        assert_eq!(disagreement.reports[2].backend, "mock://k_of_n_liar");
+        assert_eq!(
+            disagreement.reports[2].observation,
+            BackendObservation::OutputsMismatch { confirmations: 10 }
+        );
+
+        // Without a second honest backend, nothing is accepted
+        chains[1].lock().unwrap().set_offline(true);
+        assert!(client.get_transaction(&txid).await.unwrap().is_none());
+        assert_eq!(client.get_block_height().await.unwrap(), 2);
+    }
+
+    #[test]
+    fn test_quorum_invalid() {
+        let chain = MockChain::shared(ParentChainType::Btc, "invalid");
+        let client: Box<dyn ParentChainClientTrait> =
+            Box::new(MockParentChainClient::new(ParentChainType::Btc, chain));
+        let url = Url::parse("mock://invalid").unwrap();
+        assert!(matches!(
+            QuorumClient::new(
+                ParentChainType::Btc,
+                vec![(url, client)],
+                QuorumPolicy::Threshold(2),
+            ),
+            Err(Error::InvalidQuorum {
+                required: 2,
+                backends: 1
+            })
+        ));
+    }
+}
@@ -8,14 +8,37 @@
 //! is specifically for L2 → L1 peer-to-peer exchanges.
 
 use std::collections::HashMap;
+use borsh::BorshSerialize;
+use futures::StreamExt as _;
 use thiserror::Error;
-
-use crate::parent_chain::{
-    config::ParentChainType,
-    client::{ParentChainClient, TxId},
-    default_confirmations,
+use utoipa::ToSchema;
+
+use crate::{
+    parent_chain::{
+        config::ParentChainType,
+        client::{ParentChainClient, ParentChainTx, TxId},
+        default_confirmations,
+        quorum::L1Disagreement,
+        watcher::SwapWatch,
+    },
+    types::{AssetId, BitcoinOutputContent, FilledOutputContent, OutputContent},
 };
 
+/// Default number of sidechain blocks for which a fill reserves an open
+/// offer
+pub const DEFAULT_FILL_TIMEOUT: u32 = 12;
+
+/// Version of the swap ID derivation in [`Swap::with_nonce`]. `SwapCreate`
+/// txs name the version that their swap ID was derived with.
+pub const SWAP_ID_VERSION: u8 = 1;
+
+/// Maximum number of swaps polled concurrently by
+/// [`SwapManager::update_all_swaps`]
+const MAX_CONCURRENT_SWAP_UPDATES: usize = 16;
+
+/// Number of [`SwapEvent`]s buffered for each subscriber
+const SWAP_EVENT_CAPACITY: usize = 64;
+
 /// Unique identifier for a swap
 #[derive(Clone, Debug, Eq, PartialEq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct SwapId(pub [u8; 32]);
@@ -47,8 +70,87 @@ pub enum SwapState {
     Cancelled,
 }
 
+/// L2 asset escrowed by an L2 → L1 swap
+#[derive(
+    BorshSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    ToSchema,
+)]
+pub enum SwapAsset {
+    /// Bitcoin, a BitAsset, or a BitAsset control coin
+    Asset(AssetId),
+    /// LP token of the AMM pool for an asset pair
+    AmmLpToken { asset0: AssetId, asset1: AssetId },
+}
+
+impl SwapAsset {
+    pub const BITCOIN: Self = Self::Asset(AssetId::Bitcoin);
+
+    /// The asset and amount held by an output, if it holds an asset that
+    /// can be escrowed.
+    /// Hash time-locked outputs cannot be escrowed, since they are spent
+    /// under their own redeem and refund rules.
+    pub fn of_output_content(
+        content: &FilledOutputContent,
+    ) -> Option<(Self, u64)> {
+        if content.htlc().is_some() {
+            return None;
+        }
+        if let Some((asset0, asset1, amount)) = content.lp_token_amount() {
+            return Some((Self::AmmLpToken { asset0, asset1 }, amount));
+        }
+        content
+            .asset_value()
+            .map(|(asset, amount)| (Self::Asset(asset), amount))
+    }
+
+    /// Output content that holds `amount` of the asset
+    pub fn output_content(&self, amount: u64) -> OutputContent {
+        match self {
+            Self::Asset(AssetId::Bitcoin) => OutputContent::Bitcoin(
+                BitcoinOutputContent(bitcoin::Amount::from_sat(amount)),
+            ),
+            Self::Asset(AssetId::BitAsset(_)) => OutputContent::BitAsset(amount),
+            Self::Asset(AssetId::BitAssetControl(_)) => {
+                OutputContent::BitAssetControl
+            }
+            Self::AmmLpToken { .. } => OutputContent::AmmLpToken(amount),
+        }
+    }
+}
+
+impl From<AssetId> for SwapAsset {
+    fn from(asset: AssetId) -> Self {
+        Self::Asset(asset)
+    }
+}
+
+/// A filler's reservation of an open swap offer, registered by a
+/// `SwapFill` transaction
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SwapFill {
+    /// L2 address that will claim the swap
+    pub l2_recipient: crate::types::Address,
+    /// L1 transaction that pays the offerer
+    pub l1_txid: TxId,
+    /// Output locked to the swap as the filler's bond
+    pub bond: Option<crate::types::OutPoint>,
+    /// Sidechain height at which the fill was registered
+    pub height: u32,
+    /// For partially fillable offers: L1 amount paid by the fill
+    pub l1_amount: Option<bitcoin::Amount>,
+    /// Amount of the L2 asset released to the filler, once claimed
+    pub l2_amount_claimed: Option<bitcoin::Amount>,
+}
+
 /// A trustless swap between L2 coins and a parent chain asset
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Swap {
     pub id: SwapId,
     /// Direction of the swap
@@ -61,18 +163,86 @@ pub struct Swap {
     pub required_confirmations: u32,
     /// Current state of the swap
     pub state: SwapState,
-    /// L2 address that will receive the coins (or sender for L2ToL1)
-    pub l2_recipient: crate::types::Address,
-    /// Amount of L2 coins to be paid
+    /// L2 address that will receive the coins.
+    /// `None` for open offers, which any filler can take.
+    pub l2_recipient: Option<crate::types::Address>,
+    /// For L2ToL1 swaps: L2 address of the offerer, which owns the escrow
+    /// output
+    pub l2_offerer: Option<crate::types::Address>,
+    /// Amount of the L2 asset to be paid. For assets other than Bitcoin,
+    /// this is the number of base units of the asset.
     pub l2_amount: bitcoin::Amount,
+    /// L2 asset to be paid
+    pub l2_asset: SwapAsset,
     /// For L2ToL1 swaps: L1 address where L1 coins should be sent
     pub l1_recipient_address: Option<String>,
     /// For L2ToL1 swaps: Amount of L1 coins required
     pub l1_amount: Option<bitcoin::Amount>,
+    /// For L2ToL1 swaps on chains with private outputs (Monero): tx key or
+    /// tx proof from the payer, which shows what the L1 transaction pays
+    /// the L1 recipient. Only set in a node's working copy of the swap,
+    /// from its [`crate::parent_chain::watcher::SwapWatch`]; never set in
+    /// consensus state.
+    pub l1_payment_proof: Option<String>,
+    /// For L2ToL1 swaps on BTC-family chains: L1 block header that claim
+    /// proofs must build on
+    pub l1_checkpoint: Option<bitcoin::block::Header>,
+    /// Hash of the L1 block that includes the L1 transaction, as last seen
+    /// by this node's swap monitor. Used to detect L1 reorgs. Like
+    /// `l1_payment_proof`, only set in a node's working copy of the swap.
+    pub l1_block_hash: Option<String>,
     /// Block height when swap was created
     pub created_at_height: u32,
     /// Optional expiration height
     pub expires_at_height: Option<u32>,
+    /// For open offers: bond that a filler must lock to the swap
+    pub fill_bond: bitcoin::Amount,
+    /// For open offers: number of sidechain blocks for which a fill
+    /// reserves the offer
+    pub fill_timeout: u32,
+    /// For open offers: fills registered so far, oldest first. Only the
+    /// last fill can claim the swap, unless it is partially fillable.
+    pub fills: Vec<SwapFill>,
+    /// For open offers: whether several fills can each pay part of
+    /// `l1_amount`, for a pro-rata part of `l2_amount`
+    pub partial_fills: bool,
+    /// L1 amount paid by claimed fills
+    pub l1_amount_filled: bitcoin::Amount,
+    /// Amount of the L2 asset that is still escrowed
+    pub l2_amount_remaining: bitcoin::Amount,
+    /// For L2ToL1 swaps: output that escrows `l2_amount_remaining`, which
+    /// moves to the claim tx after each partial claim
+    pub escrow: Option<crate::types::OutPoint>,
+}
+
+/// Swap as reported by `get_swap_status`: the swap from consensus state,
+/// together with what this node observes of its L1 transaction
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SwapStatus {
+    #[serde(flatten)]
+    pub swap: Swap,
+    /// Disagreement between this node's parent chain backends about the
+    /// swap's L1 transaction, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub l1_disagreement: Option<L1Disagreement>,
+    /// This node's local data about the swap, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch: Option<SwapWatch>,
+}
+
+/// Change in a swap's L1 status, as seen by the swap monitor
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SwapEvent {
+    /// The swap's L1 transaction was reorged out of the block that
+    /// included it. The swap can not be claimed against the orphaned block.
+    L1Reorg {
+        swap_id: SwapId,
+        l1_txid: TxId,
+        /// Block that included the transaction, if it was seen
+        orphaned_block_hash: Option<String>,
+        /// State of the swap after the reorg
+        state: SwapState,
+    },
 }
 
 impl Swap {
@@ -89,10 +259,11 @@ impl Swap {
         let required_confirmations = required_confirmations
             .unwrap_or_else(|| default_confirmations(parent_chain));
 
-        // Generate swap ID from parent chain, txid, and recipient
+        // Generate swap ID from txid, recipient, and amount
         let mut id_data = Vec::new();
         id_data.extend_from_slice(&l1_txid.hash_bytes());
         id_data.extend_from_slice(&l2_recipient.0);
+        id_data.extend_from_slice(&l2_amount.to_sat().to_le_bytes());
         let id_hash = blake3::hash(&id_data);
         let id = SwapId(*id_hash.as_bytes());
 
@@ -103,12 +274,24 @@ impl Swap {
             l1_txid,
             required_confirmations,
             state: SwapState::Pending,
-            l2_recipient,
+            l2_recipient: Some(l2_recipient),
+            l2_offerer: None,
             l2_amount,
+            l2_asset: SwapAsset::BITCOIN,
             l1_recipient_address: None,
             l1_amount: None,
+            l1_payment_proof: None,
+            l1_checkpoint: None,
+            l1_block_hash: None,
             created_at_height: current_height,
             expires_at_height: None,
+            fill_bond: bitcoin::Amount::ZERO,
+            fill_timeout: 0,
+            fills: Vec::new(),
+            partial_fills: false,
+            l1_amount_filled: bitcoin::Amount::ZERO,
+            l2_amount_remaining: l2_amount,
+            escrow: None,
         }
     }
 
@@ -131,11 +314,13 @@ impl Swap {
         // Use a placeholder that will be updated when Bob fills the swap
         let placeholder_txid = TxId::Hash32([0u8; 32]);
 
-        // Generate swap ID from parent chain, L1 address, L1 amount, L2 sender, and L2 recipient
+        // Generate swap ID from L1 address, L1 amount, L2 sender, L2 amount,
+        // and L2 recipient
         let mut id_data = Vec::new();
         id_data.extend_from_slice(l1_recipient_address.as_bytes());
         id_data.extend_from_slice(&l1_amount.to_sat().to_le_bytes());
         id_data.extend_from_slice(&l2_sender.0);
+        id_data.extend_from_slice(&l2_amount.to_sat().to_le_bytes());
         id_data.extend_from_slice(&l2_recipient.0);
         let id_hash = blake3::hash(&id_data);
         let id = SwapId(*id_hash.as_bytes());
@@ -147,17 +332,277 @@ impl Swap {
             l1_txid: placeholder_txid,
             required_confirmations,
             state: SwapState::Pending,
-            l2_recipient, // Bob's address (will claim)
+            l2_recipient: Some(l2_recipient), // Bob's address (will claim)
+            l2_offerer: Some(l2_sender),
+            l2_amount,
+            l2_asset: SwapAsset::BITCOIN,
+            l1_recipient_address: Some(l1_recipient_address),
+            l1_amount: Some(l1_amount),
+            l1_payment_proof: None,
+            l1_checkpoint: None,
+            l1_block_hash: None,
+            created_at_height: current_height,
+            expires_at_height: None,
+            fill_bond: bitcoin::Amount::ZERO,
+            fill_timeout: 0,
+            fills: Vec::new(),
+            partial_fills: false,
+            l1_amount_filled: bitcoin::Amount::ZERO,
+            l2_amount_remaining: l2_amount,
+            escrow: None,
+        }
+    }
+
+    /// Create an open L2 → L1 offer, without an L2 recipient.
+    /// The first filler registers their L2 address and L1 txid with a
+    /// `SwapFill` transaction, locking `fill_bond` to the swap, and has
+    /// `fill_timeout` sidechain blocks to claim before the offer can be
+    /// filled again.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_open(
+        parent_chain: ParentChainType,
+        l1_recipient_address: String,
+        l1_amount: bitcoin::Amount,
+        l2_amount: bitcoin::Amount,
+        fill_bond: bitcoin::Amount,
+        fill_timeout: u32,
+        required_confirmations: Option<u32>,
+        current_height: u32,
+    ) -> Self {
+        let required_confirmations = required_confirmations
+            .unwrap_or_else(|| default_confirmations(parent_chain));
+
+        // Generate swap ID from the offer terms
+        let mut id_data = Vec::new();
+        id_data.extend_from_slice(l1_recipient_address.as_bytes());
+        id_data.extend_from_slice(&l1_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&l2_amount.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&fill_bond.to_sat().to_le_bytes());
+        id_data.extend_from_slice(&fill_timeout.to_le_bytes());
+        let id_hash = blake3::hash(&id_data);
+        let id = SwapId(*id_hash.as_bytes());
+
+        Self {
+            id,
+            direction: SwapDirection::L2ToL1,
+            parent_chain,
+            l1_txid: TxId::Hash32([0u8; 32]),
+            required_confirmations,
+            state: SwapState::Pending,
+            l2_recipient: None,
+            l2_offerer: None,
             l2_amount,
+            l2_asset: SwapAsset::BITCOIN,
             l1_recipient_address: Some(l1_recipient_address),
             l1_amount: Some(l1_amount),
+            l1_payment_proof: None,
+            l1_checkpoint: None,
+            l1_block_hash: None,
             created_at_height: current_height,
             expires_at_height: None,
+            fill_bond,
+            fill_timeout,
+            fills: Vec::new(),
+            partial_fills: false,
+            l1_amount_filled: bitcoin::Amount::ZERO,
+            l2_amount_remaining: l2_amount,
+            escrow: None,
+        }
+    }
+
+    /// Escrow `l2_asset` instead of Bitcoin. Swaps for other assets have
+    /// distinct IDs.
+    pub fn with_l2_asset(mut self, l2_asset: SwapAsset) -> Self {
+        if l2_asset != SwapAsset::BITCOIN {
+            let mut id_data = self.id.0.to_vec();
+            id_data.extend(borsh::to_vec(&l2_asset).unwrap());
+            self.id = SwapId(*blake3::hash(&id_data).as_bytes());
+        }
+        self.l2_asset = l2_asset;
+        self
+    }
+
+    /// Let several fills each pay part of `l1_amount`, for a pro-rata part
+    /// of `l2_amount`. Partially fillable offers have distinct IDs.
+    pub fn with_partial_fills(mut self) -> Self {
+        let mut id_data = self.id.0.to_vec();
+        id_data.extend_from_slice(b"partial_fills");
+        self.id = SwapId(*blake3::hash(&id_data).as_bytes());
+        self.partial_fills = true;
+        self
+    }
+
+    /// Derive the final swap ID from the swap terms, the parent chain, and
+    /// a nonce chosen by the offerer's wallet, so that identical offers have
+    /// distinct IDs. Must be applied after all other modifiers.
+    pub fn with_nonce(mut self, nonce: [u8; 32]) -> Self {
+        let mut id_data = b"coinshift/swap_id".to_vec();
+        id_data.push(SWAP_ID_VERSION);
+        id_data.extend(borsh::to_vec(&self.parent_chain).unwrap());
+        id_data.extend_from_slice(&nonce);
+        id_data.extend_from_slice(&self.id.0);
+        self.id = SwapId(*blake3::hash(&id_data).as_bytes());
+        self
+    }
+
+    /// Current fill of an open offer, if any
+    pub fn current_fill(&self) -> Option<&SwapFill> {
+        self.fills.last()
+    }
+
+    /// L2 address that can claim the swap: the recipient named at creation,
+    /// or the current filler of an open offer
+    pub fn recipient(&self) -> Option<crate::types::Address> {
+        self.l2_recipient
+            .or_else(|| self.current_fill().map(|fill| fill.l2_recipient))
+    }
+
+    /// L1 transaction that the swap waits on: the current filler's, for
+    /// open offers
+    pub fn expected_l1_txid(&self) -> &TxId {
+        self.current_fill()
+            .map_or(&self.l1_txid, |fill| &fill.l1_txid)
+    }
+
+    /// Sidechain height at which the current fill stops reserving the offer
+    pub fn fill_expires_at_height(&self) -> Option<u32> {
+        self.current_fill()
+            .map(|fill| fill.height.saturating_add(self.fill_timeout))
+    }
+
+    /// Whether `fill` is unclaimed, and still reserves its part of the offer
+    /// at sidechain height `height`
+    pub fn is_fill_active(&self, fill: &SwapFill, height: u32) -> bool {
+        fill.l2_amount_claimed.is_none()
+            && height < fill.height.saturating_add(self.fill_timeout)
+    }
+
+    /// Whether `bond` is the bond of a fill that timed out at sidechain
+    /// height `height` before it was claimed. Such bonds are forfeited to
+    /// the offerer.
+    pub fn is_bond_forfeited(
+        &self,
+        bond: &crate::types::OutPoint,
+        height: u32,
+    ) -> bool {
+        self.fills.iter().any(|fill| {
+            fill.bond.as_ref() == Some(bond)
+                && fill.l2_amount_claimed.is_none()
+                && !self.is_fill_active(fill, height)
+        })
+    }
+
+    /// L1 amount that is not yet paid by claimed fills
+    pub fn l1_amount_remaining(&self) -> bitcoin::Amount {
+        self.l1_amount
+            .and_then(|l1_amount| l1_amount.checked_sub(self.l1_amount_filled))
+            .unwrap_or(bitcoin::Amount::ZERO)
+    }
+
+    /// For partially fillable offers: L1 amount that a new fill at sidechain
+    /// height `height` can pay, excluding the parts reserved by active fills
+    pub fn l1_amount_fillable(&self, height: u32) -> bitcoin::Amount {
+        let reserved: bitcoin::Amount = self
+            .fills
+            .iter()
+            .filter(|fill| self.is_fill_active(fill, height))
+            .filter_map(|fill| fill.l1_amount)
+            .sum();
+        self.l1_amount_remaining()
+            .checked_sub(reserved)
+            .unwrap_or(bitcoin::Amount::ZERO)
+    }
+
+    /// For partially fillable offers: amount of the L2 asset released by a
+    /// fill that pays `fill_l1_amount`. This is the pro-rata part of
+    /// `l2_amount`, rounded down, or everything that remains for the fill
+    /// that completes the offer.
+    pub fn fill_l2_amount(
+        &self,
+        fill_l1_amount: bitcoin::Amount,
+    ) -> bitcoin::Amount {
+        let l1_amount = self.l1_amount.unwrap_or(bitcoin::Amount::ZERO);
+        if fill_l1_amount >= self.l1_amount_remaining() {
+            return self.l2_amount_remaining;
+        }
+        /* l2_amount * (fill_l1_amount / l1_amount) */
+        let amount = (self.l2_amount.to_sat() as u128
+            * fill_l1_amount.to_sat() as u128)
+            / l1_amount.to_sat() as u128;
+        // This is safe, as `fill_l1_amount < l1_amount`
+        bitcoin::Amount::from_sat(amount as u64).min(self.l2_amount_remaining)
+    }
+
+    /// For partially fillable offers: index of the fill that can claim the
+    /// swap with a proof of `l1_txid`
+    fn claimable_fill_index(&self, l1_txid: &TxId) -> Option<usize> {
+        if !self.partial_fills {
+            return None;
+        }
+        // Fills cannot share an L1 txid, so that each L1 payment is claimed
+        // at most once
+        let index = self
+            .fills
+            .iter()
+            .position(|fill| fill.l1_txid == *l1_txid)?;
+        let fill = &self.fills[index];
+        (fill.l2_amount_claimed.is_none()
+            && fill.l1_amount.is_some_and(|fill_l1_amount| {
+                fill_l1_amount <= self.l1_amount_remaining()
+            }))
+        .then_some(index)
+    }
+
+    /// For partially fillable offers: the fill that can claim the swap with
+    /// a proof of `l1_txid`
+    pub fn claimable_fill(&self, l1_txid: &TxId) -> Option<&SwapFill> {
+        self.claimable_fill_index(l1_txid)
+            .map(|index| &self.fills[index])
+    }
+
+    /// L2 address that can claim the swap with a proof of `l1_txid`: the
+    /// filler that registered `l1_txid`, for partially fillable offers
+    pub fn claimant(&self, l1_txid: &TxId) -> Option<crate::types::Address> {
+        if self.partial_fills {
+            self.claimable_fill(l1_txid).map(|fill| fill.l2_recipient)
+        } else {
+            self.recipient()
         }
     }
 
-    /// Update L1 transaction ID for L2ToL1 swaps (when Bob fills the swap)
-    pub fn set_l1_txid(&mut self, l1_txid: TxId) -> Result<(), SwapError> {
+    /// Whether the swap has expired at sidechain height `height`.
+    /// Expired swaps can no longer be filled or claimed, and the offerer can
+    /// refund the locked outputs.
+    pub fn is_expired(&self, height: u32) -> bool {
+        self.expires_at_height
+            .is_some_and(|expires_at| height >= expires_at)
+    }
+
+    /// Whether a `SwapFill` at sidechain height `height` can fill the swap
+    pub fn is_fillable(&self, height: u32) -> bool {
+        self.l2_recipient.is_none()
+            && !self.is_expired(height)
+            && matches!(
+                self.state,
+                SwapState::Pending
+                    | SwapState::WaitingConfirmations { .. }
+                    | SwapState::ReadyToClaim
+            )
+            && if self.partial_fills {
+                self.l1_amount_fillable(height) > bitcoin::Amount::ZERO
+            } else {
+                self.fill_expires_at_height()
+                    .is_none_or(|expires_at| height >= expires_at)
+            }
+    }
+
+    /// Update L1 transaction ID for L2ToL1 swaps (when Bob fills the swap),
+    /// with the payment proof for chains with private outputs
+    pub fn set_l1_txid(
+        &mut self,
+        l1_txid: TxId,
+        l1_payment_proof: Option<String>,
+    ) -> Result<(), SwapError> {
         if self.direction != SwapDirection::L2ToL1 {
             return Err(SwapError::InvalidStateTransition);
         }
@@ -165,6 +610,7 @@ impl Swap {
             return Err(SwapError::InvalidStateTransition);
         }
         self.l1_txid = l1_txid;
+        self.l1_payment_proof = l1_payment_proof;
         Ok(())
     }
 
@@ -187,39 +633,114 @@ impl Swap {
         )
     }
 
-    /// Update swap state based on current L1 transaction status
+    /// Check that `tx` pays the L1 recipient of an L2 → L1 swap the L1
+    /// amount that the swap waits on: the current fill's part, for
+    /// partially fillable offers
+    pub fn check_l1_payment(
+        &self,
+        tx: &ParentChainTx,
+    ) -> Result<(), SwapError> {
+        let (Some(l1_recipient_address), Some(l1_amount)) =
+            (&self.l1_recipient_address, self.l1_amount)
+        else {
+            return Ok(());
+        };
+        let required = self
+            .current_fill()
+            .and_then(|fill| fill.l1_amount)
+            .unwrap_or(l1_amount);
+        let Some(paid) =
+            tx.amount_paid_to(&self.parent_chain, None, l1_recipient_address)
+        else {
+            return Err(SwapError::L1AmountOverflow {
+                l1_txid: tx.txid.clone(),
+                l1_recipient_address: l1_recipient_address.clone(),
+            });
+        };
+        if paid < required {
+            return Err(SwapError::L1PaymentMismatch {
+                l1_txid: tx.txid.clone(),
+                l1_recipient_address: l1_recipient_address.clone(),
+                required,
+                paid,
+            });
+        }
+        Ok(())
+    }
+
+    /// Update swap state based on current L1 transaction status.
+    /// If the L1 transaction was reorged out of the block that included it,
+    /// the swap is demoted to `WaitingConfirmations`, or to `Pending` if the
+    /// transaction is gone, and the reorg is returned.
     pub async fn update_state(
         &mut self,
         client: &ParentChainClient,
         current_height: u32,
-    ) -> Result<(), SwapError> {
+    ) -> Result<Option<SwapEvent>, SwapError> {
         // Check if expired
-        if let Some(expires_at) = self.expires_at_height {
-            if current_height >= expires_at {
-                self.state = SwapState::Cancelled;
-                return Ok(());
-            }
+        if self.is_expired(current_height) {
+            self.state = SwapState::Cancelled;
+            return Ok(None);
         }
 
         // Check L1 transaction status
-        let tx_info = client
+        let client = client
             .get_client(&self.parent_chain)
-            .ok_or(SwapError::ChainNotConfigured(self.parent_chain.clone()))?
-            .get_transaction(&self.l1_txid)
-            .await
-            .map_err(|e| SwapError::ClientError(e.to_string()))?;
+            .ok_or(SwapError::ChainNotConfigured(self.parent_chain.clone()))?;
+        let l1_txid = self.expected_l1_txid().clone();
+        let tx_info = match (&self.l1_recipient_address, &self.l1_payment_proof)
+        {
+            (Some(address), Some(payment_proof)) => {
+                client
+                    .get_transaction_with_proof(
+                        &l1_txid,
+                        address,
+                        payment_proof,
+                    )
+                    .await
+            }
+            // Monero payments can only be checked with a payment proof
+            _ if self.parent_chain == ParentChainType::Xmr => return Ok(None),
+            _ => client.get_transaction(&l1_txid).await,
+        }
+        .map_err(|e| SwapError::ClientError(e.to_string()))?;
 
         match tx_info {
             None => {
                 // Transaction not found yet
                 if matches!(self.state, SwapState::Pending) {
                     // Stay in pending
-                } else {
-                    // If we were waiting but tx disappeared, something is wrong
-                    return Err(SwapError::TransactionDisappeared);
+                } else if matches!(
+                    self.state,
+                    SwapState::WaitingConfirmations { .. }
+                        | SwapState::ReadyToClaim
+                ) {
+                    // The transaction was reorged out, and dropped or
+                    // double spent
+                    self.state = SwapState::Pending;
+                    return Ok(Some(SwapEvent::L1Reorg {
+                        swap_id: self.id.clone(),
+                        l1_txid,
+                        orphaned_block_hash: self.l1_block_hash.take(),
+                        state: self.state.clone(),
+                    }));
                 }
             }
             Some(tx) => {
+                // An unrelated transaction cannot advance the swap
+                let () = self.check_l1_payment(&tx)?;
+                // The including block changes if the transaction is reorged
+                // out, whether or not it is included again
+                let orphaned_block_hash = self
+                    .l1_block_hash
+                    .clone()
+                    .filter(|seen| tx.block_hash.as_ref() != Some(seen));
+                if !matches!(
+                    self.state,
+                    SwapState::Completed | SwapState::Cancelled
+                ) {
+                    self.l1_block_hash = tx.block_hash.clone();
+                }
                 match self.state {
                     SwapState::Pending => {
                         // Transaction found, now waiting for confirmations
@@ -242,13 +763,115 @@ impl Swap {
                             };
                         }
                     }
-                    SwapState::ReadyToClaim | SwapState::Completed | SwapState::Cancelled => {
+                    SwapState::ReadyToClaim => {
+                        // A reorg can take confirmations away
+                        let required_confirmations =
+                            self.required_confirmations;
+                        if tx.confirmations < required_confirmations {
+                            self.state = SwapState::WaitingConfirmations {
+                                current_confirmations: tx.confirmations,
+                                required_confirmations,
+                            };
+                        }
+                    }
+                    SwapState::Completed | SwapState::Cancelled => {
                         // Already in final state
+                        return Ok(None);
                     }
                 }
+                if orphaned_block_hash.is_some() {
+                    return Ok(Some(SwapEvent::L1Reorg {
+                        swap_id: self.id.clone(),
+                        l1_txid,
+                        orphaned_block_hash,
+                        state: self.state.clone(),
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Mark swap as completed by a claim that proved `l1_txid` on L1.
+    /// Unlike [`Self::mark_completed`], this does not depend on the locally
+    /// observed swap state, since the claim carries its own proof.
+    pub fn mark_claimed(&mut self, l1_txid: TxId) -> Result<(), SwapError> {
+        match self.state {
+            SwapState::Completed | SwapState::Cancelled => {
+                Err(SwapError::InvalidStateTransition)
+            }
+            SwapState::Pending
+            | SwapState::WaitingConfirmations { .. }
+            | SwapState::ReadyToClaim => {
+                self.l1_txid = l1_txid;
+                self.state = SwapState::Completed;
+                Ok(())
             }
         }
+    }
 
+    /// Claim the swap with a proof of `l1_txid`, returning the amount of the
+    /// L2 asset that is released.
+    /// Partially fillable offers release the claiming fill's part, and are
+    /// completed once nothing remains in escrow.
+    pub fn claim(
+        &mut self,
+        l1_txid: TxId,
+    ) -> Result<bitcoin::Amount, SwapError> {
+        if !self.partial_fills {
+            let () = self.mark_claimed(l1_txid)?;
+            let released = std::mem::replace(
+                &mut self.l2_amount_remaining,
+                bitcoin::Amount::ZERO,
+            );
+            // Only the current fill of an open offer can claim it
+            if let Some(fill) = self.fills.last_mut() {
+                fill.l2_amount_claimed = Some(released);
+            }
+            return Ok(released);
+        }
+        if matches!(self.state, SwapState::Completed | SwapState::Cancelled) {
+            return Err(SwapError::InvalidStateTransition);
+        }
+        let index = self
+            .claimable_fill_index(&l1_txid)
+            .ok_or(SwapError::NoClaimableFill)?;
+        let fill_l1_amount =
+            self.fills[index].l1_amount.unwrap_or(bitcoin::Amount::ZERO);
+        let released = self.fill_l2_amount(fill_l1_amount);
+        self.fills[index].l2_amount_claimed = Some(released);
+        self.l1_amount_filled += fill_l1_amount;
+        self.l2_amount_remaining -= released;
+        if self.l2_amount_remaining == bitcoin::Amount::ZERO {
+            let () = self.mark_claimed(l1_txid)?;
+        }
+        Ok(released)
+    }
+
+    /// Revert the amounts released by a claim of the swap with a proof of
+    /// `l1_txid`. The state and L1 txid that the claim replaced are not
+    /// known to the swap, and must be restored by the caller.
+    pub fn revert_claim(&mut self, l1_txid: &TxId) -> Result<(), SwapError> {
+        if self.partial_fills {
+            let fill = self
+                .fills
+                .iter_mut()
+                .find(|fill| fill.l1_txid == *l1_txid)
+                .ok_or(SwapError::NoClaimableFill)?;
+            let released = fill
+                .l2_amount_claimed
+                .take()
+                .ok_or(SwapError::InvalidStateTransition)?;
+            self.l1_amount_filled -=
+                fill.l1_amount.unwrap_or(bitcoin::Amount::ZERO);
+            self.l2_amount_remaining += released;
+        } else {
+            if let Some(fill) = self.fills.last_mut() {
+                fill.l2_amount_claimed = None;
+            }
+            self.l2_amount_remaining = self.l2_amount;
+        }
         Ok(())
     }
 
@@ -267,15 +890,23 @@ impl Swap {
 /// Manager for active swaps
 pub struct SwapManager {
     pub(crate) swaps: HashMap<SwapId, Swap>,
+    events: tokio::sync::broadcast::Sender<SwapEvent>,
 }
 
 impl SwapManager {
     pub fn new() -> Self {
+        let (events, _) = tokio::sync::broadcast::channel(SWAP_EVENT_CAPACITY);
         Self {
             swaps: HashMap::new(),
+            events,
         }
     }
 
+    /// Subscribe to events from [`Self::update_all_swaps`]
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<SwapEvent> {
+        self.events.subscribe()
+    }
+
     pub fn create_swap(
         &mut self,
         parent_chain: ParentChainType,
@@ -306,15 +937,83 @@ impl SwapManager {
         self.swaps.get_mut(id)
     }
 
-    pub fn update_all_swaps(
-        &mut self,
+    /// Poll the parent chains for every swap that has not yet finished, and
+    /// update the swap states.
+    ///
+    /// The manager is only locked to snapshot the swaps and to write back the
+    /// results, so that RPC handlers are not blocked on parent chain requests.
+    /// Swaps are polled concurrently, so a slow node for one chain does not
+    /// delay updates for the others. A swap that was modified while it was
+    /// being polled is left as is, and picked up again on the next update.
+    /// Swaps whose L1 transaction was reorged out are demoted, and a
+    /// [`SwapEvent`] is sent to subscribers.
+    ///
+    /// Returns the IDs of the swaps that were updated.
+    pub async fn update_all_swaps(
+        this: &tokio::sync::Mutex<Self>,
         client: &ParentChainClient,
         current_height: u32,
-    ) -> Result<(), SwapError> {
-        for swap in self.swaps.values_mut() {
-            swap.update_state(client, current_height).ok();
+    ) -> Vec<SwapId> {
+        let snapshot: Vec<Swap> = this
+            .lock()
+            .await
+            .swaps
+            .values()
+            .filter(|swap| {
+                !matches!(
+                    swap.state,
+                    SwapState::Completed | SwapState::Cancelled
+                )
+            })
+            .cloned()
+            .collect();
+        let updated: Vec<(Swap, Swap, Option<SwapEvent>)> =
+            futures::stream::iter(snapshot)
+                .map(|swap| async move {
+                    let mut updated = swap.clone();
+                    let event = match updated
+                        .update_state(client, current_height)
+                        .await
+                    {
+                        Ok(event) => event,
+                        Err(
+                            err @ (SwapError::L1PaymentMismatch { .. }
+                            | SwapError::L1AmountOverflow { .. }),
+                        ) => {
+                            tracing::warn!(swap_id = ?swap.id, "{err}");
+                            None
+                        }
+                        Err(err) => {
+                            tracing::debug!(swap_id = ?swap.id, "{err}");
+                            None
+                        }
+                    };
+                    (swap, updated, event)
+                })
+                .buffer_unordered(MAX_CONCURRENT_SWAP_UPDATES)
+                .filter(|(swap, updated, _)| {
+                    std::future::ready(swap != updated)
+                })
+                .collect()
+                .await;
+        let mut this = this.lock().await;
+        let mut updated_ids = Vec::new();
+        for (swap, updated, event) in updated {
+            let Some(current) = this.swaps.get_mut(&swap.id) else {
+                continue;
+            };
+            if *current != swap {
+                continue;
+            }
+            *current = updated;
+            updated_ids.push(swap.id);
+            if let Some(event) = event {
+                tracing::warn!(?event, "L1 transaction was reorged out");
+                // Events are dropped if nothing is subscribed
+                let _: Result<usize, _> = this.events.send(event);
+            }
         }
-        Ok(())
+        updated_ids
     }
 }
 
@@ -330,14 +1029,30 @@ pub enum SwapError {
     ChainNotConfigured(ParentChainType),
     #[error("Client error: {0}")]
     ClientError(String),
-    #[error("Transaction disappeared from chain")]
-    TransactionDisappeared,
     #[error("Invalid state transition")]
     InvalidStateTransition,
     #[error("Swap not found")]
     SwapNotFound,
     #[error("Swap expired")]
     SwapExpired,
+    #[error("No fill can claim the swap with this L1 transaction")]
+    NoClaimableFill,
+    #[error(
+        "L1 transaction {l1_txid:?} pays {paid} to {l1_recipient_address}, but {required} is required"
+    )]
+    L1PaymentMismatch {
+        l1_txid: TxId,
+        l1_recipient_address: String,
+        required: bitcoin::Amount,
+        paid: bitcoin::Amount,
+    },
+    #[error(
+        "L1 transaction {l1_txid:?} pays {l1_recipient_address} an amount that does not fit in 64 bits"
+    )]
+    L1AmountOverflow {
+        l1_txid: TxId,
+        l1_recipient_address: String,
+    },
 }
 
 // Helper trait for TxId to get hash bytes
@@ -357,7 +1072,13 @@ impl TxIdHash for TxId {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Address;
+    use crate::{
+        parent_chain::{
+            MockChain, MockTx, address,
+            client::{L1Destination, ParentChainTxOutput},
+        },
+        types::{Address, BitAssetId},
+    };
 
     fn create_test_address() -> Address {
         Address([0u8; 32])
@@ -474,6 +1195,30 @@ mod tests {
         assert!(matches!(swap.state, SwapState::Completed));
     }
 
+    #[test]
+    fn test_swap_mark_claimed() {
+        let mut swap = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            "bc1qtest".to_string(),
+            bitcoin::Amount::from_sat(50_000),
+            create_test_address(),
+            bitcoin::Amount::from_sat(100_000),
+            create_test_address(),
+            None,
+            100,
+        );
+        let l1_txid = create_test_txid();
+
+        // A claim carries its own L1 proof, so it does not depend on the
+        // locally observed state
+        assert!(swap.mark_claimed(l1_txid.clone()).is_ok());
+        assert!(matches!(swap.state, SwapState::Completed));
+        assert_eq!(swap.l1_txid, l1_txid);
+
+        // Can't claim twice
+        assert!(swap.mark_claimed(l1_txid).is_err());
+    }
+
     #[test]
     fn test_swap_manager_create() {
         let mut manager = SwapManager::new();
@@ -506,8 +1251,199 @@ mod tests {
 
         // At height 149, should not be expired
         // At height 150, should be expired
-        // We can't test this without a mock client, but the logic is there
         assert_eq!(swap.expires_at_height, Some(150));
+        assert!(!swap.is_expired(149));
+        assert!(swap.is_expired(150));
+    }
+
+    #[tokio::test]
+    async fn test_update_all_swaps_mock_chain() {
+        let l1_address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let mut config = crate::parent_chain::ParentChainConfig::new();
+        config.set_chain(
+            ParentChainType::Btc,
+            url::Url::parse("mock://update_all_swaps").unwrap(),
+            None,
+            None,
+        );
+        let client = ParentChainClient::new(&config).unwrap();
+        let chain = MockChain::shared(ParentChainType::Btc, "update_all_swaps");
+
+        let mut swap = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            l1_address.to_string(),
+            bitcoin::Amount::from_sat(50_000),
+            create_test_address(),
+            bitcoin::Amount::from_sat(100_000),
+            create_test_address(),
+            Some(2),
+            100,
+        );
+        let l1_tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn::default()],
+            output: vec![bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(50_000),
+                script_pubkey: address::script_pubkey(
+                    &ParentChainType::Btc,
+                    l1_address,
+                )
+                .unwrap(),
+            }],
+        };
+        let l1_txid = chain.lock().unwrap().broadcast(MockTx::Bitcoin(l1_tx));
+        swap.set_l1_txid(l1_txid, None).unwrap();
+        let swap_id = swap.id.clone();
+        let manager = tokio::sync::Mutex::new(SwapManager::new());
+        manager.lock().await.swaps.insert(swap_id.clone(), swap);
+
+        let mut expected_states = vec![
+            // Seen in the mempool
+            SwapState::WaitingConfirmations {
+                current_confirmations: 0,
+                required_confirmations: 2,
+            },
+            SwapState::WaitingConfirmations {
+                current_confirmations: 1,
+                required_confirmations: 2,
+            },
+            SwapState::ReadyToClaim,
+        ]
+        .into_iter();
+        loop {
+            let updated =
+                SwapManager::update_all_swaps(&manager, &client, 100).await;
+            assert_eq!(updated, vec![swap_id.clone()]);
+            let state = manager.lock().await.swaps[&swap_id].state.clone();
+            let ready = matches!(state, SwapState::ReadyToClaim);
+            assert_eq!(Some(state), expected_states.next());
+            if ready {
+                break;
+            }
+            chain.lock().unwrap().mine(1);
+        }
+
+        // A parent chain outage leaves swaps as they are
+        chain.lock().unwrap().set_offline(true);
+        let updated =
+            SwapManager::update_all_swaps(&manager, &client, 100).await;
+        assert!(updated.is_empty());
+        assert_eq!(
+            manager.lock().await.swaps[&swap_id].state,
+            SwapState::ReadyToClaim
+        );
+        chain.lock().unwrap().set_offline(false);
+    }
+
+    #[tokio::test]
+    async fn test_update_all_swaps_l1_reorg() {
+        let l1_address = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+        let mut config = crate::parent_chain::ParentChainConfig::new();
+        config.set_chain(
+            ParentChainType::Btc,
+            url::Url::parse("mock://l1_reorg").unwrap(),
+            None,
+            None,
+        );
+        let client = ParentChainClient::new(&config).unwrap();
+        let chain = MockChain::shared(ParentChainType::Btc, "l1_reorg");
+
+        let mut swap = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            l1_address.to_string(),
+            bitcoin::Amount::from_sat(50_000),
+            create_test_address(),
+            bitcoin::Amount::from_sat(100_000),
+            create_test_address(),
+            Some(2),
+            100,
+        );
+        let l1_tx = bitcoin::Transaction {
+            version: bitcoin::transaction::Version::TWO,
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![bitcoin::TxIn::default()],
+            output: vec![bitcoin::TxOut {
+                value: bitcoin::Amount::from_sat(50_000),
+                script_pubkey: address::script_pubkey(
+                    &ParentChainType::Btc,
+                    l1_address,
+                )
+                .unwrap(),
+            }],
+        };
+        let l1_txid = chain.lock().unwrap().broadcast(MockTx::Bitcoin(l1_tx));
+        swap.set_l1_txid(l1_txid.clone(), None).unwrap();
+        let swap_id = swap.id.clone();
+        let manager = tokio::sync::Mutex::new(SwapManager::new());
+        manager.lock().await.swaps.insert(swap_id.clone(), swap);
+        let mut events = manager.lock().await.subscribe();
+
+        SwapManager::update_all_swaps(&manager, &client, 100).await;
+        chain.lock().unwrap().mine(2);
+        SwapManager::update_all_swaps(&manager, &client, 100).await;
+        let block_hash = chain
+            .lock()
+            .unwrap()
+            .get_transaction(&l1_txid)
+            .unwrap()
+            .block_hash;
+        {
+            let manager = manager.lock().await;
+            assert_eq!(manager.swaps[&swap_id].state, SwapState::ReadyToClaim);
+            assert_eq!(manager.swaps[&swap_id].l1_block_hash, block_hash);
+        }
+        assert!(events.try_recv().is_err());
+
+        // Reorging out the including block takes the swap's confirmations
+        // away
+        chain.lock().unwrap().reorg(2);
+        SwapManager::update_all_swaps(&manager, &client, 100).await;
+        let waiting = SwapState::WaitingConfirmations {
+            current_confirmations: 0,
+            required_confirmations: 2,
+        };
+        assert_eq!(manager.lock().await.swaps[&swap_id].state, waiting);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SwapEvent::L1Reorg {
+                swap_id: swap_id.clone(),
+                l1_txid: l1_txid.clone(),
+                orphaned_block_hash: block_hash,
+                state: waiting,
+            }
+        );
+
+        // Once confirmed again, a payment that is double spent in a reorg
+        // sends the swap back to pending
+        chain.lock().unwrap().mine(1);
+        SwapManager::update_all_swaps(&manager, &client, 100).await;
+        let block_hash = chain
+            .lock()
+            .unwrap()
+            .get_transaction(&l1_txid)
+            .unwrap()
+            .block_hash;
+        assert!(events.try_recv().is_err());
+        {
+            let mut chain = chain.lock().unwrap();
+            chain.reorg(1);
+            assert!(chain.evict(&l1_txid));
+            chain.mine(2);
+        }
+        SwapManager::update_all_swaps(&manager, &client, 100).await;
+        let swap = manager.lock().await.swaps[&swap_id].clone();
+        assert_eq!(swap.state, SwapState::Pending);
+        assert_eq!(swap.l1_block_hash, None);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SwapEvent::L1Reorg {
+                swap_id,
+                l1_txid,
+                orphaned_block_hash: block_hash,
+                state: SwapState::Pending,
+            }
+        );
     }
 
     #[test]
@@ -558,7 +1494,8 @@ mod tests {
         assert_eq!(swap.l1_recipient_address, Some(alice_btc_address.clone()));
         assert_eq!(swap.l1_amount, Some(l1_amount));
         assert_eq!(swap.l2_amount, l2_amount);
-        assert_eq!(swap.l2_recipient, bob_l2_address);
+        assert_eq!(swap.l2_recipient, Some(bob_l2_address));
+        assert_eq!(swap.l2_offerer, Some(alice_l2_address));
         assert!(matches!(swap.state, SwapState::Pending));
         assert_eq!(swap.required_confirmations, 3);
 
@@ -568,7 +1505,7 @@ mod tests {
         let bob_btc_txid = TxId::Hash32([0x42u8; 32]); // Bob's BTC transaction ID
         
         // Update swap with Bob's transaction
-        assert!(swap.set_l1_txid(bob_btc_txid.clone()).is_ok());
+        assert!(swap.set_l1_txid(bob_btc_txid.clone(), None).is_ok());
         assert_eq!(swap.l1_txid, bob_btc_txid);
 
         // Step 3: Simulate transaction being detected and confirmed
@@ -602,7 +1539,7 @@ mod tests {
 
         // Verify final state
         assert_eq!(swap.l1_txid, bob_btc_txid);
-        assert_eq!(swap.l2_recipient, bob_l2_address); // Bob receives the coins
+        assert_eq!(swap.l2_recipient, Some(bob_l2_address)); // Bob receives the coins
         assert_eq!(swap.l2_amount, l2_amount);
     }
 
@@ -641,6 +1578,56 @@ mod tests {
         assert_eq!(swap1.id, swap2.id);
     }
 
+    #[test]
+    fn test_swap_id_nonce() {
+        let swap = |parent_chain, l2_amount| {
+            Swap::new_l2_to_l1(
+                parent_chain,
+                "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string(),
+                bitcoin::Amount::from_sat(100_000),
+                Address([1u8; 32]),
+                bitcoin::Amount::from_sat(l2_amount),
+                Address([2u8; 32]),
+                Some(3),
+                1000,
+            )
+        };
+        let nonce = [7u8; 32];
+        let id = swap(ParentChainType::Btc, 100_000).with_nonce(nonce).id;
+
+        // Repeated identical offers have distinct IDs
+        assert_eq!(
+            swap(ParentChainType::Btc, 100_000).with_nonce(nonce).id,
+            id
+        );
+        assert_ne!(
+            swap(ParentChainType::Btc, 100_000).with_nonce([8u8; 32]).id,
+            id
+        );
+        // The ID commits to the parent chain and the L2 amount
+        assert_ne!(
+            swap(ParentChainType::Ltc, 100_000).with_nonce(nonce).id,
+            id
+        );
+        assert_ne!(
+            swap(ParentChainType::Btc, 200_000).with_nonce(nonce).id,
+            id
+        );
+        // ...also for legacy L1 → L2 swaps
+        let legacy = |l2_amount| {
+            Swap::new(
+                ParentChainType::Btc,
+                create_test_txid(),
+                None,
+                create_test_address(),
+                bitcoin::Amount::from_sat(l2_amount),
+                1000,
+            )
+            .id
+        };
+        assert_ne!(legacy(100_000), legacy(200_000));
+    }
+
     #[test]
     fn test_l2_to_l1_cannot_set_txid_after_pending() {
         let mut swap = Swap::new_l2_to_l1(
@@ -655,11 +1642,253 @@ mod tests {
         );
 
         // Can set txid when pending
-        assert!(swap.set_l1_txid(TxId::Hash32([1u8; 32])).is_ok());
+        assert!(swap.set_l1_txid(TxId::Hash32([1u8; 32]), None).is_ok());
 
         // Cannot set txid after state changes
         swap.state = SwapState::ReadyToClaim;
-        assert!(swap.set_l1_txid(TxId::Hash32([2u8; 32])).is_err());
+        assert!(swap.set_l1_txid(TxId::Hash32([2u8; 32]), None).is_err());
+    }
+
+    #[test]
+    fn test_open_offer_fill_timeout() {
+        let mut swap = Swap::new_open(
+            ParentChainType::Btc,
+            "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string(),
+            bitcoin::Amount::from_sat(100_000),
+            bitcoin::Amount::from_sat(100_000),
+            bitcoin::Amount::from_sat(1_000),
+            10,
+            Some(3),
+            1000,
+        );
+        assert_eq!(swap.recipient(), None);
+        assert!(swap.is_fillable(1000));
+
+        // Bob fills the offer, reserving it for 10 blocks
+        let bob_l2_address = Address([2u8; 20]);
+        swap.fills.push(SwapFill {
+            l2_recipient: bob_l2_address,
+            l1_txid: TxId::Hash32([1u8; 32]),
+            bond: None,
+            height: 1001,
+            l1_amount: None,
+            l2_amount_claimed: None,
+        });
+        assert_eq!(swap.recipient(), Some(bob_l2_address));
+        assert_eq!(swap.expected_l1_txid(), &TxId::Hash32([1u8; 32]));
+        assert!(!swap.is_fillable(1010));
+        assert!(swap.is_fillable(1011));
+
+        // Completed swaps cannot be filled again
+        swap.state = SwapState::Completed;
+        assert!(!swap.is_fillable(1011));
+    }
+
+    #[test]
+    fn test_swap_asset() {
+        let bitasset_id = BitAssetId([3u8; 32]);
+        let bitasset = AssetId::BitAsset(bitasset_id);
+        let swap = Swap::new_open(
+            ParentChainType::Ltc,
+            "ltc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string(),
+            bitcoin::Amount::from_sat(1_000_000),
+            bitcoin::Amount::from_sat(100),
+            bitcoin::Amount::ZERO,
+            10,
+            None,
+            1000,
+        );
+
+        // Offers for other assets have distinct IDs
+        let bitcoin_swap = swap.clone().with_l2_asset(SwapAsset::BITCOIN);
+        assert_eq!(bitcoin_swap.id, swap.id);
+        let bitasset_swap = swap.clone().with_l2_asset(bitasset.into());
+        assert_ne!(bitasset_swap.id, swap.id);
+        assert_eq!(bitasset_swap.l2_asset, SwapAsset::Asset(bitasset));
+
+        // Escrow outputs are recognized by their asset
+        let content = FilledOutputContent::BitAsset(bitasset_id, 100);
+        assert_eq!(
+            SwapAsset::of_output_content(&content),
+            Some((SwapAsset::Asset(bitasset), 100))
+        );
+        let lp_token = SwapAsset::AmmLpToken {
+            asset0: AssetId::Bitcoin,
+            asset1: bitasset,
+        };
+        let content = FilledOutputContent::AmmLpToken {
+            asset0: AssetId::Bitcoin,
+            asset1: bitasset,
+            amount: 7,
+        };
+        assert_eq!(SwapAsset::of_output_content(&content), Some((lp_token, 7)));
+        assert_eq!(lp_token.output_content(7), OutputContent::AmmLpToken(7));
+    }
+
+    #[test]
+    fn test_check_l1_payment() {
+        let alice_btc_address = "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh";
+        let swap = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            alice_btc_address.to_string(),
+            bitcoin::Amount::from_sat(100_000),
+            Address([1u8; 20]),
+            bitcoin::Amount::from_sat(50_000),
+            Address([2u8; 20]),
+            Some(3),
+            1000,
+        );
+        let alice_script = crate::parent_chain::address::script_pubkey(
+            &ParentChainType::Btc,
+            alice_btc_address,
+        )
+        .unwrap();
+        let tx = |outputs: Vec<(bitcoin::ScriptBuf, u64)>| ParentChainTx {
+            txid: create_test_txid(),
+            confirmations: 1,
+            block_hash: None,
+            block_height: None,
+            outputs: outputs
+                .into_iter()
+                .map(|(script_pubkey, amount)| ParentChainTxOutput {
+                    destination: L1Destination::Script(script_pubkey),
+                    token: None,
+                    amount: Some(bitcoin::Amount::from_sat(amount)),
+                })
+                .collect(),
+        };
+
+        // Payments to Alice can be split across outputs
+        let paying_tx = tx(vec![
+            (alice_script.clone(), 60_000),
+            (alice_script.clone(), 40_000),
+        ]);
+        assert!(swap.check_l1_payment(&paying_tx).is_ok());
+
+        // Paying an amount that does not fit in 64 bits is rejected, while
+        // such an amount to someone else does not matter
+        let mut overflowing_tx = paying_tx.clone();
+        overflowing_tx.outputs[1].amount = None;
+        assert!(matches!(
+            swap.check_l1_payment(&overflowing_tx),
+            Err(SwapError::L1AmountOverflow { .. })
+        ));
+        let mut unrelated_overflow_tx = paying_tx.clone();
+        unrelated_overflow_tx.outputs.push(ParentChainTxOutput {
+            destination: L1Destination::Script(bitcoin::ScriptBuf::new()),
+            token: None,
+            amount: None,
+        });
+        assert!(swap.check_l1_payment(&unrelated_overflow_tx).is_ok());
+
+        // Underpaying, or paying someone else, does not count
+        let underpaying_tx = tx(vec![(alice_script, 99_999)]);
+        assert!(matches!(
+            swap.check_l1_payment(&underpaying_tx),
+            Err(SwapError::L1PaymentMismatch { paid, .. })
+                if paid.to_sat() == 99_999
+        ));
+        let unrelated_tx = tx(vec![(bitcoin::ScriptBuf::new(), 100_000)]);
+        assert!(matches!(
+            swap.check_l1_payment(&unrelated_tx),
+            Err(SwapError::L1PaymentMismatch { paid, .. })
+                if paid == bitcoin::Amount::ZERO
+        ));
+    }
+
+    #[test]
+    fn test_check_l1_payment_ignores_tokens() {
+        const ALICE: &str = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let swap = Swap::new_l2_to_l1(
+            ParentChainType::Eth,
+            ALICE.to_string(),
+            bitcoin::Amount::from_sat(50_000_000),
+            Address([1u8; 20]),
+            bitcoin::Amount::from_sat(50_000),
+            Address([2u8; 20]),
+            Some(3),
+            1000,
+        );
+        let tx = |token: Option<&str>| ParentChainTx {
+            txid: create_test_txid(),
+            confirmations: 1,
+            block_hash: None,
+            block_height: None,
+            outputs: vec![ParentChainTxOutput {
+                destination: L1Destination::Address(ALICE.to_owned()),
+                token: token.map(str::to_owned),
+                amount: Some(bitcoin::Amount::from_sat(50_000_000)),
+            }],
+        };
+
+        // Paying in USDC does not pay a swap in ETH
+        assert!(swap.check_l1_payment(&tx(None)).is_ok());
+        assert!(swap.check_l1_payment(&tx(Some(USDC))).is_err());
+    }
+
+    #[test]
+    fn test_partial_fills() {
+        let open_swap = Swap::new_open(
+            ParentChainType::Btc,
+            "bc1qxy2kgdygjrsqtzq2n0yrf2493p83kkfjhx0wlh".to_string(),
+            bitcoin::Amount::from_sat(300),
+            bitcoin::Amount::from_sat(1_000),
+            bitcoin::Amount::ZERO,
+            10,
+            Some(3),
+            1000,
+        );
+        let mut swap = open_swap.clone().with_partial_fills();
+        assert_ne!(swap.id, open_swap.id);
+        let fill = |l2_recipient, l1_txid: u8, l1_amount, height| SwapFill {
+            l2_recipient,
+            l1_txid: TxId::Hash32([l1_txid; 32]),
+            bond: None,
+            height,
+            l1_amount: Some(bitcoin::Amount::from_sat(l1_amount)),
+            l2_amount_claimed: None,
+        };
+
+        // Bob and Carol each fill a part of the offer
+        let bob_l2_address = Address([2u8; 20]);
+        let carol_l2_address = Address([3u8; 20]);
+        swap.fills.push(fill(bob_l2_address, 1, 100, 1001));
+        swap.fills.push(fill(carol_l2_address, 2, 150, 1002));
+        assert_eq!(swap.l1_amount_fillable(1002).to_sat(), 50);
+        assert!(swap.is_fillable(1002));
+        assert_eq!(
+            swap.claimant(&TxId::Hash32([2u8; 32])),
+            Some(carol_l2_address)
+        );
+        assert_eq!(swap.claimant(&TxId::Hash32([3u8; 32])), None);
+
+        // Bob's part is a third of the L2 amount, rounded down
+        let released = swap.claim(TxId::Hash32([1u8; 32])).unwrap();
+        assert_eq!(released.to_sat(), 333);
+        assert_eq!(swap.l1_amount_filled.to_sat(), 100);
+        assert_eq!(swap.l2_amount_remaining.to_sat(), 667);
+        assert_eq!(swap.state, SwapState::Pending);
+        // Each L1 payment is claimed at most once
+        assert!(swap.claim(TxId::Hash32([1u8; 32])).is_err());
+
+        // Dave takes the rest, and completes the offer when he claims
+        let dave_l2_address = Address([4u8; 20]);
+        swap.fills.push(fill(dave_l2_address, 3, 50, 1003));
+        assert!(!swap.is_fillable(1003));
+        assert_eq!(swap.claim(TxId::Hash32([2u8; 32])).unwrap().to_sat(), 500);
+        assert_eq!(swap.claim(TxId::Hash32([3u8; 32])).unwrap().to_sat(), 167);
+        assert_eq!(swap.l2_amount_remaining, bitcoin::Amount::ZERO);
+        assert_eq!(swap.state, SwapState::Completed);
+
+        // Reverting the last claim restores Dave's part
+        swap.revert_claim(&TxId::Hash32([3u8; 32])).unwrap();
+        assert_eq!(swap.l2_amount_remaining.to_sat(), 167);
+        assert_eq!(swap.l1_amount_filled.to_sat(), 250);
+        assert_eq!(
+            swap.claimant(&TxId::Hash32([3u8; 32])),
+            Some(dave_l2_address)
+        );
     }
 
     
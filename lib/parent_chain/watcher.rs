@@ -0,0 +1,278 @@
+//! Node-local swap tracking
+//!
+//! Swaps in [`crate::state::State`] are consensus data: they only change
+//! when blocks are connected or disconnected, and are the same on every
+//! node. What a node observes of a swap's L1 transaction, and what its user
+//! records about a swap, is kept separately in a [`SwapWatcher`], which
+//! never affects consensus and is not replicated.
+
+use fallible_iterator::FallibleIterator as _;
+use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
+use sneed::{
+    DatabaseUnique, DbError, EnvError, RoTxn, RwTxn, RwTxnError, UnitKey, db,
+    env, rwtxn,
+};
+
+use crate::parent_chain::{
+    client::TxId,
+    swap::{Swap, SwapDirection, SwapId, SwapState},
+};
+
+/// Version of the swap watcher database layout
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[allow(clippy::duplicated_attributes)]
+#[derive(thiserror::Error, transitive::Transitive, Debug)]
+#[transitive(from(db::error::Delete, DbError))]
+#[transitive(from(db::error::Put, DbError))]
+#[transitive(from(db::error::TryGet, DbError))]
+#[transitive(from(env::error::CreateDb, EnvError))]
+#[transitive(from(env::error::WriteTxn, EnvError))]
+#[transitive(from(rwtxn::error::Commit, RwTxnError))]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] DbError),
+    #[error("Database env error")]
+    DbEnv(#[from] EnvError),
+    #[error("Database write error")]
+    DbWrite(#[from] RwTxnError),
+    #[error(
+        "Swap watcher schema version {found} is not supported (expected {supported})"
+    )]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+}
+
+/// L1 status of a swap, as last seen by this node's swap monitor
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct L1Observation {
+    /// L1 transaction that was observed
+    pub l1_txid: TxId,
+    /// Either [`SwapState::WaitingConfirmations`] or
+    /// [`SwapState::ReadyToClaim`]
+    pub state: SwapState,
+    /// Hash of the L1 block that includes the transaction
+    pub block_hash: Option<String>,
+}
+
+/// Node-local data about a swap
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SwapWatch {
+    /// For L2 → L1 swaps without a fill: L1 transaction that this node
+    /// expects to pay the swap, set with `update_swap_l1_txid`
+    pub l1_txid: Option<TxId>,
+    /// Payment proof for `l1_txid`, on chains with private outputs
+    pub l1_payment_proof: Option<String>,
+    /// Last L1 status seen by the swap monitor
+    pub observed: Option<L1Observation>,
+    /// Free-form note from the user
+    pub note: Option<String>,
+}
+
+impl SwapWatch {
+    /// Whether the watch holds no data, and need not be stored
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Overlay the node-local data onto a swap from consensus state.
+    /// Swaps that have completed or been cancelled are left as is, and an
+    /// observation only applies to the L1 transaction that it was made for.
+    pub fn apply(&self, swap: &mut Swap) {
+        if swap.state != SwapState::Pending {
+            return;
+        }
+        if swap.direction == SwapDirection::L2ToL1
+            && swap.current_fill().is_none()
+            && let Some(l1_txid) = &self.l1_txid
+        {
+            swap.l1_txid = l1_txid.clone();
+            swap.l1_payment_proof = self.l1_payment_proof.clone();
+        }
+        if let Some(observed) = &self.observed
+            && observed.l1_txid == *swap.expected_l1_txid()
+        {
+            swap.state = observed.state.clone();
+            swap.l1_block_hash = observed.block_hash.clone();
+        }
+    }
+
+    /// Record the L1 status of a swap, as updated by the swap monitor
+    pub fn record(&mut self, swap: &Swap) {
+        self.observed = match swap.state {
+            SwapState::WaitingConfirmations { .. }
+            | SwapState::ReadyToClaim => Some(L1Observation {
+                l1_txid: swap.expected_l1_txid().clone(),
+                state: swap.state.clone(),
+                block_hash: swap.l1_block_hash.clone(),
+            }),
+            SwapState::Pending
+            | SwapState::Completed
+            | SwapState::Cancelled => None,
+        };
+    }
+}
+
+/// Node-local store of [`SwapWatch`]es, in its own databases so that
+/// consensus state only ever holds replicated data
+#[derive(Clone)]
+pub struct SwapWatcher {
+    watches: DatabaseUnique<SerdeBincode<SwapId>, SerdeBincode<SwapWatch>>,
+    _schema_version: DatabaseUnique<UnitKey, SerdeBincode<u32>>,
+}
+
+impl SwapWatcher {
+    pub const NUM_DBS: u32 = 2;
+
+    pub fn new(env: &sneed::Env) -> Result<Self, Error> {
+        let mut rwtxn = env.write_txn()?;
+        let watches = DatabaseUnique::create(env, &mut rwtxn, "swap_watches")?;
+        let schema_version =
+            DatabaseUnique::create(env, &mut rwtxn, "swap_watcher_version")?;
+        // Migrations from older layouts go here, once there are any
+        match schema_version.try_get(&rwtxn, &())? {
+            None => schema_version.put(&mut rwtxn, &(), &SCHEMA_VERSION)?,
+            Some(SCHEMA_VERSION) => (),
+            Some(found) => {
+                return Err(Error::UnsupportedSchemaVersion {
+                    found,
+                    supported: SCHEMA_VERSION,
+                });
+            }
+        }
+        rwtxn.commit()?;
+        Ok(Self {
+            watches,
+            _schema_version: schema_version,
+        })
+    }
+
+    pub fn get(
+        &self,
+        rotxn: &RoTxn,
+        swap_id: &SwapId,
+    ) -> Result<Option<SwapWatch>, Error> {
+        Ok(self.watches.try_get(rotxn, swap_id)?)
+    }
+
+    /// Store the watch for a swap. Empty watches are deleted.
+    pub fn put(
+        &self,
+        rwtxn: &mut RwTxn,
+        swap_id: &SwapId,
+        watch: &SwapWatch,
+    ) -> Result<(), Error> {
+        if watch.is_empty() {
+            let _ = self.watches.delete(rwtxn, swap_id)?;
+        } else {
+            self.watches.put(rwtxn, swap_id, watch)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_all(
+        &self,
+        rotxn: &RoTxn,
+    ) -> Result<Vec<(SwapId, SwapWatch)>, Error> {
+        self.watches
+            .iter(rotxn)
+            .map_err(DbError::from)?
+            .collect()
+            .map_err(|err| DbError::from(err).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parent_chain::config::ParentChainType, types::Address};
+    use tempfile::TempDir;
+
+    fn create_test_watcher() -> (SwapWatcher, sneed::Env, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let env_path = temp_dir.path().join("test.mdb");
+        std::fs::create_dir_all(&env_path).unwrap();
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts.map_size(1024 * 1024); // 1MB
+        env_open_opts.max_dbs(SwapWatcher::NUM_DBS);
+        let env =
+            unsafe { heed::Env::open(&env_open_opts, &env_path).unwrap() };
+        let watcher = SwapWatcher::new(&env).unwrap();
+        (watcher, env, temp_dir)
+    }
+
+    fn create_test_swap() -> Swap {
+        Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            "bc1qtest".to_string(),
+            bitcoin::Amount::from_sat(50_000),
+            Address([0u8; 32]),
+            bitcoin::Amount::from_sat(100_000),
+            Address([0u8; 32]),
+            Some(3),
+            100,
+        )
+    }
+
+    #[test]
+    fn test_swap_watch_overlay() {
+        let (watcher, env, _temp_dir) = create_test_watcher();
+        let consensus_swap = create_test_swap();
+        let l1_txid = TxId::Hash32([1u8; 32]);
+
+        let mut swap = consensus_swap.clone();
+        swap.set_l1_txid(l1_txid.clone(), None).unwrap();
+        swap.state = SwapState::ReadyToClaim;
+        swap.l1_block_hash = Some("block".to_string());
+        let mut watch = SwapWatch {
+            l1_txid: Some(l1_txid.clone()),
+            note: Some("paid from cold wallet".to_string()),
+            ..SwapWatch::default()
+        };
+        watch.record(&swap);
+        {
+            let mut rwtxn = env.write_txn().unwrap();
+            watcher.put(&mut rwtxn, &swap.id, &watch).unwrap();
+            rwtxn.commit().unwrap();
+        }
+
+        // Reopening the store keeps the watch
+        let watcher = SwapWatcher::new(&env).unwrap();
+        let rotxn = env.read_txn().unwrap();
+        let stored = watcher.get(&rotxn, &swap.id).unwrap().unwrap();
+        assert_eq!(stored, watch);
+        let mut overlaid = consensus_swap.clone();
+        stored.apply(&mut overlaid);
+        assert_eq!(overlaid, swap);
+
+        // Final consensus states are not overridden
+        let mut completed = consensus_swap;
+        completed.state = SwapState::Completed;
+        stored.apply(&mut completed);
+        assert_eq!(completed.state, SwapState::Completed);
+        assert_eq!(completed.l1_block_hash, None);
+        drop(rotxn);
+
+        // Dropping the observation and note deletes the watch
+        let mut rwtxn = env.write_txn().unwrap();
+        watcher
+            .put(&mut rwtxn, &swap.id, &SwapWatch::default())
+            .unwrap();
+        assert!(watcher.get(&rwtxn, &swap.id).unwrap().is_none());
+        assert!(watcher.get_all(&rwtxn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_swap_watch_stale_observation() {
+        let mut swap = create_test_swap();
+        swap.set_l1_txid(TxId::Hash32([1u8; 32]), None).unwrap();
+        swap.state = SwapState::ReadyToClaim;
+        let mut watch = SwapWatch::default();
+        watch.record(&swap);
+
+        // The observation was made for another L1 transaction
+        let mut swap = create_test_swap();
+        watch.apply(&mut swap);
+        assert_eq!(swap.state, SwapState::Pending);
+    }
+}
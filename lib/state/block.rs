@@ -1,14 +1,16 @@
 //! Connect and disconnect blocks
 
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
 use rayon::prelude::*;
 use sneed::{RoTxn, RwTxn};
 
 use crate::{
-    parent_chain::{swap::Swap, SwapId, client::TxId, config::ParentChainType},
-    state::{Error, PrevalidatedBlock, State, amm, dutch_auction, error},
+    parent_chain::{ParentChainType, Swap, SwapId},
+    state::{Error, PrevalidatedBlock, State, amm, dutch_auction, error, swap},
     types::{
         AmountOverflowError, Authorization, BitAssetId, BlockHash, Body,
-        FilledOutput, FilledOutputContent, GetAddress as _,
+        FilledOutput, FilledOutputContent, FilledTransaction, GetAddress as _,
         GetBitcoinValue as _, Hash, Header, InPoint, OutPoint, OutPointKey,
         OutputContent, SpentOutput, TxData, Verify as _,
     },
@@ -19,6 +21,96 @@ fn calculate_total_inputs(body: &Body) -> usize {
     body.transactions.iter().map(|t| t.inputs.len()).sum()
 }
 
+/// Swap fills, claimed L1 outputs and relayed headers of the txs validated
+/// so far in a block.
+/// Each tx is validated against the state before the block, so txs that
+/// are only invalid after an earlier tx in the same block are rejected
+/// with these, rather than when the block is connected.
+#[derive(Default)]
+pub(in crate::state) struct BlockTxs {
+    /// Swaps filled in the block, with their fills applied
+    filled_swaps: HashMap<SwapId, Swap>,
+    claimed_l1_outputs: HashSet<swap::ClaimedL1Output>,
+    relay_headers: HashSet<(ParentChainType, bitcoin::BlockHash)>,
+}
+
+impl BlockTxs {
+    /// Validate a tx against the earlier txs of the block, and add it.
+    /// The tx must already be valid against the state before the block.
+    pub(in crate::state) fn validate_tx(
+        &mut self,
+        state: &State,
+        rotxn: &RoTxn,
+        filled_tx: &FilledTransaction,
+        height: u32,
+    ) -> Result<(), Error> {
+        match &filled_tx.transaction.data {
+            Some(TxData::SwapFill { swap_id, .. }) => {
+                let swap = match self.filled_swaps.entry(SwapId(*swap_id)) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let swap = state.get_swap(rotxn, entry.key())?;
+                        let swap = swap.ok_or_else(|| {
+                            Error::InvalidTransaction(format!(
+                                "Swap not found: {:?}",
+                                entry.key()
+                            ))
+                        })?;
+                        entry.insert(swap)
+                    }
+                };
+                let fill = swap::new_fill(swap, filled_tx, height)?;
+                swap.fills.push(fill);
+            }
+            Some(TxData::SwapClaim {
+                swap_id,
+                proof_data,
+            }) => {
+                let swap_id = SwapId(*swap_id);
+                let swap =
+                    state.get_swap(rotxn, &swap_id)?.ok_or_else(|| {
+                        Error::InvalidTransaction(format!(
+                            "Swap not found: {:?}",
+                            swap_id
+                        ))
+                    })?;
+                let l1_txid = proof_data.l1_txid();
+                for vout in swap::l1_payment_vouts(
+                    state,
+                    rotxn,
+                    &swap,
+                    proof_data,
+                    &self.claimed_l1_outputs,
+                )? {
+                    self.claimed_l1_outputs.insert((
+                        swap.parent_chain,
+                        l1_txid.clone(),
+                        vout,
+                    ));
+                }
+            }
+            Some(TxData::ParentChainHeaders {
+                parent_chain,
+                start_height: _,
+                headers,
+            }) => {
+                for header in headers {
+                    let block_hash = header.block_hash();
+                    if !self.relay_headers.insert((*parent_chain, block_hash)) {
+                        let err = error::HeaderRelay::DuplicateHeader {
+                            parent_chain: *parent_chain,
+                            block_hash,
+                        };
+                        return Err(err.into());
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
 /// Validate a block, returning the merkle root and fees
 pub fn validate(
     state: &State,
@@ -71,19 +163,25 @@ pub fn validate(
         return Err(Error::UtxoDoubleSpent);
     }
 
+    let height = state.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+    let mut block_txs = BlockTxs::default();
     for filled_tx in &filled_txs {
         total_fees = total_fees
             .checked_add(state.validate_filled_transaction(rotxn, filled_tx)?)
             .ok_or(AmountOverflowError)?;
+        let () = block_txs.validate_tx(state, rotxn, filled_tx, height)?;
     }
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
-    let spent_utxos = filled_txs.iter().flat_map(|t| t.spent_utxos.iter());
-    for (authorization, spent_utxo) in
-        body.authorizations.iter().zip(spent_utxos)
+    let mut authorizers = Vec::with_capacity(total_inputs);
+    for filled_tx in &filled_txs {
+        authorizers.extend(state.get_authorizers(rotxn, filled_tx)?);
+    }
+    for (authorization, authorizer) in
+        body.authorizations.iter().zip(authorizers)
     {
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address() != authorizer {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -147,23 +245,27 @@ pub fn prevalidate(
         return Err(Error::UtxoDoubleSpent);
     }
 
+    let height = state.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+    let mut block_txs = BlockTxs::default();
     for filled_tx in &filled_transactions {
         total_fees = total_fees
             .checked_add(state.validate_filled_transaction(rotxn, filled_tx)?)
             .ok_or(AmountOverflowError)?;
+        let () = block_txs.validate_tx(state, rotxn, filled_tx, height)?;
     }
 
     if coinbase_value > total_fees {
         return Err(Error::NotEnoughFees);
     }
 
-    let spent_utxos_iter = filled_transactions
-        .iter()
-        .flat_map(|t| t.spent_utxos.iter());
-    for (authorization, spent_utxo) in
-        body.authorizations.iter().zip(spent_utxos_iter)
+    let mut authorizers = Vec::with_capacity(total_inputs);
+    for filled_tx in &filled_transactions {
+        authorizers.extend(state.get_authorizers(rotxn, filled_tx)?);
+    }
+    for (authorization, authorizer) in
+        body.authorizations.iter().zip(authorizers)
     {
-        if authorization.get_address() != spent_utxo.address {
+        if authorization.get_address() != authorizer {
             return Err(Error::WrongPubKeyForAddress);
         }
     }
@@ -172,8 +274,6 @@ pub fn prevalidate(
         return Err(Error::AuthorizationError);
     }
 
-    let height = state.try_get_height(rotxn)?.map_or(0, |height| height + 1);
-
     Ok(PrevalidatedBlock {
         filled_transactions,
         computed_merkle_root: BlockHash::from(Hash::from(computed_merkle_root)),
@@ -230,7 +330,8 @@ pub fn connect_prevalidated(
             | OutputContent::BitAsset(_)
             | OutputContent::BitAssetControl
             | OutputContent::BitAssetReservation
-            | OutputContent::DutchAuctionReceipt => {
+            | OutputContent::DutchAuctionReceipt
+            | OutputContent::Htlc(_) => {
                 return Err(Error::BadCoinbaseOutputContent);
             }
         };
@@ -282,7 +383,7 @@ pub fn connect_prevalidated(
 
         // Apply transaction data effects
         match &transaction.data {
-            None => (),
+            None | Some(TxData::HtlcRedeem { .. }) => (),
             Some(TxData::AmmBurn { .. }) => {
                 let () = amm::apply_burn(&state.amm_pools, rwtxn, filled_tx)?;
             }
@@ -353,102 +454,50 @@ pub fn connect_prevalidated(
                     prevalidated.next_height,
                 )?;
             }
-            Some(TxData::SwapCreate {
-                swap_id,
+            Some(TxData::SwapCreate { .. }) => {
+                let () = swap::apply_create(
+                    state,
+                    rwtxn,
+                    filled_tx,
+                    prevalidated.next_height,
+                )?;
+            }
+            Some(TxData::SwapClaim { .. }) => {
+                let () = swap::apply_claim(
+                    state,
+                    rwtxn,
+                    filled_tx,
+                    prevalidated.next_height,
+                )?;
+            }
+            Some(TxData::SwapFill { .. }) => {
+                let () = swap::apply_fill(
+                    state,
+                    rwtxn,
+                    filled_tx,
+                    prevalidated.next_height,
+                )?;
+            }
+            Some(TxData::SwapRefund { .. }) => {
+                let () = swap::apply_refund(
+                    state,
+                    rwtxn,
+                    filled_tx,
+                    prevalidated.next_height,
+                )?;
+            }
+            Some(TxData::ParentChainHeaders {
                 parent_chain,
-                l1_txid_bytes,
-                required_confirmations,
-                l2_recipient,
-                l2_amount,
-                l1_recipient_address,
-                ..
+                start_height,
+                headers,
             }) => {
-                // Reconstruct TxId from bytes
-                let l1_txid = if l1_txid_bytes.len() == 32 {
-                    let mut hash32 = [0u8; 32];
-                    hash32.copy_from_slice(&l1_txid_bytes);
-                    TxId::Hash32(hash32)
-                } else {
-                    TxId::Hash(l1_txid_bytes.clone())
-                };
-                
-                // Create swap
-                let swap = Swap::new(
-                    parent_chain.clone(),
-                    l1_txid,
-                    Some(*required_confirmations),
-                    *l2_recipient,
-                    bitcoin::Amount::from_sat(*l2_amount),
+                let () = state.header_relay.apply_headers(
+                    rwtxn,
+                    parent_chain,
+                    *start_height,
+                    headers,
                     prevalidated.next_height,
-                );
-                
-                // Verify swap ID matches
-                if swap.id.0 != *swap_id {
-                    return Err(Error::InvalidTransaction(format!(
-                        "Swap ID mismatch: expected {:?}, got {:?}",
-                        swap.id.0, swap_id
-                    )));
-                }
-                
-                // COIN LOCKING FOR L2 → L1 SWAPS
-                // If l1_recipient_address is set, this is an L2 → L1 swap
-                // Lock all outputs of this transaction to the swap
-                if l1_recipient_address.is_some() {
-                    let swap_id = SwapId(*swap_id);
-                    let Some(filled_outputs) = filled_tx.filled_outputs() else {
-                        return Err(Error::InvalidTransaction(
-                            "SwapCreate transaction must have outputs".to_string()
-                        ));
-                    };
-                    
-                    // Lock all outputs to this swap
-                    for (vout, _filled_output) in filled_outputs.iter().enumerate() {
-                        let outpoint = OutPoint::Regular {
-                            txid: filled_tx.txid(),
-                            vout: vout as u32,
-                        };
-                        state.lock_output_to_swap(rwtxn, &outpoint, &swap_id)?;
-                    }
-                }
-                
-                // Save swap to database
-                state.save_swap(rwtxn, &swap)?;
-            }
-            Some(TxData::SwapClaim { swap_id, .. }) => {
-                let swap_id = SwapId(*swap_id);
-                
-                // Get swap from database
-                let swap = state.get_swap(rwtxn, &swap_id)?
-                    .ok_or_else(|| Error::InvalidTransaction(
-                        format!("Swap not found: {:?}", swap_id)
-                    ))?;
-                
-                // Verify swap is ready to claim
-                if !matches!(swap.state, crate::parent_chain::swap::SwapState::ReadyToClaim) {
-                    return Err(Error::InvalidTransaction(format!(
-                        "Swap not ready to claim: {:?}",
-                        swap.state
-                    )));
-                }
-                
-                // COIN UNLOCKING FOR SWAP CLAIMS
-                // Unlock all inputs that are locked to this swap
-                for input in &filled_tx.transaction.inputs {
-                    if let Some(locked_swap_id) = state.is_output_locked_to_swap(rwtxn, input)? {
-                        if locked_swap_id == swap_id {
-                            // Unlock this output
-                            state.unlock_output_from_swap(rwtxn, input)?;
-                        }
-                    }
-                }
-                
-                // Mark swap as completed
-                let mut swap = swap;
-                swap.mark_completed()
-                    .map_err(|e| Error::InvalidTransaction(format!("Failed to mark swap completed: {}", e)))?;
-                
-                // Update swap in database
-                state.save_swap(rwtxn, &swap)?;
+                )?;
             }
         }
     }
@@ -519,7 +568,8 @@ pub fn connect(
             | OutputContent::BitAsset(_)
             | OutputContent::BitAssetControl
             | OutputContent::BitAssetReservation
-            | OutputContent::DutchAuctionReceipt => {
+            | OutputContent::DutchAuctionReceipt
+            | OutputContent::Htlc(_) => {
                 return Err(Error::BadCoinbaseOutputContent);
             }
         };
@@ -562,7 +612,7 @@ pub fn connect(
             state.utxos.put(rwtxn, &outpoint_key, filled_output)?;
         }
         match &transaction.data {
-            None => (),
+            None | Some(TxData::HtlcRedeem { .. }) => (),
             Some(TxData::AmmBurn { .. }) => {
                 let () = amm::apply_burn(&state.amm_pools, rwtxn, &filled_tx)?;
             }
@@ -578,12 +628,16 @@ pub fn connect(
                     .put_reservation(rwtxn, &txid, commitment)?;
             }
             Some(TxData::SwapCreate { .. }) => {
-                // TODO: Handle SwapCreate in connect() function
-                // See: lib/state/block.rs:356-392 for implementation in connect_prevalidated
+                let () = swap::apply_create(state, rwtxn, &filled_tx, height)?;
             }
             Some(TxData::SwapClaim { .. }) => {
-                // TODO: Handle SwapClaim in connect() function
-                // See: lib/state/block.rs:394-420 for implementation in connect_prevalidated
+                let () = swap::apply_claim(state, rwtxn, &filled_tx, height)?;
+            }
+            Some(TxData::SwapFill { .. }) => {
+                let () = swap::apply_fill(state, rwtxn, &filled_tx, height)?;
+            }
+            Some(TxData::SwapRefund { .. }) => {
+                let () = swap::apply_refund(state, rwtxn, &filled_tx, height)?;
             }
             Some(TxData::BitAssetRegistration {
                 name_hash,
@@ -641,6 +695,19 @@ pub fn connect(
                     height,
                 )?;
             }
+            Some(TxData::ParentChainHeaders {
+                parent_chain,
+                start_height,
+                headers,
+            }) => {
+                let () = state.header_relay.apply_headers(
+                    rwtxn,
+                    parent_chain,
+                    *start_height,
+                    headers,
+                    height,
+                )?;
+            }
         }
     }
     let block_hash = header.hash();
@@ -680,7 +747,7 @@ pub fn disconnect_tip(
         let filled_tx = state.fill_transaction_from_stxos(rwtxn, tx.clone())?;
         // revert transaction effects
         match &tx.data {
-            None => (),
+            None | Some(TxData::HtlcRedeem { .. }) => (),
             Some(TxData::AmmBurn { .. }) => {
                 let () = amm::revert_burn(&state.amm_pools, rwtxn, &filled_tx)?;
             }
@@ -744,52 +811,29 @@ pub fn disconnect_tip(
                     &filled_tx,
                 )?;
             }
-            Some(TxData::SwapCreate { swap_id, l1_recipient_address, .. }) => {
-                // Delete swap created in this block
-                let swap_id = SwapId(*swap_id);
-                
-                // ROLLBACK: Unlock outputs if this was an L2 → L1 swap
-                if l1_recipient_address.is_some() {
-                    if let Some(filled_outputs) = filled_tx.filled_outputs() {
-                        // Unlock all outputs that were locked to this swap
-                        for (vout, _filled_output) in filled_outputs.iter().enumerate() {
-                            let outpoint = OutPoint::Regular {
-                                txid: filled_tx.txid(),
-                                vout: vout as u32,
-                            };
-                            // Only unlock if it's actually locked to this swap
-                            if let Some(locked_swap_id) = state.is_output_locked_to_swap(rwtxn, &outpoint)? {
-                                if locked_swap_id == swap_id {
-                                    state.unlock_output_from_swap(rwtxn, &outpoint)?;
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                state.delete_swap(rwtxn, &swap_id)?;
+            Some(TxData::ParentChainHeaders {
+                parent_chain,
+                start_height: _,
+                headers,
+            }) => {
+                let () = state.header_relay.revert_headers(
+                    rwtxn,
+                    parent_chain,
+                    headers,
+                    height,
+                )?;
             }
-            Some(TxData::SwapClaim { swap_id, .. }) => {
-                // Revert swap claim - set state back to ReadyToClaim
-                let swap_id = SwapId(*swap_id);
-                
-                // ROLLBACK: Re-lock outputs that were unlocked by this claim
-                for input in &filled_tx.transaction.inputs {
-                    // Check if this input is currently unlocked (it was unlocked by the claim)
-                    // We need to re-lock it to the swap
-                    if state.is_output_locked_to_swap(rwtxn, input)?.is_none() {
-                        // This output was unlocked by the claim, re-lock it
-                        state.lock_output_to_swap(rwtxn, input, &swap_id)?;
-                    }
-                }
-                
-                if let Some(mut swap) = state.get_swap(rwtxn, &swap_id)? {
-                    // Only revert if swap was completed (not if it was already in another state)
-                    if matches!(swap.state, crate::parent_chain::swap::SwapState::Completed) {
-                        swap.state = crate::parent_chain::swap::SwapState::ReadyToClaim;
-                        state.save_swap(rwtxn, &swap)?;
-                    }
-                }
+            Some(TxData::SwapCreate { .. }) => {
+                let () = swap::revert_create(state, rwtxn, &filled_tx)?;
+            }
+            Some(TxData::SwapClaim { .. }) => {
+                let () = swap::revert_claim(state, rwtxn, &filled_tx)?;
+            }
+            Some(TxData::SwapFill { .. }) => {
+                let () = swap::revert_fill(state, rwtxn, &filled_tx)?;
+            }
+            Some(TxData::SwapRefund { .. }) => {
+                let () = swap::revert_refund(state, rwtxn, &filled_tx)?;
             }
         }
         // delete UTXOs, last-to-first
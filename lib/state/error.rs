@@ -5,6 +5,9 @@ use sneed::{db::error as db, env::error as env, rwtxn::error as rwtxn};
 use thiserror::Error;
 use transitive::Transitive;
 
+use crate::parent_chain::{
+    ParentChainType, SwapAsset, SwapId, SwapState, TxId,
+};
 use crate::types::{
     AmountOverflowError, AmountUnderflowError, AssetId, BitAssetId, BlockHash,
     Hash, M6id, MerkleRoot, OutPoint, Txid, WithdrawalBundleError,
@@ -171,6 +174,205 @@ pub mod dutch_auction {
 }
 pub use dutch_auction::Error as DutchAuction;
 
+/// Errors related to the parent chain header relay
+#[derive(Debug, Error, Transitive)]
+#[transitive(from(db::Delete, db::Error))]
+#[transitive(from(db::Error, sneed::Error))]
+#[transitive(from(db::Put, db::Error))]
+#[transitive(from(db::TryGet, db::Error))]
+pub enum HeaderRelay {
+    #[error("relay header {index} does not extend the previous header")]
+    BrokenHeaderChain { index: usize },
+    #[error(transparent)]
+    Db(#[from] sneed::Error),
+    #[error("{parent_chain} header {block_hash} is already in the relay")]
+    DuplicateHeader {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error("no headers to relay")]
+    Empty,
+    #[error(
+        "relay headers start at height {provided}, but the parent is at height {parent_height}"
+    )]
+    HeightMismatch { parent_height: u32, provided: u32 },
+    #[error("relay header {index} has an invalid difficulty target")]
+    InvalidTarget { index: usize },
+    #[error("relay header {index} does not meet its proof-of-work target")]
+    InvalidPow { index: usize },
+    #[error("missing {parent_chain} relay header {block_hash}")]
+    MissingHeader {
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    },
+    #[error("unknown parent {prev_blockhash} for {parent_chain} relay headers")]
+    UnknownParent {
+        parent_chain: ParentChainType,
+        prev_blockhash: bitcoin::BlockHash,
+    },
+    #[error("header relay is not supported for {0}")]
+    UnsupportedChain(ParentChainType),
+}
+
+/// Errors related to hash time-locked outputs
+#[derive(Debug, Error)]
+pub enum Htlc {
+    #[error(
+        "hash time-locked output {vout} times out at height {timeout_height}, which is not after height {height}"
+    )]
+    InvalidTimeout {
+        vout: u32,
+        timeout_height: u32,
+        height: u32,
+    },
+    #[error("HtlcRedeem tx does not redeem any hash time-locked input")]
+    NoRedeemedInput,
+    #[error(
+        "hash time-locked input {outpoint} cannot be refunded before height {timeout_height}"
+    )]
+    NotTimedOut {
+        outpoint: OutPoint,
+        timeout_height: u32,
+    },
+    #[error(
+        "hash time-locked input {outpoint} timed out at height {timeout_height}, and can only be refunded"
+    )]
+    TimedOut {
+        outpoint: OutPoint,
+        timeout_height: u32,
+    },
+}
+
+/// Errors related to swaps
+#[derive(Debug, Error)]
+pub enum Swap {
+    #[error(
+        "swap {swap_id:?} is reserved by a fill until sidechain height {expires_at_height}"
+    )]
+    AlreadyFilled {
+        swap_id: SwapId,
+        expires_at_height: u32,
+    },
+    #[error("L1 tx {l1_txid:?} already fills swap {swap_id:?}")]
+    DuplicateFill { swap_id: SwapId, l1_txid: TxId },
+    #[error("swap {swap_id:?} expired at sidechain height {expires_at_height}")]
+    Expired {
+        swap_id: SwapId,
+        expires_at_height: u32,
+    },
+    #[error(
+        "a fill of swap {swap_id:?} at sidechain height {height} would reserve it past its expiry at height {expires_at_height}"
+    )]
+    FillPastExpiry {
+        swap_id: SwapId,
+        height: u32,
+        expires_at_height: u32,
+    },
+    #[error(
+        "escrow output {vout} for swap {swap_id:?} holds {actual}, expected {expected}"
+    )]
+    EscrowAmountMismatch {
+        swap_id: SwapId,
+        vout: u32,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("claim of swap {swap_id:?} does not spend its escrow {escrow:?}")]
+    EscrowNotSpent { swap_id: SwapId, escrow: OutPoint },
+    #[error(
+        "fill bond for swap {swap_id:?} is {provided}, but at least {required} is required"
+    )]
+    InsufficientFillBond {
+        swap_id: SwapId,
+        required: bitcoin::Amount,
+        provided: bitcoin::Amount,
+    },
+    #[error(
+        "escrow output {vout} for swap {swap_id:?} does not exist, or does not hold {asset:?} for the offerer"
+    )]
+    InvalidEscrowOutput {
+        swap_id: SwapId,
+        vout: u32,
+        asset: SwapAsset,
+    },
+    #[error(
+        "fill of swap {swap_id:?} pays {l1_amount:?}, but at most {fillable} can be filled"
+    )]
+    InvalidFillAmount {
+        swap_id: SwapId,
+        l1_amount: Option<bitcoin::Amount>,
+        fillable: bitcoin::Amount,
+    },
+    #[error("invalid L1 recipient address for swap {swap_id:?}: {source}")]
+    InvalidL1Address {
+        swap_id: SwapId,
+        source: crate::parent_chain::address::Error,
+    },
+    #[error("invalid L1 proof for swap {swap_id:?}: {source}")]
+    InvalidL1Proof {
+        swap_id: SwapId,
+        source: crate::parent_chain::proof::Error,
+    },
+    #[error(
+        "swap {swap_id:?} expires at sidechain height {expires_at_height}, which is not after height {height}"
+    )]
+    InvalidExpiry {
+        swap_id: SwapId,
+        expires_at_height: u32,
+        height: u32,
+    },
+    #[error("only open swap offers can be partially filled ({swap_id:?})")]
+    InvalidPartialFills { swap_id: SwapId },
+    #[error("Invalid TxData")]
+    InvalidTxData,
+    #[error(
+        "outputs of L1 tx {l1_txid:?} that pay swap {swap_id:?} already paid for another claim"
+    )]
+    L1PaymentClaimed { swap_id: SwapId, l1_txid: TxId },
+    #[error(
+        "L1 proof for swap {swap_id:?} proves txid {proven:?}, expected {expected:?}"
+    )]
+    L1TxidMismatch {
+        swap_id: SwapId,
+        expected: TxId,
+        proven: TxId,
+    },
+    #[error("missing fill timeout for open swap offer {swap_id:?}")]
+    MissingFillTimeout { swap_id: SwapId },
+    #[error("missing L1 amount for swap {swap_id:?}")]
+    MissingL1Amount { swap_id: SwapId },
+    #[error("missing L1 checkpoint for swap {swap_id:?}")]
+    MissingL1Checkpoint { swap_id: SwapId },
+    #[error("missing L1 recipient address for swap {swap_id:?}")]
+    MissingL1Recipient { swap_id: SwapId },
+    #[error("missing rollback data for swap {swap_id:?}")]
+    MissingRollback { swap_id: SwapId },
+    #[error("no fill of swap {swap_id:?} can claim with L1 tx {l1_txid:?}")]
+    NoClaimableFill { swap_id: SwapId, l1_txid: TxId },
+    #[error("swap {swap_id:?} can no longer be claimed ({state:?})")]
+    NotClaimable { swap_id: SwapId, state: SwapState },
+    #[error("swap {swap_id:?} has not expired (expires at {expires_at_height:?})")]
+    NotExpired {
+        swap_id: SwapId,
+        expires_at_height: Option<u32>,
+    },
+    #[error("swap {swap_id:?} cannot be filled ({state:?})")]
+    NotFillable { swap_id: SwapId, state: SwapState },
+    #[error("open swap offer {swap_id:?} has not been filled")]
+    NotFilled { swap_id: SwapId },
+    #[error("refund input {outpoint} is not locked to swap {swap_id:?}")]
+    RefundInputNotLocked { swap_id: SwapId, outpoint: OutPoint },
+    #[error("swap {swap_id:?} uses unsupported swap ID version {version}")]
+    UnsupportedIdVersion { swap_id: SwapId, version: u8 },
+    #[error(
+        "swap {swap_id:?} could never be claimed, as L1 payments on {parent_chain} cannot be proven"
+    )]
+    UnsupportedParentChain {
+        swap_id: SwapId,
+        parent_chain: ParentChainType,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum InvalidHeader {
     #[error("expected block hash {expected}, but computed {computed}")]
@@ -246,6 +448,10 @@ pub enum Error {
     DutchAuction(#[from] DutchAuction),
     #[error(transparent)]
     FillTxOutputContents(#[from] FillTxOutputContents),
+    #[error(transparent)]
+    HeaderRelay(#[from] HeaderRelay),
+    #[error(transparent)]
+    Htlc(#[from] Htlc),
     #[error(
         "invalid body: expected merkle root {expected}, but computed {computed}"
     )]
@@ -285,6 +491,8 @@ pub enum Error {
     SecondLastOutputNotBitAsset,
     #[error(transparent)]
     SignatureError(#[from] ed25519_dalek::SignatureError),
+    #[error(transparent)]
+    Swap(#[from] Swap),
     #[error("Too few BitAsset control coin outputs")]
     TooFewBitAssetControlOutputs,
     #[error(
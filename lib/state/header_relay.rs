@@ -0,0 +1,845 @@
+//! Relay of parent chain block headers
+//!
+//! Anyone can submit BTC-family headers to the sidechain. Each submission
+//! must extend a header that is already in the relay, or the relay anchor,
+//! which is fixed by consensus for each parent chain. The relay tip is the
+//! relayed header with the most chain work, so swap claims can be checked
+//! for confirmations against the sidechain's own copy of each parent chain.
+
+use bitcoin::{Target, Work, block::Header as BlockHeader};
+use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
+use sneed::{DatabaseUnique, RoTxn, RwTxn, env};
+use utoipa::ToSchema;
+
+use crate::{
+    parent_chain::{ParentChainType, proof},
+    state::{
+        error::HeaderRelay as Error,
+        rollback::{HeightStamped, RollBack},
+    },
+};
+
+/// Number of blocks between difficulty adjustments on BTC and LTC
+const RETARGET_INTERVAL: u32 = 2016;
+
+/// BCH adjusts difficulty every block. The target of a header may exceed the
+/// target of its parent by at most `2^-BCH_MAX_TARGET_INCREASE_SHIFT` of the
+/// parent target.
+const BCH_MAX_TARGET_INCREASE_SHIFT: u32 = 3;
+
+/// Litecoin genesis block header
+const LTC_GENESIS_HEADER: [u8; 80] = hex_literal::hex!(
+    "01000000"
+    "0000000000000000000000000000000000000000000000000000000000000000"
+    "d9ced4ed1130f7b7faad9be25323ffafa33232a17c3edf6cfd97bee6bafbdd97"
+    "b9aa8e4e"
+    "f0ff0f1e"
+    "cd513f7c"
+);
+
+/// A parent chain header stored in the relay
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema)]
+pub struct RelayHeader {
+    #[schema(value_type = crate::types::schema::BitcoinBlockHeader)]
+    pub header: BlockHeader,
+    /// Parent chain height of the header
+    pub height: u32,
+    /// Total work of the relayed chain, up to and including this header
+    #[schema(value_type = String)]
+    pub chain_work: Work,
+}
+
+/// Tip of the relayed chain with the most work
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ToSchema)]
+pub struct RelayTip {
+    #[schema(value_type = crate::types::schema::BitcoinBlockHash)]
+    pub block_hash: bitcoin::BlockHash,
+    /// Parent chain height of the tip
+    pub height: u32,
+    /// Total work of the relayed chain, up to and including the tip
+    #[schema(value_type = String)]
+    pub chain_work: Work,
+}
+
+impl From<&RelayHeader> for RelayTip {
+    fn from(relay_header: &RelayHeader) -> Self {
+        Self {
+            block_hash: relay_header.header.block_hash(),
+            height: relay_header.height,
+            chain_work: relay_header.chain_work,
+        }
+    }
+}
+
+/// `target * (1 + 2^-shift)`, saturating
+fn target_with_margin(target: Target, shift: u32) -> Target {
+    let bytes = target.to_le_bytes();
+    let lo = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+    let hi = u128::from_le_bytes(bytes[16..].try_into().unwrap());
+    let margin_lo = (lo >> shift) | (hi << (128 - shift));
+    let margin_hi = hi >> shift;
+    let (lo, carry) = lo.overflowing_add(margin_lo);
+    let Some(hi) = hi
+        .checked_add(margin_hi)
+        .and_then(|hi| hi.checked_add(u128::from(carry)))
+    else {
+        return Target::from_le_bytes([0xff; 32]);
+    };
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&lo.to_le_bytes());
+    bytes[16..].copy_from_slice(&hi.to_le_bytes());
+    Target::from_le_bytes(bytes)
+}
+
+/// Relay anchor for a parent chain, fixed by consensus. Anchors are genesis
+/// blocks, and no relayed header may have a target above the anchor target.
+fn consensus_anchor(parent_chain: &ParentChainType) -> Option<RelayHeader> {
+    let header = match parent_chain {
+        // BCH shares its genesis block with BTC
+        ParentChainType::Btc | ParentChainType::Bch => {
+            bitcoin::constants::genesis_block(bitcoin::Network::Bitcoin).header
+        }
+        ParentChainType::Ltc => {
+            bitcoin::consensus::deserialize(&LTC_GENESIS_HEADER)
+                .expect("Litecoin genesis header should be valid")
+        }
+        ParentChainType::Xmr | ParentChainType::Eth | ParentChainType::Tron => {
+            return None;
+        }
+    };
+    Some(RelayHeader {
+        header,
+        height: 0,
+        chain_work: header.work(),
+    })
+}
+
+/// Check the difficulty target of a header against its parent.
+/// BTC and LTC may only change the target at retarget heights, by at most
+/// 4x. BCH may change the target every block, within a small margin.
+/// Testnet minimum-difficulty blocks are not supported.
+fn is_valid_target(
+    parent_chain: &ParentChainType,
+    parent: &BlockHeader,
+    header: &BlockHeader,
+    height: u32,
+) -> bool {
+    match parent_chain {
+        ParentChainType::Btc | ParentChainType::Ltc => {
+            if height % RETARGET_INTERVAL == 0 {
+                header.target() <= proof::max_target(parent.target())
+            } else {
+                header.bits == parent.bits
+            }
+        }
+        ParentChainType::Bch => {
+            header.target()
+                <= target_with_margin(
+                    parent.target(),
+                    BCH_MAX_TARGET_INCREASE_SHIFT,
+                )
+        }
+        ParentChainType::Xmr | ParentChainType::Eth | ParentChainType::Tron => {
+            false
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Dbs {
+    /// Relayed headers, keyed by parent chain and block hash
+    headers: DatabaseUnique<
+        SerdeBincode<(ParentChainType, bitcoin::BlockHash)>,
+        SerdeBincode<RelayHeader>,
+    >,
+    /// Block hashes of the relayed chain with the most work, keyed by parent
+    /// chain and height
+    best_chain: DatabaseUnique<
+        SerdeBincode<(ParentChainType, u32)>,
+        SerdeBincode<bitcoin::BlockHash>,
+    >,
+    /// Relay tip for each parent chain
+    tips: DatabaseUnique<
+        SerdeBincode<ParentChainType>,
+        SerdeBincode<RollBack<HeightStamped<RelayTip>>>,
+    >,
+    /// Relay anchor for each parent chain
+    anchor: fn(&ParentChainType) -> Option<RelayHeader>,
+}
+
+impl Dbs {
+    pub const NUM_DBS: u32 = 3;
+
+    /// Create / Open DBs. Does not commit the RwTxn.
+    pub(in crate::state) fn new(
+        env: &sneed::Env,
+        rwtxn: &mut RwTxn,
+    ) -> Result<Self, env::error::CreateDb> {
+        let headers =
+            DatabaseUnique::create(env, rwtxn, "parent_chain_relay_headers")?;
+        let best_chain = DatabaseUnique::create(
+            env,
+            rwtxn,
+            "parent_chain_relay_best_chain",
+        )?;
+        let tips =
+            DatabaseUnique::create(env, rwtxn, "parent_chain_relay_tips")?;
+        Ok(Self {
+            headers,
+            best_chain,
+            tips,
+            anchor: consensus_anchor,
+        })
+    }
+
+    /// Get a relayed header, or the relay anchor
+    pub fn try_get_header(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: &ParentChainType,
+        block_hash: &bitcoin::BlockHash,
+    ) -> Result<Option<RelayHeader>, Error> {
+        if let Some(anchor) = (self.anchor)(parent_chain)
+            && anchor.header.block_hash() == *block_hash
+        {
+            return Ok(Some(anchor));
+        }
+        let key = (*parent_chain, *block_hash);
+        Ok(self.headers.try_get(rotxn, &key)?)
+    }
+
+    /// Get the relay tip for a parent chain, which is the relay anchor until
+    /// headers are relayed
+    pub fn try_get_tip(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: &ParentChainType,
+    ) -> Result<Option<RelayTip>, Error> {
+        let tip = match self.tips.try_get(rotxn, parent_chain)? {
+            Some(tip) => Some(tip.latest().value),
+            None => (self.anchor)(parent_chain)
+                .map(|anchor| RelayTip::from(&anchor)),
+        };
+        Ok(tip)
+    }
+
+    /// Number of confirmations of a block on the relayed chain with the most
+    /// work, or `None` if the block is not on that chain
+    pub fn confirmations(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: &ParentChainType,
+        block_hash: &bitcoin::BlockHash,
+    ) -> Result<Option<u32>, Error> {
+        let Some(relay_header) =
+            self.try_get_header(rotxn, parent_chain, block_hash)?
+        else {
+            return Ok(None);
+        };
+        let Some(tip) = self.try_get_tip(rotxn, parent_chain)? else {
+            return Ok(None);
+        };
+        if relay_header.height > tip.height {
+            return Ok(None);
+        }
+        // The tip is not indexed until headers are relayed
+        if *block_hash != tip.block_hash
+            && self
+                .best_chain
+                .try_get(rotxn, &(*parent_chain, relay_header.height))?
+                .as_ref()
+                != Some(block_hash)
+        {
+            return Ok(None);
+        }
+        Ok(Some(tip.height - relay_header.height + 1))
+    }
+
+    /// `true` if a block is buried at least `required_confirmations` deep
+    /// on the relayed chain with the most work, after `checkpoint`, and the
+    /// relayed headers after `checkpoint` carry at least as much work as
+    /// `required_confirmations` headers at the checkpoint target. These are
+    /// the same requirements as for the headers of an
+    /// [`proof::L1TxProof`].
+    pub fn is_confirmed_after_checkpoint(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: &ParentChainType,
+        checkpoint: &BlockHeader,
+        block_hash: &bitcoin::BlockHash,
+        required_confirmations: u32,
+    ) -> Result<bool, Error> {
+        let checkpoint_hash = checkpoint.block_hash();
+        let (Some(checkpoint_confirmations), Some(confirmations)) = (
+            self.confirmations(rotxn, parent_chain, &checkpoint_hash)?,
+            self.confirmations(rotxn, parent_chain, block_hash)?,
+        ) else {
+            return Ok(false);
+        };
+        if confirmations >= checkpoint_confirmations
+            || confirmations < required_confirmations
+        {
+            return Ok(false);
+        }
+        let (Some(checkpoint), Some(tip)) = (
+            self.try_get_header(rotxn, parent_chain, &checkpoint_hash)?,
+            self.try_get_tip(rotxn, parent_chain)?,
+        ) else {
+            return Ok(false);
+        };
+        // There are at least `required_confirmations` headers after the
+        // checkpoint, so this is bounded by the length of the relay
+        let no_work = Work::from_le_bytes([0; 32]);
+        let required_work = (0..required_confirmations)
+            .fold(no_work, |work, _| work + checkpoint.header.work());
+        Ok(tip.chain_work - checkpoint.chain_work >= required_work)
+    }
+
+    /// Validate headers without storing them, returning the relay headers
+    /// that they would be stored as
+    pub(in crate::state) fn validate_headers(
+        &self,
+        rotxn: &RoTxn,
+        parent_chain: &ParentChainType,
+        start_height: u32,
+        headers: &[BlockHeader],
+    ) -> Result<Vec<RelayHeader>, Error> {
+        let Some(anchor) = (self.anchor)(parent_chain) else {
+            return Err(Error::UnsupportedChain(*parent_chain));
+        };
+        let Some(first_header) = headers.first() else {
+            return Err(Error::Empty);
+        };
+        let Some(mut parent) = self.try_get_header(
+            rotxn,
+            parent_chain,
+            &first_header.prev_blockhash,
+        )?
+        else {
+            return Err(Error::UnknownParent {
+                parent_chain: *parent_chain,
+                prev_blockhash: first_header.prev_blockhash,
+            });
+        };
+        if parent.height.checked_add(1) != Some(start_height) {
+            return Err(Error::HeightMismatch {
+                parent_height: parent.height,
+                provided: start_height,
+            });
+        }
+        let mut relay_headers = Vec::with_capacity(headers.len());
+        for (index, header) in headers.iter().enumerate() {
+            let block_hash = header.block_hash();
+            if self
+                .try_get_header(rotxn, parent_chain, &block_hash)?
+                .is_some()
+            {
+                return Err(Error::DuplicateHeader {
+                    parent_chain: *parent_chain,
+                    block_hash,
+                });
+            }
+            let height = start_height + index as u32;
+            if header.prev_blockhash != parent.header.block_hash() {
+                return Err(Error::BrokenHeaderChain { index });
+            }
+            // Headers are checked against the anchor target, as well as
+            // their parent, so that the target can not drift to a trivial
+            // difficulty
+            if !is_valid_target(parent_chain, &parent.header, header, height)
+                || header.target() > anchor.header.target()
+            {
+                return Err(Error::InvalidTarget { index });
+            }
+            proof::validate_header_pow(parent_chain, header)
+                .map_err(|_| Error::InvalidPow { index })?;
+            let relay_header = RelayHeader {
+                header: *header,
+                height,
+                chain_work: parent.chain_work + header.work(),
+            };
+            relay_headers.push(relay_header);
+            parent = relay_header;
+        }
+        Ok(relay_headers)
+    }
+
+    /// Index the relayed chain that ends at `tip` by height. Only the
+    /// heights after the fork with the previously indexed chain are written.
+    fn index_best_chain(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: &ParentChainType,
+        tip: &RelayTip,
+    ) -> Result<(), Error> {
+        let mut height = tip.height + 1;
+        while self.best_chain.delete(rwtxn, &(*parent_chain, height))? {
+            height += 1;
+        }
+        let mut block_hash = tip.block_hash;
+        let mut height = tip.height;
+        loop {
+            let key = (*parent_chain, height);
+            if self.best_chain.try_get(rwtxn, &key)? == Some(block_hash) {
+                break;
+            }
+            self.best_chain.put(rwtxn, &key, &block_hash)?;
+            let Some(relay_header) =
+                self.try_get_header(rwtxn, parent_chain, &block_hash)?
+            else {
+                return Err(Error::MissingHeader {
+                    parent_chain: *parent_chain,
+                    block_hash,
+                });
+            };
+            // Anchors are at height 0
+            let Some(prev_height) = height.checked_sub(1) else {
+                break;
+            };
+            block_hash = relay_header.header.prev_blockhash;
+            height = prev_height;
+        }
+        Ok(())
+    }
+
+    /// Store headers, updating the relay tip if they extend the chain with
+    /// the most work
+    pub(in crate::state) fn apply_headers(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: &ParentChainType,
+        start_height: u32,
+        headers: &[BlockHeader],
+        height: u32,
+    ) -> Result<(), Error> {
+        let relay_headers =
+            self.validate_headers(rwtxn, parent_chain, start_height, headers)?;
+        for relay_header in &relay_headers {
+            let key = (*parent_chain, relay_header.header.block_hash());
+            self.headers.put(rwtxn, &key, relay_header)?;
+        }
+        // Chain work increases along the headers, so only the last header
+        // can become the new tip
+        let Some(last_header) = relay_headers.last() else {
+            return Err(Error::Empty);
+        };
+        let new_tip = RelayTip::from(last_header);
+        let tip = match self.tips.try_get(rwtxn, parent_chain)? {
+            Some(mut tip) => {
+                if new_tip.chain_work <= tip.latest().value.chain_work {
+                    return Ok(());
+                }
+                tip.push(new_tip, height)
+                    .expect("Push relay tip should be valid");
+                tip
+            }
+            None => RollBack::<HeightStamped<_>>::new(new_tip, height),
+        };
+        self.tips.put(rwtxn, parent_chain, &tip)?;
+        let () = self.index_best_chain(rwtxn, parent_chain, &new_tip)?;
+        Ok(())
+    }
+
+    /// Remove headers that were stored at the specified height
+    pub(in crate::state) fn revert_headers(
+        &self,
+        rwtxn: &mut RwTxn,
+        parent_chain: &ParentChainType,
+        headers: &[BlockHeader],
+        height: u32,
+    ) -> Result<(), Error> {
+        if let Some(tip) = self.tips.try_get(rwtxn, parent_chain)? {
+            let latest = tip.latest();
+            if latest.height == height
+                && headers
+                    .iter()
+                    .any(|header| header.block_hash() == latest.value.block_hash)
+            {
+                let (prev_tip, _) = tip.pop();
+                if let Some(prev_tip) = prev_tip {
+                    self.tips.put(rwtxn, parent_chain, &prev_tip)?;
+                } else {
+                    self.tips.delete(rwtxn, parent_chain)?;
+                }
+                if let Some(prev_tip) = self.try_get_tip(rwtxn, parent_chain)? {
+                    let () =
+                        self.index_best_chain(rwtxn, parent_chain, &prev_tip)?;
+                }
+            }
+        }
+        for header in headers.iter().rev() {
+            let block_hash = header.block_hash();
+            if !self.headers.delete(rwtxn, &(*parent_chain, block_hash))? {
+                return Err(Error::MissingHeader {
+                    parent_chain: *parent_chain,
+                    block_hash,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        CompactTarget, TxMerkleNode, block::Version, hashes::Hash as _,
+    };
+    use fallible_iterator::FallibleIterator as _;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        state::{block::BlockTxs, test_utils::create_test_state},
+        types::{Transaction, TxData},
+    };
+
+    /// Regtest-difficulty header target
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    /// Target of the BCH test anchor. BCH targets may increase slightly
+    /// every block, so the anchor is harder than regtest difficulty.
+    const BCH_ANCHOR_BITS: u32 = 0x20700000;
+
+    /// Relay anchor for tests, mined at a difficulty that tests can meet
+    fn test_anchor(parent_chain: &ParentChainType) -> Option<RelayHeader> {
+        let _: RelayHeader = consensus_anchor(parent_chain)?;
+        let bits = match parent_chain {
+            ParentChainType::Bch => BCH_ANCHOR_BITS,
+            _ => REGTEST_BITS,
+        };
+        let header = mine_header(bitcoin::BlockHash::all_zeros(), 0, bits);
+        Some(RelayHeader {
+            header,
+            height: 0,
+            chain_work: header.work(),
+        })
+    }
+
+    fn anchor_hash(parent_chain: &ParentChainType) -> bitcoin::BlockHash {
+        test_anchor(parent_chain).unwrap().header.block_hash()
+    }
+
+    fn create_test_dbs() -> (Dbs, sneed::Env, TempDir) {
+        create_test_dbs_with_anchor(test_anchor)
+    }
+
+    fn create_test_dbs_with_anchor(
+        anchor: fn(&ParentChainType) -> Option<RelayHeader>,
+    ) -> (Dbs, sneed::Env, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let env = {
+            let mut env_open_opts = heed::EnvOpenOptions::new();
+            env_open_opts.map_size(10 * 1024 * 1024).max_dbs(Dbs::NUM_DBS);
+            unsafe { sneed::Env::open(&env_open_opts, temp_dir.path()) }
+                .unwrap()
+        };
+        let mut rwtxn = env.write_txn().unwrap();
+        let dbs = Dbs {
+            anchor,
+            ..Dbs::new(&env, &mut rwtxn).unwrap()
+        };
+        rwtxn.commit().unwrap();
+        (dbs, env, temp_dir)
+    }
+
+    /// Mine a header on top of `prev_blockhash`
+    fn mine_header(
+        prev_blockhash: bitcoin::BlockHash,
+        time: u32,
+        bits: u32,
+    ) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: Version::TWO,
+            prev_blockhash,
+            merkle_root: TxMerkleNode::all_zeros(),
+            time,
+            bits: CompactTarget::from_consensus(bits),
+            nonce: 0,
+        };
+        while header.validate_pow(header.target()).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    fn mine_chain(
+        prev_blockhash: bitcoin::BlockHash,
+        len: u32,
+        time: u32,
+    ) -> Vec<BlockHeader> {
+        let mut prev_blockhash = prev_blockhash;
+        (0..len)
+            .map(|i| {
+                let header =
+                    mine_header(prev_blockhash, time + i, REGTEST_BITS);
+                prev_blockhash = header.block_hash();
+                header
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_relay_tip_follows_most_work() {
+        let (dbs, env, _temp_dir) = create_test_dbs();
+        let parent_chain = ParentChainType::Btc;
+        let anchor = anchor_hash(&parent_chain);
+        let branch_a = mine_chain(anchor, 3, 100);
+        let branch_b = mine_chain(anchor, 4, 200);
+
+        let mut rwtxn = env.write_txn().unwrap();
+        let tip = dbs.try_get_tip(&rwtxn, &parent_chain).unwrap().unwrap();
+        assert_eq!(tip.block_hash, anchor);
+        dbs.apply_headers(&mut rwtxn, &parent_chain, 1, &branch_a, 1)
+            .unwrap();
+        let tip = dbs.try_get_tip(&rwtxn, &parent_chain).unwrap().unwrap();
+        assert_eq!(tip.block_hash, branch_a[2].block_hash());
+        assert_eq!(tip.height, 3);
+        assert_eq!(
+            dbs.confirmations(&rwtxn, &parent_chain, &branch_a[0].block_hash())
+                .unwrap(),
+            Some(3)
+        );
+
+        // A heavier branch takes over the tip
+        dbs.apply_headers(&mut rwtxn, &parent_chain, 1, &branch_b, 2)
+            .unwrap();
+        let tip = dbs.try_get_tip(&rwtxn, &parent_chain).unwrap().unwrap();
+        assert_eq!(tip.block_hash, branch_b[3].block_hash());
+        assert_eq!(
+            dbs.confirmations(&rwtxn, &parent_chain, &branch_a[0].block_hash())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            dbs.confirmations(&rwtxn, &parent_chain, &anchor).unwrap(),
+            Some(5)
+        );
+
+        // Reverting the heavier branch restores the previous tip
+        dbs.revert_headers(&mut rwtxn, &parent_chain, &branch_b, 2)
+            .unwrap();
+        let tip = dbs.try_get_tip(&rwtxn, &parent_chain).unwrap().unwrap();
+        assert_eq!(tip.block_hash, branch_a[2].block_hash());
+        assert_eq!(
+            dbs.confirmations(&rwtxn, &parent_chain, &branch_a[0].block_hash())
+                .unwrap(),
+            Some(3)
+        );
+        assert!(
+            dbs.try_get_header(&rwtxn, &parent_chain, &branch_b[0].block_hash())
+                .unwrap()
+                .is_none()
+        );
+
+        // Reverting everything leaves only the anchor
+        dbs.revert_headers(&mut rwtxn, &parent_chain, &branch_a, 1)
+            .unwrap();
+        let tip = dbs.try_get_tip(&rwtxn, &parent_chain).unwrap().unwrap();
+        assert_eq!(tip.block_hash, anchor);
+        assert_eq!(
+            dbs.confirmations(&rwtxn, &parent_chain, &anchor).unwrap(),
+            Some(1)
+        );
+        let best_chain: Vec<_> =
+            dbs.best_chain.iter(&rwtxn).unwrap().collect().unwrap();
+        assert_eq!(best_chain, vec![((parent_chain, 0), anchor)]);
+    }
+
+    #[test]
+    fn test_invalid_headers_rejected() {
+        let (dbs, env, _temp_dir) = create_test_dbs();
+        let parent_chain = ParentChainType::Btc;
+        let chain = mine_chain(anchor_hash(&parent_chain), 2, 0);
+        let mut rwtxn = env.write_txn().unwrap();
+        dbs.apply_headers(&mut rwtxn, &parent_chain, 1, &chain, 0)
+            .unwrap();
+
+        // Headers that do not build on the anchor
+        let orphan = mine_chain(bitcoin::BlockHash::all_zeros(), 1, 50);
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &parent_chain, 1, &orphan),
+            Err(Error::UnknownParent { .. })
+        ));
+
+        // Wrong start height
+        let next = mine_chain(chain[1].block_hash(), 1, 100);
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &parent_chain, 4, &next),
+            Err(Error::HeightMismatch { .. })
+        ));
+
+        // Duplicate header
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &parent_chain, 2, &chain[1..]),
+            Err(Error::DuplicateHeader { .. })
+        ));
+
+        // Target change outside of a retarget height
+        let harder = mine_header(chain[1].block_hash(), 100, 0x1f7fffff);
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &parent_chain, 3, &[harder]),
+            Err(Error::InvalidTarget { index: 0 })
+        ));
+
+        // Invalid proof-of-work
+        let mut invalid_pow = next[0];
+        while invalid_pow.validate_pow(invalid_pow.target()).is_ok() {
+            invalid_pow.nonce += 1;
+        }
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &parent_chain, 3, &[invalid_pow]),
+            Err(Error::InvalidPow { index: 0 })
+        ));
+
+        // Unsupported chain
+        assert!(matches!(
+            dbs.validate_headers(&rwtxn, &ParentChainType::Eth, 3, &next),
+            Err(Error::UnsupportedChain(ParentChainType::Eth))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_headers_in_block_rejected() {
+        let (state, env, _temp_dir) = create_test_state();
+        let chain = mine_chain(bitcoin::BlockHash::all_zeros(), 2, 0);
+        let rotxn = env.read_txn().unwrap();
+        let mut block_txs = BlockTxs::default();
+        let mut validate = |headers: &[BlockHeader]| {
+            let tx = Transaction {
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                memo: Vec::new(),
+                data: Some(TxData::ParentChainHeaders {
+                    parent_chain: ParentChainType::Btc,
+                    start_height: 1,
+                    headers: headers.to_vec(),
+                }),
+            };
+            let filled_tx = state.fill_transaction(&rotxn, &tx).unwrap();
+            block_txs.validate_tx(&state, &rotxn, &filled_tx, 0)
+        };
+        assert!(validate(&chain[..1]).is_ok());
+        assert!(validate(&chain[1..]).is_ok());
+        // Both headers were already relayed earlier in the block
+        assert!(matches!(
+            validate(&chain),
+            Err(crate::state::Error::HeaderRelay(
+                Error::DuplicateHeader { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_consensus_anchors() {
+        let anchor_hash = |parent_chain| {
+            consensus_anchor(&parent_chain).unwrap().header.block_hash()
+        };
+        let btc_genesis_hash =
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        assert_eq!(
+            anchor_hash(ParentChainType::Btc).to_string(),
+            btc_genesis_hash
+        );
+        assert_eq!(
+            anchor_hash(ParentChainType::Bch).to_string(),
+            btc_genesis_hash
+        );
+        assert_eq!(
+            anchor_hash(ParentChainType::Ltc).to_string(),
+            "12a765e31ffd4059bada1e25190f6e98c99d9714d334efa41a195a7e7e04bfe2"
+        );
+        assert!(consensus_anchor(&ParentChainType::Eth).is_none());
+
+        // Regtest-difficulty headers can not build on a consensus anchor
+        let (dbs, env, _temp_dir) =
+            create_test_dbs_with_anchor(consensus_anchor);
+        let rotxn = env.read_txn().unwrap();
+        let headers = mine_chain(anchor_hash(ParentChainType::Bch), 1, 0);
+        assert!(matches!(
+            dbs.validate_headers(&rotxn, &ParentChainType::Bch, 1, &headers),
+            Err(Error::InvalidTarget { index: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_targets_bounded_by_anchor() {
+        let (dbs, env, _temp_dir) = create_test_dbs();
+        let parent_chain = ParentChainType::Bch;
+        let rotxn = env.read_txn().unwrap();
+        // Within the BCH margin of the parent, but above the anchor target
+        let easier = mine_header(
+            anchor_hash(&parent_chain),
+            100,
+            BCH_ANCHOR_BITS + 0xb0000,
+        );
+        assert!(is_valid_target(
+            &parent_chain,
+            &test_anchor(&parent_chain).unwrap().header,
+            &easier,
+            1
+        ));
+        assert!(matches!(
+            dbs.validate_headers(&rotxn, &parent_chain, 1, &[easier]),
+            Err(Error::InvalidTarget { index: 0 })
+        ));
+        let same =
+            mine_header(anchor_hash(&parent_chain), 100, BCH_ANCHOR_BITS);
+        dbs.validate_headers(&rotxn, &parent_chain, 1, &[same])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_confirmed_after_checkpoint() {
+        let (dbs, env, _temp_dir) = create_test_dbs();
+        let parent_chain = ParentChainType::Btc;
+        let chain = mine_chain(anchor_hash(&parent_chain), 5, 0);
+        let other_branch = mine_chain(chain[0].block_hash(), 1, 100);
+        let mut rwtxn = env.write_txn().unwrap();
+        dbs.apply_headers(&mut rwtxn, &parent_chain, 1, &chain, 0)
+            .unwrap();
+        dbs.apply_headers(&mut rwtxn, &parent_chain, 2, &other_branch, 0)
+            .unwrap();
+        let is_confirmed = |checkpoint: &BlockHeader, block_hash, required| {
+            dbs.is_confirmed_after_checkpoint(
+                &rwtxn,
+                &parent_chain,
+                checkpoint,
+                &block_hash,
+                required,
+            )
+            .unwrap()
+        };
+        // Block 3 has 3 confirmations
+        assert!(is_confirmed(&chain[0], chain[2].block_hash(), 3));
+        assert!(!is_confirmed(&chain[0], chain[2].block_hash(), 4));
+        // The block must come after the checkpoint
+        assert!(!is_confirmed(&chain[2], chain[2].block_hash(), 1));
+        assert!(!is_confirmed(&chain[3], chain[2].block_hash(), 1));
+        // The checkpoint must be on the relayed chain with the most work
+        assert!(!is_confirmed(&other_branch[0], chain[2].block_hash(), 1));
+        let unknown =
+            mine_header(bitcoin::BlockHash::all_zeros(), 7, REGTEST_BITS);
+        assert!(!is_confirmed(&unknown, chain[2].block_hash(), 1));
+    }
+
+    #[test]
+    fn test_bch_target_margin() {
+        let target = Target::from_le_bytes({
+            let mut bytes = [0u8; 32];
+            bytes[20] = 0x80;
+            bytes
+        });
+        let max_target =
+            target_with_margin(target, BCH_MAX_TARGET_INCREASE_SHIFT);
+        let mut expected = [0u8; 32];
+        expected[20] = 0x90;
+        assert_eq!(max_target.to_le_bytes(), expected);
+        assert_eq!(
+            target_with_margin(Target::from_le_bytes([0xff; 32]), 3)
+                .to_le_bytes(),
+            [0xff; 32]
+        );
+    }
+}
@@ -9,13 +9,16 @@ use sneed::{DatabaseUnique, RoDatabaseUnique, RoTxn, RwTxn, UnitKey};
 
 use crate::{
     authorization::Authorization,
-    parent_chain::{swap::Swap, SwapId, client::TxId, config::ParentChainType},
+    parent_chain::{
+        L1TxProof, SwapId, client::TxId, config::ParentChainType,
+        swap::{Swap, SwapAsset, SwapDirection, SwapState},
+    },
     types::{
         Address, AmountOverflowError, Authorized, AuthorizedTransaction,
         BitAssetId, BlockHash, Body, FilledOutput, FilledTransaction,
         GetAddress as _, GetBitcoinValue as _, Header, InPoint, M6id, OutPoint,
-        OutPointKey, SpentOutput, Transaction, TxData, VERSION, Verify as _,
-        Version, WithdrawalBundle, WithdrawalBundleStatus,
+        OutPointKey, OutputContent, SpentOutput, Transaction, TxData, VERSION,
+        Verify as _, Version, WithdrawalBundle, WithdrawalBundleStatus,
         proto::mainchain::TwoWayPegData,
     },
     util::Watchable,
@@ -26,13 +29,18 @@ pub mod bitassets;
 mod block;
 mod dutch_auction;
 pub mod error;
+pub mod header_relay;
 mod rollback;
+mod swap;
+#[cfg(test)]
+mod test_utils;
 mod two_way_peg_data;
 
 pub use amm::{AmmPair, PoolState as AmmPoolState};
 pub use bitassets::SeqId as BitAssetSeqId;
 pub use dutch_auction::DutchAuctionState;
 pub use error::Error;
+pub use header_relay::{RelayHeader, RelayTip};
 use rollback::{HeightStamped, RollBack};
 
 pub const WITHDRAWAL_BUNDLE_FAILURE_GAP: u32 = 4;
@@ -89,6 +97,8 @@ pub struct State {
     bitassets: bitassets::Dbs,
     /// Associates Dutch auction sequence numbers with auction state
     dutch_auctions: dutch_auction::Db,
+    /// Relayed parent chain headers
+    header_relay: header_relay::Dbs,
     utxos: DatabaseUnique<OutPointKey, SerdeBincode<FilledOutput>>,
     stxos: DatabaseUnique<OutPointKey, SerdeBincode<SpentOutput>>,
     /// Pending withdrawal bundle and block height
@@ -125,15 +135,23 @@ pub struct State {
     >,
     /// Outputs locked to swaps (can only be spent by SwapClaim)
     /// Maps OutPoint -> SwapId for L2 → L1 swaps
-    locked_swap_outputs: DatabaseUnique<
-        SerdeBincode<OutPointKey>,
+    locked_swap_outputs: DatabaseUnique<OutPointKey, SerdeBincode<SwapId>>,
+    /// L1 outputs that paid for swap claims, keyed by parent chain, L1 txid
+    /// and output index. Each L1 output can pay for at most one claim.
+    claimed_l1_outputs: DatabaseUnique<
+        SerdeBincode<(ParentChainType, TxId, u32)>,
         SerdeBincode<SwapId>,
     >,
+    /// Swap data replaced by claims and refunds, restored when they are
+    /// disconnected
+    swap_rollbacks: swap::RollbacksDb,
     _version: DatabaseUnique<UnitKey, SerdeBincode<Version>>,
 }
 
 impl State {
-    pub const NUM_DBS: u32 = bitassets::Dbs::NUM_DBS + 16; // Added 3 swap databases + 1 locked outputs database
+    pub const NUM_DBS: u32 = bitassets::Dbs::NUM_DBS
+        + header_relay::Dbs::NUM_DBS
+        + 18; // Added 6 swap databases
 
     pub fn new(env: &sneed::Env) -> Result<Self, Error> {
         let mut rwtxn = env.write_txn()?;
@@ -143,6 +161,7 @@ impl State {
         let bitassets = bitassets::Dbs::new(env, &mut rwtxn)?;
         let dutch_auctions =
             DatabaseUnique::create(env, &mut rwtxn, "dutch_auctions")?;
+        let header_relay = header_relay::Dbs::new(env, &mut rwtxn)?;
         let utxos = DatabaseUnique::create(env, &mut rwtxn, "utxos")?;
         let stxos = DatabaseUnique::create(env, &mut rwtxn, "stxos")?;
         let pending_withdrawal_bundle = DatabaseUnique::create(
@@ -180,6 +199,13 @@ impl State {
             &mut rwtxn,
             "locked_swap_outputs",
         )?;
+        let claimed_l1_outputs = DatabaseUnique::create(
+            env,
+            &mut rwtxn,
+            "claimed_l1_outputs",
+        )?;
+        let swap_rollbacks =
+            DatabaseUnique::create(env, &mut rwtxn, "swap_rollbacks")?;
         let version = DatabaseUnique::create(env, &mut rwtxn, "state_version")?;
         if version.try_get(&rwtxn, &())?.is_none() {
             version.put(&mut rwtxn, &(), &*VERSION)?;
@@ -191,6 +217,7 @@ impl State {
             amm_pools,
             bitassets,
             dutch_auctions,
+            header_relay,
             utxos,
             stxos,
             pending_withdrawal_bundle,
@@ -202,6 +229,8 @@ impl State {
             swaps_by_l1_txid,
             swaps_by_recipient,
             locked_swap_outputs,
+            claimed_l1_outputs,
+            swap_rollbacks,
             _version: version,
         })
     }
@@ -227,6 +256,10 @@ impl State {
         &self.dutch_auctions
     }
 
+    pub fn header_relay(&self) -> &header_relay::Dbs {
+        &self.header_relay
+    }
+
     /// Get swap by ID
     pub fn get_swap(
         &self,
@@ -270,8 +303,9 @@ impl State {
         }
     }
 
-    /// Save a swap to the database
-    pub fn save_swap(
+    /// Save a swap to the database. Swaps are consensus data, so this is
+    /// only used when connecting and disconnecting blocks.
+    pub(in crate::state) fn save_swap(
         &self,
         rwtxn: &mut RwTxn,
         swap: &Swap,
@@ -283,21 +317,23 @@ impl State {
         let l1_key = (swap.parent_chain.clone(), swap.l1_txid.clone());
         self.swaps_by_l1_txid.put(rwtxn, &l1_key, &swap.id)?;
 
-        // Update recipient index
-        let mut swap_ids = self
-            .swaps_by_recipient
-            .try_get(rwtxn, &swap.l2_recipient)?
-            .unwrap_or_default();
-        if !swap_ids.contains(&swap.id) {
-            swap_ids.push(swap.id.clone());
-            self.swaps_by_recipient.put(rwtxn, &swap.l2_recipient, &swap_ids)?;
+        // Update recipient index. Open offers are indexed once filled.
+        if let Some(recipient) = swap.recipient() {
+            let mut swap_ids = self
+                .swaps_by_recipient
+                .try_get(rwtxn, &recipient)?
+                .unwrap_or_default();
+            if !swap_ids.contains(&swap.id) {
+                swap_ids.push(swap.id.clone());
+                self.swaps_by_recipient.put(rwtxn, &recipient, &swap_ids)?;
+            }
         }
 
         Ok(())
     }
 
     /// Delete a swap from the database
-    pub fn delete_swap(
+    pub(in crate::state) fn delete_swap(
         &self,
         rwtxn: &mut RwTxn,
         swap_id: &SwapId,
@@ -311,21 +347,85 @@ impl State {
             self.swaps_by_l1_txid.delete(rwtxn, &l1_key)?;
 
             // Update recipient index
-            if let Some(mut swap_ids) = self
-                .swaps_by_recipient
-                .try_get(rwtxn, &swap.l2_recipient)?
+            if let Some(recipient) = swap.recipient()
+                && let Some(mut swap_ids) =
+                    self.swaps_by_recipient.try_get(rwtxn, &recipient)?
             {
                 swap_ids.retain(|id| id != swap_id);
                 if swap_ids.is_empty() {
-                    self.swaps_by_recipient.delete(rwtxn, &swap.l2_recipient)?;
+                    self.swaps_by_recipient.delete(rwtxn, &recipient)?;
                 } else {
-                    self.swaps_by_recipient.put(rwtxn, &swap.l2_recipient, &swap_ids)?;
+                    self.swaps_by_recipient.put(rwtxn, &recipient, &swap_ids)?;
                 }
             }
         }
         Ok(())
     }
 
+    /// Lock an output to a swap, so that it can only be spent by a
+    /// `SwapClaim`
+    pub fn lock_output_to_swap(
+        &self,
+        rwtxn: &mut RwTxn,
+        outpoint: &OutPoint,
+        swap_id: &SwapId,
+    ) -> Result<(), Error> {
+        let key = OutPointKey::from_outpoint(outpoint);
+        self.locked_swap_outputs.put(rwtxn, &key, swap_id)?;
+        Ok(())
+    }
+
+    /// Unlock an output that was locked to a swap
+    pub fn unlock_output_from_swap(
+        &self,
+        rwtxn: &mut RwTxn,
+        outpoint: &OutPoint,
+    ) -> Result<(), Error> {
+        let key = OutPointKey::from_outpoint(outpoint);
+        self.locked_swap_outputs.delete(rwtxn, &key)?;
+        Ok(())
+    }
+
+    /// Get the swap that an output is locked to, if any
+    pub fn is_output_locked_to_swap(
+        &self,
+        rotxn: &RoTxn,
+        outpoint: &OutPoint,
+    ) -> Result<Option<SwapId>, Error> {
+        let key = OutPointKey::from_outpoint(outpoint);
+        Ok(self.locked_swap_outputs.try_get(rotxn, &key)?)
+    }
+
+    /// Get the addresses whose keys must authorize each input of a tx, in
+    /// input order, if the tx is included in the next block
+    pub fn get_authorizers(
+        &self,
+        rotxn: &RoTxn,
+        filled_tx: &FilledTransaction,
+    ) -> Result<Vec<Address>, Error> {
+        let height = self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+        filled_tx
+            .transaction
+            .inputs
+            .iter()
+            .zip(filled_tx.spent_utxos.iter())
+            .map(|(input, spent_utxo)| {
+                let locked_to =
+                    match self.is_output_locked_to_swap(rotxn, input)? {
+                        Some(swap_id) => self.get_swap(rotxn, &swap_id)?,
+                        None => None,
+                    };
+                Ok(crate::authorization::get_authorizer(
+                    &filled_tx.transaction,
+                    input,
+                    spent_utxo,
+                    locked_to.as_ref(),
+                    height,
+                ))
+            })
+            .collect()
+    }
+
     /// Load all swaps from database
     pub fn load_all_swaps(&self, rotxn: &RoTxn) -> Result<Vec<Swap>, Error> {
         let swaps: Vec<Swap> = self
@@ -709,6 +809,289 @@ impl State {
         }
     }
 
+    /// Check that a swap can be claimed with the provided L1 proof.
+    /// Only consensus data may be used here, so that all nodes agree on
+    /// the validity of a claim.
+    /// If the including block is buried deep enough in the header relay,
+    /// the proof's own headers are not needed. Otherwise, the proof must
+    /// build on the swap's checkpoint.
+    fn validate_swap_claim_proof(
+        &self,
+        rotxn: &RoTxn,
+        swap: &Swap,
+        proof: &L1TxProof,
+    ) -> Result<(), Error> {
+        if matches!(swap.state, SwapState::Completed | SwapState::Cancelled) {
+            return Err(error::Swap::NotClaimable {
+                swap_id: swap.id.clone(),
+                state: swap.state.clone(),
+            }
+            .into());
+        }
+        // Claims are only accepted once the swap commits to an L1
+        // checkpoint, whichever way the including block is confirmed
+        let Some(checkpoint) = &swap.l1_checkpoint else {
+            return Err(error::Swap::MissingL1Checkpoint {
+                swap_id: swap.id.clone(),
+            }
+            .into());
+        };
+        // The header relay can stand in for the headers of the proof, if
+        // the relayed chain confirms the including block after the
+        // checkpoint
+        let relay_confirmed = match proof.including_header() {
+            Ok(including_header) => {
+                self.header_relay.is_confirmed_after_checkpoint(
+                    rotxn,
+                    &swap.parent_chain,
+                    checkpoint,
+                    &including_header.block_hash(),
+                    swap.required_confirmations,
+                )?
+            }
+            Err(_) => false,
+        };
+        let res = if relay_confirmed {
+            proof.verify_inclusion(&swap.parent_chain)
+        } else {
+            proof.verify(
+                &swap.parent_chain,
+                checkpoint,
+                swap.required_confirmations,
+            )
+        };
+        let () = res.map_err(|source| error::Swap::InvalidL1Proof {
+            swap_id: swap.id.clone(),
+            source,
+        })?;
+        // Each fill of a partially fillable offer pays its own L1 amount
+        let l1_amount = if swap.partial_fills {
+            let proven = proof.l1_txid();
+            let Some(fill) = swap.claimable_fill(&proven) else {
+                return Err(error::Swap::NoClaimableFill {
+                    swap_id: swap.id.clone(),
+                    l1_txid: proven,
+                }
+                .into());
+            };
+            fill.l1_amount
+        } else {
+            swap.l1_amount
+        };
+        // The L1 transaction must pay the L1 recipient of the swap. Only
+        // L1 → L2 swaps, whose L1 txid is fixed when the swap is created,
+        // have no L1 payment to check.
+        match (&swap.l1_recipient_address, l1_amount) {
+            (Some(l1_recipient_address), Some(l1_amount)) => {
+                let script_pubkey =
+                    crate::parent_chain::address::script_pubkey(
+                        &swap.parent_chain,
+                        l1_recipient_address,
+                    )
+                    .map_err(|source| {
+                        error::Swap::InvalidL1Address {
+                            swap_id: swap.id.clone(),
+                            source,
+                        }
+                    })?;
+                let () = proof
+                    .verify_payment(&script_pubkey, l1_amount)
+                    .map_err(|source| error::Swap::InvalidL1Proof {
+                        swap_id: swap.id.clone(),
+                        source,
+                    })?;
+            }
+            (None, _) if swap.direction == SwapDirection::L1ToL2 => (),
+            _ => {
+                return Err(error::Swap::MissingL1Amount {
+                    swap_id: swap.id.clone(),
+                }
+                .into());
+            }
+        }
+        // ...with outputs that have not paid for another claim
+        let _: Vec<u32> =
+            swap::l1_payment_vouts(self, rotxn, swap, proof, &HashSet::new())?;
+        // The L1 txid of an L1 → L2 swap is fixed when the swap is created,
+        // and the L1 txid of an open offer is fixed when it is filled
+        if !swap.partial_fills
+            && (swap.direction == SwapDirection::L1ToL2
+                || swap.current_fill().is_some())
+        {
+            let proven = proof.l1_txid();
+            let expected = swap.expected_l1_txid();
+            if proven != *expected {
+                return Err(error::Swap::L1TxidMismatch {
+                    swap_id: swap.id.clone(),
+                    expected: expected.clone(),
+                    proven,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that outputs locked to an expired or completed swap, and
+    /// forfeited bonds, can be refunded by a `SwapRefund` tx.
+    /// Locked outputs are refunded with the signatures of their owners: the
+    /// escrow by the offerer, and bonds by their fillers. The bond of a fill
+    /// that timed out before it was claimed is forfeited, and refunded by
+    /// the offerer as soon as the fill times out.
+    fn validate_swap_refund(
+        &self,
+        rotxn: &RoTxn,
+        tx: &FilledTransaction,
+    ) -> Result<(), Error> {
+        let Some(TxData::SwapRefund { swap_id }) = &tx.transaction.data else {
+            return Err(error::Swap::InvalidTxData.into());
+        };
+        let swap_id = SwapId(*swap_id);
+        let swap = self.get_swap(rotxn, &swap_id)?.ok_or_else(|| {
+            Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+        })?;
+        // Refunds are checked against the height of the block that includes
+        // them
+        let height = self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+        let refundable =
+            swap.state == SwapState::Completed || swap.is_expired(height);
+        if tx.transaction.inputs.is_empty() {
+            return Err(Error::InvalidTransaction(
+                "SwapRefund must spend at least one output locked to this swap"
+                    .to_owned(),
+            ));
+        }
+        for input in &tx.transaction.inputs {
+            if self.is_output_locked_to_swap(rotxn, input)?.as_ref()
+                != Some(&swap_id)
+            {
+                return Err(error::Swap::RefundInputNotLocked {
+                    swap_id,
+                    outpoint: *input,
+                }
+                .into());
+            }
+            if !refundable && !swap.is_bond_forfeited(input, height) {
+                return Err(error::Swap::NotExpired {
+                    swap_id,
+                    expires_at_height: swap.expires_at_height,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the hash time-locked inputs and outputs of a tx.
+    /// Inputs whose hash lock is opened by the preimage in an `HtlcRedeem` tx
+    /// are redeemed by their recipient, and must not have timed out. Other
+    /// hash time-locked inputs are refunded by their creator, and must have
+    /// timed out.
+    fn validate_htlcs(
+        &self,
+        rotxn: &RoTxn,
+        tx: &FilledTransaction,
+    ) -> Result<(), Error> {
+        // Time locks are checked against the height of the block that
+        // includes the tx
+        let height = self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+        let preimage = match &tx.transaction.data {
+            Some(TxData::HtlcRedeem { preimage }) => Some(preimage),
+            _ => None,
+        };
+        let mut redeemed = false;
+        for (outpoint, spent_output) in tx.spent_inputs() {
+            let Some(htlc) = spent_output.htlc() else {
+                continue;
+            };
+            if preimage.is_some_and(|preimage| htlc.is_preimage(preimage)) {
+                if !htlc.is_redeemable(height) {
+                    return Err(error::Htlc::TimedOut {
+                        outpoint: *outpoint,
+                        timeout_height: htlc.timeout_height,
+                    }
+                    .into());
+                }
+                redeemed = true;
+            } else if htlc.is_redeemable(height) {
+                return Err(error::Htlc::NotTimedOut {
+                    outpoint: *outpoint,
+                    timeout_height: htlc.timeout_height,
+                }
+                .into());
+            }
+        }
+        if preimage.is_some() && !redeemed {
+            return Err(error::Htlc::NoRedeemedInput.into());
+        }
+        for (vout, output) in tx.transaction.outputs.iter().enumerate() {
+            if let OutputContent::Htlc(htlc) = &output.content
+                && !htlc.is_redeemable(height)
+            {
+                return Err(error::Htlc::InvalidTimeout {
+                    vout: vout as u32,
+                    timeout_height: htlc.timeout_height,
+                    height,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that an open swap offer can be filled by a `SwapFill` tx
+    fn validate_swap_fill(
+        &self,
+        rotxn: &RoTxn,
+        tx: &FilledTransaction,
+    ) -> Result<(), Error> {
+        let Some(TxData::SwapFill {
+            swap_id,
+            l2_recipient: _,
+            l1_txid_bytes,
+            l1_amount,
+        }) = &tx.transaction.data
+        else {
+            return Err(error::Swap::InvalidTxData.into());
+        };
+        let swap_id = SwapId(*swap_id);
+        let swap = self.get_swap(rotxn, &swap_id)?.ok_or_else(|| {
+            Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+        })?;
+        // Fills are checked against the height of the block that includes
+        // them
+        let height = self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+        let () = swap::check_fillable(
+            &swap,
+            height,
+            &swap::l1_txid_from_bytes(l1_txid_bytes),
+            l1_amount.map(bitcoin::Amount::from_sat),
+        )?;
+        if swap.fill_bond > bitcoin::Amount::ZERO {
+            // Like the escrow, the bond must be plain Bitcoin, since
+            // withdrawal and hash time-locked outputs can be spent without
+            // unlocking them
+            let provided = match tx
+                .transaction
+                .outputs
+                .first()
+                .map(|output| &output.content)
+            {
+                Some(OutputContent::Bitcoin(value)) => value.0,
+                _ => bitcoin::Amount::ZERO,
+            };
+            if provided < swap.fill_bond {
+                return Err(error::Swap::InsufficientFillBond {
+                    swap_id,
+                    required: swap.fill_bond,
+                    provided,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
     /// Validates a filled transaction, and returns the fee
     pub fn validate_filled_transaction(
         &self,
@@ -717,9 +1100,10 @@ impl State {
     ) -> Result<bitcoin::Amount, Error> {
         let () = self.validate_reservations(tx)?;
         let () = self.validate_bitassets(rotxn, tx)?;
+        let () = self.validate_htlcs(rotxn, tx)?;
         
         // Validate swap transactions
-        if let Some(TxData::SwapCreate { swap_id, parent_chain, l1_txid_bytes, l2_amount, l2_recipient, l1_recipient_address, .. }) = &tx.transaction.data {
+        if let Some(TxData::SwapCreate { swap_id, parent_chain, l2_amount, l1_recipient_address, l1_amount, l1_checkpoint, escrow_vout, l2_asset, .. }) = &tx.transaction.data {
             // Verify swap doesn't already exist
             let swap_id = SwapId(*swap_id);
             if self.get_swap(rotxn, &swap_id)?.is_some() {
@@ -735,6 +1119,48 @@ impl State {
                 ));
             }
             
+            // Claims are proven against the checkpoint with an L1 proof, and
+            // must pay the L1 recipient. Swaps on chains without L1 proofs
+            // could never be claimed.
+            if !crate::parent_chain::proof::is_supported(parent_chain) {
+                return Err(error::Swap::UnsupportedParentChain {
+                    swap_id,
+                    parent_chain: *parent_chain,
+                }
+                .into());
+            }
+            let Some(l1_checkpoint) = l1_checkpoint else {
+                return Err(error::Swap::MissingL1Checkpoint {
+                    swap_id: swap_id.clone(),
+                }
+                .into());
+            };
+            let () = crate::parent_chain::proof::validate_header_pow(
+                parent_chain,
+                l1_checkpoint,
+            )
+            .map_err(|source| error::Swap::InvalidL1Proof {
+                swap_id: swap_id.clone(),
+                source,
+            })?;
+            if let Some(l1_recipient_address) = l1_recipient_address {
+                let () = crate::parent_chain::address::script_pubkey(
+                    parent_chain,
+                    l1_recipient_address,
+                )
+                .map(drop)
+                .map_err(|source| error::Swap::InvalidL1Address {
+                    swap_id: swap_id.clone(),
+                    source,
+                })?;
+                if !l1_amount.is_some_and(|l1_amount| l1_amount > 0) {
+                    return Err(error::Swap::MissingL1Amount {
+                        swap_id: swap_id.clone(),
+                    }
+                    .into());
+                }
+            }
+
             // COIN LOCKING VALIDATION FOR L2 → L1 SWAPS
             // If l1_recipient_address is set, this is an L2 → L1 swap
             // Alice's coins must be locked when creating the swap
@@ -749,44 +1175,55 @@ impl State {
                     }
                 }
                 
-                // Verify transaction spends at least l2_amount worth of Bitcoin
-                let spent_value = tx.spent_bitcoin_value()
-                    .map_err(|e| Error::InvalidTransaction(format!("Failed to calculate spent value: {:?}", e)))?;
-                let required_amount = bitcoin::Amount::from_sat(*l2_amount);
-                
-                if spent_value < required_amount {
-                    return Err(Error::InvalidTransaction(format!(
-                        "SwapCreate must spend at least {} sats, but only spent {} sats",
-                        required_amount.to_sat(), spent_value.to_sat()
-                    )));
+                // Verify that the escrow output holds exactly l2_amount of
+                // the L2 asset. Other outputs, such as change, are not
+                // locked.
+                let Some(filled_outputs) = tx.filled_outputs() else {
+                    let err = error::FillTxOutputContents(Box::new(tx.clone()));
+                    return Err(err.into());
+                };
+                let (_, actual) = filled_outputs
+                    .get(*escrow_vout as usize)
+                    .and_then(|output| {
+                        SwapAsset::of_output_content(&output.content)
+                    })
+                    .filter(|(asset, _)| asset == l2_asset)
+                    .ok_or_else(|| error::Swap::InvalidEscrowOutput {
+                        swap_id: swap_id.clone(),
+                        vout: *escrow_vout,
+                        asset: *l2_asset,
+                    })?;
+                let expected = *l2_amount;
+                if actual != expected {
+                    return Err(error::Swap::EscrowAmountMismatch {
+                        swap_id: swap_id.clone(),
+                        vout: *escrow_vout,
+                        expected,
+                        actual,
+                    }
+                    .into());
                 }
             }
             
             // Verify swap ID is correctly computed
-            let l1_txid = if l1_txid_bytes.len() == 32 {
-                let mut hash32 = [0u8; 32];
-                hash32.copy_from_slice(l1_txid_bytes);
-                TxId::Hash32(hash32)
-            } else {
-                TxId::Hash(l1_txid_bytes.clone())
-            };
-            
-            let swap = Swap::new(
-                parent_chain.clone(),
-                l1_txid,
-                None, // Will use default confirmations
-                *l2_recipient,
-                bitcoin::Amount::from_sat(*l2_amount),
-                0, // Height doesn't matter for validation
-            );
-            
-            if swap.id.0 != *swap_id {
-                return Err(Error::InvalidTransaction(format!(
-                    "Swap ID mismatch: expected {:?}, got {:?}",
-                    swap.id.0, swap_id
-                )));
+            let height =
+                self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+            let swap = swap::new_swap(tx, height)?;
+            if let Some(expires_at_height) = swap.expires_at_height
+                && expires_at_height <= height
+            {
+                return Err(error::Swap::InvalidExpiry {
+                    swap_id,
+                    expires_at_height,
+                    height,
+                }
+                .into());
             }
-        } else if let Some(TxData::SwapClaim { swap_id, .. }) = &tx.transaction.data {
+        } else if let Some(TxData::SwapFill { .. }) = &tx.transaction.data {
+            let () = self.validate_swap_fill(rotxn, tx)?;
+        } else if let Some(TxData::SwapRefund { .. }) = &tx.transaction.data {
+            let () = self.validate_swap_refund(rotxn, tx)?;
+        } else if let Some(TxData::SwapClaim { swap_id, proof_data }) = &tx.transaction.data {
             let swap_id = SwapId(*swap_id);
             
             // Verify swap exists
@@ -795,13 +1232,34 @@ impl State {
                     format!("Swap not found: {:?}", swap_id)
                 ))?;
             
-            // Verify swap is ready to claim
-            if !matches!(swap.state, crate::parent_chain::swap::SwapState::ReadyToClaim) {
-                return Err(Error::InvalidTransaction(format!(
-                    "Swap not ready to claim: {:?}",
-                    swap.state
-                )));
+            // Claims are checked against the height of the block that
+            // includes them
+            let height =
+                self.try_get_height(rotxn)?.map_or(0, |height| height + 1);
+            if let Some(expires_at_height) = swap.expires_at_height
+                && swap.is_expired(height)
+            {
+                return Err(error::Swap::Expired {
+                    swap_id,
+                    expires_at_height,
+                }
+                .into());
+            }
+
+            // A claim must spend the escrow itself, rather than only a fill
+            // bond, since applying the claim moves the swap's escrow
+            if let Some(escrow) = swap.escrow
+                && !tx.transaction.inputs.contains(&escrow)
+            {
+                return Err(
+                    error::Swap::EscrowNotSpent { swap_id, escrow }.into()
+                );
             }
+
+            // Verify the L1 proof carried by the claim. Readiness is decided
+            // by the proof, never by this node's view of the parent chain.
+            let () =
+                self.validate_swap_claim_proof(rotxn, &swap, proof_data)?;
             
             // COIN UNLOCKING VALIDATION FOR SWAP CLAIMS
             // Verify that at least one input is locked to this swap
@@ -825,19 +1283,82 @@ impl State {
                 ));
             }
             
-            // Verify at least one output goes to the swap recipient
+            // Verify at least one output goes to the swap recipient.
+            // Open offers can only be claimed by their current filler, or
+            // by the filler that paid the proven L1 tx if they are
+            // partially fillable.
+            let Some(recipient) = swap.claimant(&proof_data.l1_txid()) else {
+                return Err(error::Swap::NotFilled { swap_id }.into());
+            };
             let has_recipient_output = tx.transaction.outputs.iter()
-                .any(|output| output.address == swap.l2_recipient);
+                .any(|output| output.address == recipient);
             
             if !has_recipient_output {
                 return Err(Error::InvalidTransaction(
                     "Swap claim transaction must have output to swap recipient".to_string()
                 ));
             }
+
+            // The rest of a partially filled offer must be re-escrowed in
+            // the first output, owned by the offerer
+            if let Some(fill) = swap.claimable_fill(&proof_data.l1_txid()) {
+                let released = swap.fill_l2_amount(
+                    fill.l1_amount.unwrap_or(bitcoin::Amount::ZERO),
+                );
+                let expected =
+                    (swap.l2_amount_remaining - released).to_sat();
+                if expected != 0 {
+                    let Some(filled_outputs) = tx.filled_outputs() else {
+                        let err =
+                            error::FillTxOutputContents(Box::new(tx.clone()));
+                        return Err(err.into());
+                    };
+                    let (_, actual) = filled_outputs
+                        .first()
+                        .filter(|output| {
+                            swap.l2_offerer == Some(output.address)
+                        })
+                        .and_then(|output| {
+                            SwapAsset::of_output_content(&output.content)
+                        })
+                        .filter(|(asset, _)| *asset == swap.l2_asset)
+                        .ok_or_else(|| error::Swap::InvalidEscrowOutput {
+                            swap_id: swap_id.clone(),
+                            vout: 0,
+                            asset: swap.l2_asset,
+                        })?;
+                    if actual != expected {
+                        return Err(error::Swap::EscrowAmountMismatch {
+                            swap_id,
+                            vout: 0,
+                            expected,
+                            actual,
+                        }
+                        .into());
+                    }
+                }
+            }
         }
         
+        if let Some(TxData::ParentChainHeaders {
+            parent_chain,
+            start_height,
+            headers,
+        }) = &tx.transaction.data
+        {
+            let _: Vec<RelayHeader> = self.header_relay.validate_headers(
+                rotxn,
+                parent_chain,
+                *start_height,
+                headers,
+            )?;
+        }
+
         // Prevent locked outputs from being spent by non-swap transactions
-        if !matches!(tx.transaction.data, Some(TxData::SwapClaim { .. })) {
+        if !matches!(
+            tx.transaction.data,
+            Some(TxData::SwapClaim { .. } | TxData::SwapRefund { .. })
+        ) {
             for input in &tx.transaction.inputs {
                 if let Some(locked_swap_id) = self.is_output_locked_to_swap(rotxn, input)? {
                     return Err(Error::InvalidTransaction(format!(
@@ -858,12 +1379,11 @@ impl State {
     ) -> Result<bitcoin::Amount, Error> {
         let filled_transaction =
             self.fill_transaction(rotxn, &transaction.transaction)?;
-        for (authorization, spent_utxo) in transaction
-            .authorizations
-            .iter()
-            .zip(filled_transaction.spent_utxos.iter())
+        let authorizers = self.get_authorizers(rotxn, &filled_transaction)?;
+        for (authorization, authorizer) in
+            transaction.authorizations.iter().zip(authorizers)
         {
-            if authorization.get_address() != spent_utxo.address {
+            if authorization.get_address() != authorizer {
                 return Err(Error::WrongPubKeyForAddress);
             }
         }
@@ -1027,21 +1547,8 @@ impl Watchable<()> for State {
 mod swap_tests {
     use super::*;
     use crate::parent_chain::{swap::Swap, SwapId, client::TxId, config::ParentChainType};
+    use crate::state::test_utils::create_test_state;
     use crate::types::Address;
-    use tempfile::TempDir;
-
-    fn create_test_state() -> (State, sneed::Env, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let env_path = temp_dir.path().join("test.mdb");
-        std::fs::create_dir_all(&env_path).unwrap();
-        
-        let mut env_open_opts = heed::EnvOpenOptions::new();
-        env_open_opts.map_size(1024 * 1024); // 1MB
-        env_open_opts.max_dbs(State::NUM_DBS);
-        let env = unsafe { heed::Env::open(&env_open_opts, &env_path).unwrap() };
-        let state = State::new(&env).unwrap();
-        (state, env, temp_dir)
-    }
 
     fn create_test_swap() -> Swap {
         Swap::new(
@@ -1352,3 +1859,151 @@ mod swap_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod htlc_tests {
+    use bitcoin::hashes::{Hash as _, sha256};
+
+    use super::*;
+    use crate::{
+        state::test_utils::{bitcoin_output, connect_block, create_test_state},
+        types::{Address, Body, HtlcOutputContent, Output, OutputContent},
+    };
+
+    const HTLC_VALUE: u64 = 50_000;
+    const FEE: u64 = 1_000;
+    const PREIMAGE: [u8; 32] = [7u8; 32];
+    const TIMEOUT_HEIGHT: u32 = 3;
+
+    fn validate(
+        state: &State,
+        env: &sneed::Env,
+        tx: &Transaction,
+    ) -> Result<bitcoin::Amount, Error> {
+        let rotxn = env.read_txn().unwrap();
+        let filled_tx = state.fill_transaction(&rotxn, tx)?;
+        state.validate_filled_transaction(&rotxn, &filled_tx)
+    }
+
+    #[test]
+    fn htlc_redeem_and_refund() {
+        let (state, env, _temp_dir) = create_test_state();
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let htlc = HtlcOutputContent {
+            value: bitcoin::Amount::from_sat(HTLC_VALUE),
+            hash_lock: sha256::Hash::hash(&PREIMAGE).to_byte_array(),
+            recipient: bob,
+            timeout_height: TIMEOUT_HEIGHT,
+        };
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, HTLC_VALUE)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        connect_block(&state, &env, &body, false);
+
+        // HTLCs must not time out before the block that includes them
+        let expired_tx = Transaction::new(
+            vec![funding],
+            vec![Output::new(
+                alice,
+                OutputContent::Htlc(HtlcOutputContent {
+                    timeout_height: 1,
+                    ..htlc.clone()
+                }),
+            )],
+        );
+        assert!(matches!(
+            validate(&state, &env, &expired_tx),
+            Err(Error::Htlc(error::Htlc::InvalidTimeout { .. }))
+        ));
+
+        // Block 1: Alice locks her coins for Bob, refundable to her
+        let create_tx = Transaction::new(
+            vec![funding],
+            vec![Output::new(alice, OutputContent::Htlc(htlc.clone()))],
+        );
+        let _: bitcoin::Amount = validate(&state, &env, &create_tx).unwrap();
+        let htlc_outpoint = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 0,
+        };
+        connect_block(
+            &state,
+            &env,
+            &Body {
+                coinbase: Vec::new(),
+                transactions: vec![create_tx],
+                authorizations: Vec::new(),
+            },
+            false,
+        );
+
+        let refund_tx = Transaction::new(
+            vec![htlc_outpoint],
+            vec![bitcoin_output(alice, HTLC_VALUE - FEE)],
+        );
+        let redeem_tx = |preimage| {
+            let mut tx = Transaction::new(
+                vec![htlc_outpoint],
+                vec![bitcoin_output(bob, HTLC_VALUE - FEE)],
+            );
+            tx.data = Some(TxData::HtlcRedeem { preimage });
+            tx
+        };
+
+        // Before the timeout, only Bob can redeem, with the preimage
+        assert!(matches!(
+            validate(&state, &env, &refund_tx),
+            Err(Error::Htlc(error::Htlc::NotTimedOut { .. }))
+        ));
+        assert!(matches!(
+            validate(&state, &env, &redeem_tx([8u8; 32])),
+            Err(Error::Htlc(error::Htlc::NoRedeemedInput))
+        ));
+        let redeem = redeem_tx(PREIMAGE);
+        assert_eq!(
+            validate(&state, &env, &redeem).unwrap(),
+            bitcoin::Amount::from_sat(FEE)
+        );
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_tx = state.fill_transaction(&rotxn, &redeem).unwrap();
+            assert_eq!(
+                state.get_authorizers(&rotxn, &filled_tx).unwrap(),
+                vec![bob]
+            );
+            let filled_tx = state.fill_transaction(&rotxn, &refund_tx).unwrap();
+            assert_eq!(
+                state.get_authorizers(&rotxn, &filled_tx).unwrap(),
+                vec![alice]
+            );
+        }
+
+        // Blocks 2 and 3: from the timeout height, only Alice can refund
+        for _ in 0..2 {
+            connect_block(
+                &state,
+                &env,
+                &Body {
+                    coinbase: Vec::new(),
+                    transactions: Vec::new(),
+                    authorizations: Vec::new(),
+                },
+                false,
+            );
+        }
+        assert!(matches!(
+            validate(&state, &env, &redeem),
+            Err(Error::Htlc(error::Htlc::TimedOut { .. }))
+        ));
+        let _: bitcoin::Amount = validate(&state, &env, &refund_tx).unwrap();
+    }
+}
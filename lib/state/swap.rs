@@ -0,0 +1,2385 @@
+//! Functions related to swaps
+//!
+//! Swap bookkeeping is shared by every path that connects or disconnects
+//! blocks, so that the swap and locked output databases do not depend on
+//! how a block was applied.
+
+use std::collections::HashSet;
+
+use heed::types::SerdeBincode;
+use serde::{Deserialize, Serialize};
+use sneed::{DatabaseUnique, RoTxn, RwTxn};
+
+use crate::{
+    parent_chain::{
+        L1TxProof, ParentChainType, SWAP_ID_VERSION, SwapId, address,
+        client::TxId,
+        swap::{Swap, SwapDirection, SwapFill, SwapState},
+    },
+    state::{
+        Error, State, error,
+        rollback::{HeightStamped, RollBack},
+    },
+    types::{AmountOverflowError, FilledTransaction, OutPoint, TxData},
+};
+
+/// Swap data that a claim or refund replaces, restored when the claim or
+/// refund is disconnected
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(in crate::state) struct SwapRollback {
+    /// Output that escrowed the swap's L2 coins before the tx
+    escrow: Option<OutPoint>,
+    state: SwapState,
+    l1_txid: TxId,
+}
+
+impl SwapRollback {
+    fn new(swap: &Swap) -> Self {
+        Self {
+            escrow: swap.escrow,
+            state: swap.state.clone(),
+            l1_txid: swap.l1_txid.clone(),
+        }
+    }
+
+    /// Restore the swap data that the tx replaced
+    fn restore(self, swap: &mut Swap) {
+        swap.escrow = self.escrow;
+        swap.state = self.state;
+        swap.l1_txid = self.l1_txid;
+    }
+}
+
+/// Rollback data of each swap, pushed by every claim and refund of the swap
+pub(in crate::state) type RollbacksDb = DatabaseUnique<
+    SerdeBincode<SwapId>,
+    SerdeBincode<RollBack<HeightStamped<SwapRollback>>>,
+>;
+
+/// Record the swap data that a tx at the specified height replaces
+fn push_rollback(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    swap_id: &SwapId,
+    rollback: SwapRollback,
+    height: u32,
+) -> Result<(), Error> {
+    let rollbacks = match state.swap_rollbacks.try_get(rwtxn, swap_id)? {
+        Some(mut rollbacks) => {
+            rollbacks
+                .push(rollback, height)
+                .expect("Push swap rollback should be valid");
+            rollbacks
+        }
+        None => RollBack::<HeightStamped<_>>::new(rollback, height),
+    };
+    state.swap_rollbacks.put(rwtxn, swap_id, &rollbacks)?;
+    Ok(())
+}
+
+/// Remove and return the most recent rollback data of a swap
+fn pop_rollback(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    swap_id: &SwapId,
+) -> Result<SwapRollback, Error> {
+    let Some(rollbacks) = state.swap_rollbacks.try_get(rwtxn, swap_id)? else {
+        return Err(error::Swap::MissingRollback {
+            swap_id: swap_id.clone(),
+        }
+        .into());
+    };
+    let (rollbacks, latest) = rollbacks.pop();
+    if let Some(rollbacks) = rollbacks {
+        state.swap_rollbacks.put(rwtxn, swap_id, &rollbacks)?;
+    } else {
+        let _: bool = state.swap_rollbacks.delete(rwtxn, swap_id)?;
+    }
+    Ok(latest.value)
+}
+
+/// L1 output that paid for a swap claim, as parent chain, L1 txid and
+/// output index
+pub(in crate::state) type ClaimedL1Output = (ParentChainType, TxId, u32);
+
+/// Reconstruct an L1 txid from its bytes in tx data
+pub(in crate::state) fn l1_txid_from_bytes(l1_txid_bytes: &[u8]) -> TxId {
+    if let Ok(hash32) = l1_txid_bytes.try_into() {
+        TxId::Hash32(hash32)
+    } else {
+        TxId::Hash(l1_txid_bytes.to_vec())
+    }
+}
+
+/// Whether `outpoint` is `escrow`, or the bond of a fill of the swap. These
+/// are the only outputs that are locked to a swap.
+fn is_escrow_or_bond(
+    swap: &Swap,
+    escrow: Option<&OutPoint>,
+    outpoint: &OutPoint,
+) -> bool {
+    escrow == Some(outpoint)
+        || swap
+            .fills
+            .iter()
+            .any(|fill| fill.bond.as_ref() == Some(outpoint))
+}
+
+/// Build the swap created by a `SwapCreate` tx at the specified height,
+/// checking that the swap ID matches the swap parameters
+pub(in crate::state) fn new_swap(
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<Swap, Error> {
+    let Some(TxData::SwapCreate {
+        swap_id,
+        swap_id_version,
+        nonce,
+        parent_chain,
+        l1_txid_bytes,
+        required_confirmations,
+        l2_recipient,
+        l2_amount,
+        l1_recipient_address,
+        l1_amount,
+        l1_checkpoint,
+        fill_bond,
+        fill_timeout,
+        expires_at_height,
+        escrow_vout,
+        l2_asset,
+        partial_fills,
+    }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    if *swap_id_version != SWAP_ID_VERSION {
+        return Err(error::Swap::UnsupportedIdVersion {
+            swap_id: SwapId(*swap_id),
+            version: *swap_id_version,
+        }
+        .into());
+    }
+    let l2_amount = bitcoin::Amount::from_sat(*l2_amount);
+    let mut swap = if let Some(l2_recipient) = l2_recipient {
+        if *partial_fills {
+            let swap_id = SwapId(*swap_id);
+            return Err(error::Swap::InvalidPartialFills { swap_id }.into());
+        }
+        if let Some(l1_recipient_address) = l1_recipient_address {
+            // The offerer owns the escrow output
+            let Some(escrow) =
+                filled_tx.transaction.outputs.get(*escrow_vout as usize)
+            else {
+                return Err(error::Swap::InvalidEscrowOutput {
+                    swap_id: SwapId(*swap_id),
+                    vout: *escrow_vout,
+                    asset: *l2_asset,
+                }
+                .into());
+            };
+            let mut swap = Swap::new_l2_to_l1(
+                *parent_chain,
+                l1_recipient_address.clone(),
+                bitcoin::Amount::from_sat(l1_amount.unwrap_or(0)),
+                escrow.address,
+                l2_amount,
+                *l2_recipient,
+                Some(*required_confirmations),
+                height,
+            );
+            swap.l1_txid = l1_txid_from_bytes(l1_txid_bytes);
+            swap.l1_amount = l1_amount.map(bitcoin::Amount::from_sat);
+            swap
+        } else {
+            Swap::new(
+                *parent_chain,
+                l1_txid_from_bytes(l1_txid_bytes),
+                Some(*required_confirmations),
+                *l2_recipient,
+                l2_amount,
+                height,
+            )
+        }
+    } else {
+        // Open offers are filled by paying the L1 recipient
+        let swap_id = SwapId(*swap_id);
+        let Some(l1_recipient_address) = l1_recipient_address else {
+            return Err(error::Swap::MissingL1Recipient { swap_id }.into());
+        };
+        let Some(l1_amount) = l1_amount else {
+            return Err(error::Swap::MissingL1Amount { swap_id }.into());
+        };
+        if *fill_timeout == 0 {
+            return Err(error::Swap::MissingFillTimeout { swap_id }.into());
+        }
+        let swap = Swap::new_open(
+            *parent_chain,
+            l1_recipient_address.clone(),
+            bitcoin::Amount::from_sat(*l1_amount),
+            l2_amount,
+            bitcoin::Amount::from_sat(*fill_bond),
+            *fill_timeout,
+            Some(*required_confirmations),
+            height,
+        );
+        if *partial_fills {
+            swap.with_partial_fills()
+        } else {
+            swap
+        }
+    }
+    .with_l2_asset(*l2_asset)
+    .with_nonce(*nonce);
+    if swap.id.0 != *swap_id {
+        return Err(Error::InvalidTransaction(format!(
+            "Swap ID mismatch: expected {:?}, got {:?}",
+            swap.id.0, swap_id
+        )));
+    }
+    swap.l1_checkpoint = *l1_checkpoint;
+    swap.expires_at_height = *expires_at_height;
+    swap.escrow = escrow_outpoint(filled_tx)?;
+    // The offerer owns the escrow output
+    if swap.direction == SwapDirection::L2ToL1 {
+        swap.l2_offerer = filled_tx
+            .transaction
+            .outputs
+            .get(*escrow_vout as usize)
+            .map(|output| output.address);
+    }
+    Ok(swap)
+}
+
+/// The escrow output of a `SwapCreate` tx for an L2 → L1 swap, which holds
+/// the swap's L2 coins. Returns `None` for other swaps, which do not escrow
+/// L2 coins.
+pub(in crate::state) fn escrow_outpoint(
+    filled_tx: &FilledTransaction,
+) -> Result<Option<OutPoint>, Error> {
+    let Some(TxData::SwapCreate {
+        l1_recipient_address,
+        escrow_vout,
+        ..
+    }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    if l1_recipient_address.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(OutPoint::Regular {
+        txid: filled_tx.txid(),
+        vout: *escrow_vout,
+    }))
+}
+
+// Apply swap creation
+pub(in crate::state) fn apply_create(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<(), Error> {
+    let swap = new_swap(filled_tx, height)?;
+    // Lock the escrow output to the swap. Other outputs, such as change,
+    // remain spendable.
+    if let Some(escrow) = escrow_outpoint(filled_tx)? {
+        state.lock_output_to_swap(rwtxn, &escrow, &swap.id)?;
+    }
+    state.save_swap(rwtxn, &swap)?;
+    Ok(())
+}
+
+// Revert swap creation
+pub(in crate::state) fn revert_create(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+) -> Result<(), Error> {
+    let Some(TxData::SwapCreate { swap_id, .. }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    if let Some(escrow) = escrow_outpoint(filled_tx)?
+        && state.is_output_locked_to_swap(rwtxn, &escrow)?.as_ref()
+            == Some(&swap_id)
+    {
+        state.unlock_output_from_swap(rwtxn, &escrow)?;
+    }
+    state.delete_swap(rwtxn, &swap_id)?;
+    Ok(())
+}
+
+/// Outputs of a claim's L1 transaction that pay the L1 recipient of the
+/// swap, in output order until the L1 amount is covered. Outputs that
+/// already paid for another claim, in state or in `claimed_in_block`, are
+/// skipped, so that each L1 output pays for at most one claim, and the
+/// claim is rejected if the L1 amount is only covered with them.
+/// Whether the transaction pays enough at all is checked with the rest of
+/// the L1 proof, during validation.
+pub(in crate::state) fn l1_payment_vouts(
+    state: &State,
+    rotxn: &RoTxn,
+    swap: &Swap,
+    proof: &L1TxProof,
+    claimed_in_block: &HashSet<ClaimedL1Output>,
+) -> Result<Vec<u32>, Error> {
+    let l1_txid = proof.l1_txid();
+    // Each fill of a partially fillable offer pays its own L1 amount
+    let l1_amount = if swap.partial_fills {
+        swap.claimable_fill(&l1_txid)
+            .and_then(|fill| fill.l1_amount)
+    } else {
+        swap.l1_amount
+    };
+    let (Some(l1_recipient_address), Some(l1_amount)) =
+        (&swap.l1_recipient_address, l1_amount)
+    else {
+        return Ok(Vec::new());
+    };
+    let script_pubkey =
+        address::script_pubkey(&swap.parent_chain, l1_recipient_address)
+            .map_err(|source| error::Swap::InvalidL1Address {
+                swap_id: swap.id.clone(),
+                source,
+            })?;
+    let mut vouts = Vec::new();
+    let mut paid = bitcoin::Amount::ZERO;
+    let mut reused = false;
+    for (vout, output) in proof.tx.output.iter().enumerate() {
+        if paid >= l1_amount {
+            break;
+        }
+        if output.script_pubkey != script_pubkey {
+            continue;
+        }
+        let vout = vout as u32;
+        let key = (swap.parent_chain, l1_txid.clone(), vout);
+        if claimed_in_block.contains(&key)
+            || state.claimed_l1_outputs.try_get(rotxn, &key)?.is_some()
+        {
+            reused = true;
+            continue;
+        }
+        paid = paid.checked_add(output.value).ok_or(AmountOverflowError)?;
+        vouts.push(vout);
+    }
+    if reused && paid < l1_amount {
+        return Err(error::Swap::L1PaymentClaimed {
+            swap_id: swap.id.clone(),
+            l1_txid,
+        }
+        .into());
+    }
+    Ok(vouts)
+}
+
+/// Check that a `SwapFill` at the specified height can fill the swap,
+/// paying `l1_amount` of a partially fillable offer with `l1_txid`
+pub(in crate::state) fn check_fillable(
+    swap: &Swap,
+    height: u32,
+    l1_txid: &TxId,
+    l1_amount: Option<bitcoin::Amount>,
+) -> Result<(), error::Swap> {
+    if swap.is_fillable(height) {
+        // A fill times out before the swap expires, so that an unclaimed
+        // fill's bond is forfeited before the swap can be refunded
+        if let Some(expires_at_height) = swap.expires_at_height
+            && height.saturating_add(swap.fill_timeout) > expires_at_height
+        {
+            return Err(error::Swap::FillPastExpiry {
+                swap_id: swap.id.clone(),
+                height,
+                expires_at_height,
+            });
+        }
+        if !swap.partial_fills {
+            // Other offers are filled for the whole L1 amount
+            if l1_amount
+                .is_none_or(|l1_amount| Some(l1_amount) == swap.l1_amount)
+            {
+                return Ok(());
+            }
+            return Err(error::Swap::InvalidFillAmount {
+                swap_id: swap.id.clone(),
+                l1_amount,
+                fillable: swap.l1_amount_remaining(),
+            });
+        }
+        if swap.fills.iter().any(|fill| fill.l1_txid == *l1_txid) {
+            return Err(error::Swap::DuplicateFill {
+                swap_id: swap.id.clone(),
+                l1_txid: l1_txid.clone(),
+            });
+        }
+        // Each fill must release some of the escrowed L2 asset
+        let fillable = swap.l1_amount_fillable(height);
+        if l1_amount.is_some_and(|l1_amount| {
+            l1_amount <= fillable
+                && swap.fill_l2_amount(l1_amount) > bitcoin::Amount::ZERO
+        }) {
+            return Ok(());
+        }
+        return Err(error::Swap::InvalidFillAmount {
+            swap_id: swap.id.clone(),
+            l1_amount,
+            fillable,
+        });
+    }
+    if let Some(expires_at_height) = swap.expires_at_height
+        && swap.is_expired(height)
+    {
+        return Err(error::Swap::Expired {
+            swap_id: swap.id.clone(),
+            expires_at_height,
+        });
+    }
+    let reserved_until = if swap.partial_fills {
+        swap.fills
+            .iter()
+            .filter(|fill| swap.is_fill_active(fill, height))
+            .map(|fill| fill.height.saturating_add(swap.fill_timeout))
+            .min()
+    } else {
+        swap.fill_expires_at_height()
+    };
+    match reserved_until {
+        Some(expires_at_height)
+            if swap.l2_recipient.is_none() && height < expires_at_height =>
+        {
+            Err(error::Swap::AlreadyFilled {
+                swap_id: swap.id.clone(),
+                expires_at_height,
+            })
+        }
+        _ => Err(error::Swap::NotFillable {
+            swap_id: swap.id.clone(),
+            state: swap.state.clone(),
+        }),
+    }
+}
+
+/// Build the fill that a `SwapFill` tx at the specified height adds to the
+/// swap, checking that the swap can be filled
+pub(in crate::state) fn new_fill(
+    swap: &Swap,
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<SwapFill, Error> {
+    let Some(TxData::SwapFill {
+        swap_id: _,
+        l2_recipient,
+        l1_txid_bytes,
+        l1_amount,
+    }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let l1_txid = l1_txid_from_bytes(l1_txid_bytes);
+    let l1_amount = l1_amount.map(bitcoin::Amount::from_sat);
+    let () = check_fillable(swap, height, &l1_txid, l1_amount)?;
+    let bond =
+        (swap.fill_bond > bitcoin::Amount::ZERO).then(|| OutPoint::Regular {
+            txid: filled_tx.txid(),
+            vout: 0,
+        });
+    Ok(SwapFill {
+        l2_recipient: *l2_recipient,
+        l1_txid,
+        bond,
+        height,
+        l1_amount: swap.partial_fills.then_some(l1_amount).flatten(),
+        l2_amount_claimed: None,
+    })
+}
+
+// Apply swap fill. The fill is checked during validation, against the
+// state before the block and against earlier fills in the same block.
+pub(in crate::state) fn apply_fill(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<(), Error> {
+    let Some(TxData::SwapFill { swap_id, .. }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    let mut swap = state.get_swap(rwtxn, &swap_id)?.ok_or_else(|| {
+        Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+    })?;
+    let fill = new_fill(&swap, filled_tx, height)?;
+    // Lock the filler's bond to the swap
+    if let Some(bond) = &fill.bond {
+        state.lock_output_to_swap(rwtxn, bond, &swap_id)?;
+    }
+    swap.fills.push(fill);
+    state.save_swap(rwtxn, &swap)?;
+    Ok(())
+}
+
+// Revert swap fill
+pub(in crate::state) fn revert_fill(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+) -> Result<(), Error> {
+    let Some(TxData::SwapFill { swap_id, .. }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    let mut swap = state.get_swap(rwtxn, &swap_id)?.ok_or_else(|| {
+        Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+    })?;
+    let Some(fill) = swap.fills.pop() else {
+        return Err(error::Swap::NotFilled { swap_id }.into());
+    };
+    if let Some(bond) = fill.bond
+        && state.is_output_locked_to_swap(rwtxn, &bond)?.as_ref()
+            == Some(&swap_id)
+    {
+        state.unlock_output_from_swap(rwtxn, &bond)?;
+    }
+    // Re-index the swap under the previous filler, if any
+    state.delete_swap(rwtxn, &swap_id)?;
+    state.save_swap(rwtxn, &swap)?;
+    Ok(())
+}
+
+// Apply swap claim. The L1 proof is checked during validation.
+pub(in crate::state) fn apply_claim(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<(), Error> {
+    let Some(TxData::SwapClaim {
+        swap_id,
+        proof_data,
+    }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    let mut swap = state.get_swap(rwtxn, &swap_id)?.ok_or_else(|| {
+        Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+    })?;
+    let rollback = SwapRollback::new(&swap);
+    let () = push_rollback(state, rwtxn, &swap_id, rollback, height)?;
+    // Unlock all inputs that are locked to this swap
+    for input in &filled_tx.transaction.inputs {
+        if state.is_output_locked_to_swap(rwtxn, input)?.as_ref()
+            == Some(&swap_id)
+        {
+            state.unlock_output_from_swap(rwtxn, input)?;
+        }
+    }
+    // Outputs are checked again, as another claim in the same block may
+    // have been paid with them
+    let l1_txid = proof_data.l1_txid();
+    for vout in
+        l1_payment_vouts(state, rwtxn, &swap, proof_data, &HashSet::new())?
+    {
+        let key = (swap.parent_chain, l1_txid.clone(), vout);
+        state.claimed_l1_outputs.put(rwtxn, &key, &swap_id)?;
+    }
+    // Release the claimed part of the swap, and mark the swap as completed
+    // once nothing remains, recording the proven L1 txid
+    if swap.claim(l1_txid).is_err() {
+        return Err(error::Swap::NotClaimable {
+            swap_id,
+            state: swap.state,
+        }
+        .into());
+    }
+    // The rest of a partially filled offer stays in escrow
+    if swap.l2_amount_remaining > bitcoin::Amount::ZERO {
+        let escrow = OutPoint::Regular {
+            txid: filled_tx.txid(),
+            vout: 0,
+        };
+        state.lock_output_to_swap(rwtxn, &escrow, &swap_id)?;
+        swap.escrow = Some(escrow);
+    }
+    state.save_swap(rwtxn, &swap)?;
+    Ok(())
+}
+
+// Apply swap refund. The refund is checked during validation.
+pub(in crate::state) fn apply_refund(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+    height: u32,
+) -> Result<(), Error> {
+    let Some(TxData::SwapRefund { swap_id }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    let mut swap = state.get_swap(rwtxn, &swap_id)?.ok_or_else(|| {
+        Error::InvalidTransaction(format!("Swap not found: {:?}", swap_id))
+    })?;
+    let rollback = SwapRollback::new(&swap);
+    let () = push_rollback(state, rwtxn, &swap_id, rollback, height)?;
+    // Every input of a refund is locked to the swap
+    for input in &filled_tx.transaction.inputs {
+        state.unlock_output_from_swap(rwtxn, input)?;
+    }
+    // Forfeited bonds can be refunded before the swap expires, which does
+    // not cancel the swap
+    if swap.state != SwapState::Completed && swap.is_expired(height) {
+        swap.state = SwapState::Cancelled;
+        state.save_swap(rwtxn, &swap)?;
+    }
+    Ok(())
+}
+
+// Revert swap refund
+pub(in crate::state) fn revert_refund(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+) -> Result<(), Error> {
+    let Some(TxData::SwapRefund { swap_id }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    let rollback = pop_rollback(state, rwtxn, &swap_id)?;
+    let Some(mut swap) = state.get_swap(rwtxn, &swap_id)? else {
+        return Ok(());
+    };
+    rollback.restore(&mut swap);
+    // Re-lock the escrow and bonds that this refund spent
+    for input in &filled_tx.transaction.inputs {
+        if is_escrow_or_bond(&swap, swap.escrow.as_ref(), input) {
+            state.lock_output_to_swap(rwtxn, input, &swap_id)?;
+        }
+    }
+    state.save_swap(rwtxn, &swap)?;
+    Ok(())
+}
+
+// Revert swap claim
+pub(in crate::state) fn revert_claim(
+    state: &State,
+    rwtxn: &mut RwTxn,
+    filled_tx: &FilledTransaction,
+) -> Result<(), Error> {
+    let Some(TxData::SwapClaim {
+        swap_id,
+        proof_data,
+    }) = &filled_tx.transaction.data
+    else {
+        return Err(error::Swap::InvalidTxData.into());
+    };
+    let swap_id = SwapId(*swap_id);
+    // Unlock the rest of a partially filled offer, that this claim
+    // re-escrowed
+    let escrow = OutPoint::Regular {
+        txid: filled_tx.txid(),
+        vout: 0,
+    };
+    if state.is_output_locked_to_swap(rwtxn, &escrow)?.as_ref()
+        == Some(&swap_id)
+    {
+        state.unlock_output_from_swap(rwtxn, &escrow)?;
+    }
+    let rollback = pop_rollback(state, rwtxn, &swap_id)?;
+    if let Some(mut swap) = state.get_swap(rwtxn, &swap_id)? {
+        // Re-lock the escrow and bonds that this claim spent. Other inputs,
+        // such as the claimer's fee inputs, were never locked to the swap.
+        for input in &filled_tx.transaction.inputs {
+            if is_escrow_or_bond(&swap, rollback.escrow.as_ref(), input) {
+                state.lock_output_to_swap(rwtxn, input, &swap_id)?;
+            }
+        }
+        // Release the L1 outputs that paid for this claim
+        let l1_txid = proof_data.l1_txid();
+        for vout in 0..proof_data.tx.output.len() as u32 {
+            let key = (swap.parent_chain, l1_txid.clone(), vout);
+            if state.claimed_l1_outputs.try_get(rwtxn, &key)?.as_ref()
+                == Some(&swap_id)
+            {
+                let _ = state.claimed_l1_outputs.delete(rwtxn, &key)?;
+            }
+        }
+        if swap.revert_claim(&l1_txid).is_err() {
+            return Err(error::Swap::NotClaimable {
+                swap_id,
+                state: swap.state,
+            }
+            .into());
+        }
+        // Restore the state and L1 txid that the claim replaced, and
+        // re-index the swap under that L1 txid
+        rollback.restore(&mut swap);
+        state.delete_swap(rwtxn, &swap_id)?;
+        state.save_swap(rwtxn, &swap)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash as _;
+    use fallible_iterator::FallibleIterator as _;
+
+    use super::*;
+    use crate::{
+        parent_chain::{L1TxProof, ParentChainType, SwapAsset},
+        state::{
+            block,
+            test_utils::{bitcoin_output, connect_block, create_test_state},
+        },
+        types::{
+            Address, BitcoinOutputContent, Body, Header, HtlcOutputContent,
+            OutPointKey, Output, OutputContent, Transaction,
+            WithdrawalOutputContent,
+        },
+    };
+
+    const L1_AMOUNT: u64 = 50_000;
+    const L1_RECIPIENT_ADDRESS: &str =
+        "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+    const L2_AMOUNT: u64 = 100_000;
+    const NONCE: [u8; 32] = [4u8; 32];
+
+    /// L1 checkpoint for BTC swaps, which meets its proof-of-work target
+    fn checkpoint() -> bitcoin::block::Header {
+        bitcoin::constants::genesis_block(bitcoin::Network::Bitcoin).header
+    }
+
+    /// Contents of the swap and locked output databases
+    fn swap_dbs(
+        state: &State,
+        env: &sneed::Env,
+    ) -> (Vec<Swap>, Vec<(OutPointKey, SwapId)>) {
+        let rotxn = env.read_txn().unwrap();
+        let mut swaps = state.load_all_swaps(&rotxn).unwrap();
+        swaps.sort_by_key(|swap| swap.id.0);
+        let locked: Vec<_> = state
+            .locked_swap_outputs
+            .iter(&rotxn)
+            .unwrap()
+            .collect()
+            .unwrap();
+        (swaps, locked)
+    }
+
+    #[test]
+    fn connect_and_connect_prevalidated_agree() {
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let l1_txid_bytes = [3u8; 32];
+        let required_confirmations = 3;
+        let states = [create_test_state(), create_test_state()];
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, 2 * L2_AMOUNT)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        for (i, (state, env, _)) in states.iter().enumerate() {
+            let _: Header = connect_block(state, env, &body, i == 1);
+        }
+
+        // Block 1: Alice offers L2 coins to Bob for L1 coins
+        let swap_id = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            alice,
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bob,
+            Some(required_confirmations),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding],
+            outputs: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(alice, L2_AMOUNT),
+            ],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: l1_txid_bytes.to_vec(),
+                required_confirmations,
+                l2_recipient: Some(bob),
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: 0,
+                fill_timeout: 0,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let escrow = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 0,
+        };
+        let change = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 1,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        for (i, (state, env, _)) in states.iter().enumerate() {
+            let _: Header = connect_block(state, env, &body, i == 1);
+        }
+        let [(state0, env0, _), (state1, env1, _)] = &states;
+        let (swaps, locked) = swap_dbs(state0, env0);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].direction, SwapDirection::L2ToL1);
+        assert_eq!(swaps[0].l2_offerer, Some(alice));
+        // Only the escrow output is locked, and the change stays spendable
+        assert_eq!(
+            locked,
+            vec![(OutPointKey::from_outpoint(&escrow), swap_id.clone())]
+        );
+        {
+            let rotxn = env0.read_txn().unwrap();
+            assert_eq!(
+                state0.is_output_locked_to_swap(&rotxn, &change).unwrap(),
+                None
+            );
+        }
+        assert_eq!((swaps, locked), swap_dbs(state1, env1));
+
+        // Block 2: Bob claims the escrowed coins
+        let proof_data = L1TxProof {
+            tx: bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: Vec::new(),
+                output: Vec::new(),
+            },
+            tx_index: 0,
+            merkle_branch: Vec::new(),
+            headers: Vec::new(),
+            block_index: 0,
+        };
+        let proven_l1_txid = proof_data.l1_txid();
+        let claim_tx = Transaction {
+            inputs: vec![escrow],
+            outputs: vec![bitcoin_output(bob, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapClaim {
+                swap_id: swap_id.0,
+                proof_data,
+            }),
+        };
+        // Bob's key authorizes the claim of Alice's escrow. Alice's key is
+        // still needed for her other outputs, and for other spends.
+        {
+            let rotxn = env0.read_txn().unwrap();
+            let authorizers = |tx: &Transaction| {
+                let filled_tx = state0.fill_transaction(&rotxn, tx).unwrap();
+                state0.get_authorizers(&rotxn, &filled_tx).unwrap()
+            };
+            assert_eq!(authorizers(&claim_tx), vec![bob]);
+            let mut claim_change_tx = claim_tx.clone();
+            claim_change_tx.inputs.push(change);
+            assert_eq!(authorizers(&claim_change_tx), vec![bob, alice]);
+            let mut transfer_tx = claim_tx.clone();
+            transfer_tx.data = None;
+            assert_eq!(authorizers(&transfer_tx), vec![alice]);
+        }
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![claim_tx],
+            authorizations: Vec::new(),
+        };
+        let unclaimed = swap_dbs(state0, env0);
+        let headers: Vec<Header> = states
+            .iter()
+            .enumerate()
+            .map(|(i, (state, env, _))| {
+                connect_block(state, env, &body, i == 1)
+            })
+            .collect();
+        let (swaps, locked) = swap_dbs(state0, env0);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].state, SwapState::Completed);
+        assert_eq!(swaps[0].l1_txid, proven_l1_txid);
+        assert!(locked.is_empty());
+        assert_eq!((swaps, locked), swap_dbs(state1, env1));
+
+        // Disconnecting the claim restores the swap exactly, including its
+        // state and L1 txid
+        {
+            let mut rwtxn = env0.write_txn().unwrap();
+            block::disconnect_tip(state0, &mut rwtxn, &headers[0], &body)
+                .unwrap();
+            rwtxn.commit().unwrap();
+        }
+        assert_eq!(swap_dbs(state0, env0), unclaimed);
+        let rotxn = env0.read_txn().unwrap();
+        assert_eq!(
+            state0
+                .get_swap_by_l1_txid(
+                    &rotxn,
+                    &ParentChainType::Btc,
+                    &proven_l1_txid
+                )
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn open_offer_fill_and_revert() {
+        const FILL_BOND: u64 = 1_000;
+        const FILL_TIMEOUT: u32 = 10;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let carol = Address([3u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice, Bob, and Carol
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(bob, FILL_BOND),
+                bitcoin_output(carol, FILL_BOND),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let funding = |vout| OutPoint::Coinbase { merkle_root, vout };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice posts an open offer
+        let swap_id = Swap::new_open(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bitcoin::Amount::from_sat(FILL_BOND),
+            FILL_TIMEOUT,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding(0)],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: None,
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: FILL_BOND,
+                fill_timeout: FILL_TIMEOUT,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        let fill_tx = |filler, vout, l1_txid: [u8; 32]| Transaction {
+            inputs: vec![funding(vout)],
+            outputs: vec![bitcoin_output(filler, FILL_BOND)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapFill {
+                swap_id: swap_id.0,
+                l2_recipient: filler,
+                l1_txid_bytes: l1_txid.to_vec(),
+                l1_amount: None,
+            }),
+        };
+
+        // Bob and Carol can not both fill the offer in one block, although
+        // each fill is valid against the state before the block
+        {
+            let body = Body {
+                coinbase: Vec::new(),
+                transactions: vec![
+                    fill_tx(bob, 1, [4u8; 32]),
+                    fill_tx(carol, 2, [5u8; 32]),
+                ],
+                authorizations: Vec::new(),
+            };
+            let rotxn = env.read_txn().unwrap();
+            let header = Header {
+                merkle_root: body.compute_merkle_root(),
+                prev_side_hash: state.try_get_tip(&rotxn).unwrap(),
+                prev_main_hash: bitcoin::BlockHash::all_zeros(),
+            };
+            assert!(matches!(
+                block::validate(&state, &rotxn, &header, &body),
+                Err(Error::Swap(error::Swap::AlreadyFilled { .. }))
+            ));
+            assert!(matches!(
+                block::prevalidate(&state, &rotxn, &header, &body),
+                Err(Error::Swap(error::Swap::AlreadyFilled { .. }))
+            ));
+        }
+
+        // Block 2: Bob fills the offer, locking his bond
+        let bob_fill_tx = fill_tx(bob, 1, [4u8; 32]);
+        let bond = OutPoint::Regular {
+            txid: bob_fill_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![bob_fill_tx],
+            authorizations: Vec::new(),
+        };
+        let header = connect_block(&state, &env, &body, false);
+        {
+            let rotxn = env.read_txn().unwrap();
+            let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+            assert_eq!(swap.recipient(), Some(bob));
+            assert_eq!(swap.expected_l1_txid(), &TxId::Hash32([4u8; 32]));
+            assert_eq!(swap.fill_expires_at_height(), Some(2 + FILL_TIMEOUT));
+            assert_eq!(
+                state.is_output_locked_to_swap(&rotxn, &bond).unwrap(),
+                Some(swap_id.clone())
+            );
+            assert_eq!(
+                state.get_swaps_by_recipient(&rotxn, &bob).unwrap(),
+                vec![swap.clone()]
+            );
+            // Carol cannot fill the offer while Bob's fill reserves it
+            let carol_fill_tx = state
+                .fill_transaction(&rotxn, &fill_tx(carol, 2, [5u8; 32]))
+                .unwrap();
+            assert!(matches!(
+                state.validate_filled_transaction(&rotxn, &carol_fill_tx),
+                Err(Error::Swap(error::Swap::AlreadyFilled { .. }))
+            ));
+        }
+
+        // Disconnecting the block reverts the fill and releases the bond
+        {
+            let mut rwtxn = env.write_txn().unwrap();
+            block::disconnect_tip(&state, &mut rwtxn, &header, &body).unwrap();
+            rwtxn.commit().unwrap();
+        }
+        let rotxn = env.read_txn().unwrap();
+        let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+        assert!(swap.fills.is_empty());
+        assert_eq!(swap.recipient(), None);
+        assert!(swap.is_fillable(2));
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &bond).unwrap(),
+            None
+        );
+        assert!(
+            state
+                .get_swaps_by_recipient(&rotxn, &bob)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn timed_out_fill_forfeits_bond() {
+        const EXPIRES_AT_HEIGHT: u32 = 5;
+        const FILL_BOND: u64 = 1_000;
+        const FILL_TIMEOUT: u32 = 2;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice and Bob
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(bob, FILL_BOND),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let funding = |vout| OutPoint::Coinbase { merkle_root, vout };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice posts an open offer, until height 5
+        let swap_id = Swap::new_open(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bitcoin::Amount::from_sat(FILL_BOND),
+            FILL_TIMEOUT,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding(0)],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: None,
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: FILL_BOND,
+                fill_timeout: FILL_TIMEOUT,
+                expires_at_height: Some(EXPIRES_AT_HEIGHT),
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 2: Bob fills the offer, locking his bond until height 4
+        let fill_tx = Transaction {
+            inputs: vec![funding(1)],
+            outputs: vec![bitcoin_output(bob, FILL_BOND)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapFill {
+                swap_id: swap_id.0,
+                l2_recipient: bob,
+                l1_txid_bytes: vec![4u8; 32],
+                l1_amount: None,
+            }),
+        };
+        let bond = OutPoint::Regular {
+            txid: fill_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![fill_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        let refund_tx = Transaction {
+            inputs: vec![bond],
+            outputs: vec![bitcoin_output(alice, FILL_BOND)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapRefund { swap_id: swap_id.0 }),
+        };
+
+        // The bond cannot be refunded while Bob's fill reserves the offer,
+        // and Bob signs for it
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_tx = state.fill_transaction(&rotxn, &refund_tx).unwrap();
+            assert_eq!(
+                state.get_authorizers(&rotxn, &filled_tx).unwrap(),
+                vec![bob]
+            );
+            assert!(matches!(
+                state.validate_filled_transaction(&rotxn, &filled_tx),
+                Err(Error::Swap(error::Swap::NotExpired { .. }))
+            ));
+        }
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Bob's fill times out at height 4 without a claim. His bond is
+        // forfeited to Alice, and the offer cannot be filled again, since a
+        // new fill would reserve it past its expiry.
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_tx = state.fill_transaction(&rotxn, &refund_tx).unwrap();
+            assert_eq!(
+                state.get_authorizers(&rotxn, &filled_tx).unwrap(),
+                vec![alice]
+            );
+            state
+                .validate_filled_transaction(&rotxn, &filled_tx)
+                .unwrap();
+            let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+            assert!(matches!(
+                check_fillable(&swap, 4, &TxId::Hash32([5u8; 32]), None),
+                Err(error::Swap::FillPastExpiry { .. })
+            ));
+        }
+
+        // Block 4: Alice takes the bond, which does not cancel the swap
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![refund_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        let rotxn = env.read_txn().unwrap();
+        let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+        assert_eq!(swap.state, SwapState::Pending);
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &bond).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn fill_bond_must_be_bitcoin() {
+        const FILL_BOND: u64 = 1_000;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice and Bob
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(bob, FILL_BOND),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let funding = |vout| OutPoint::Coinbase { merkle_root, vout };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice posts an open offer that requires a fill bond
+        let swap_id = Swap::new_open(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bitcoin::Amount::from_sat(FILL_BOND),
+            2,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding(0)],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: None,
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: Some(checkpoint()),
+                fill_bond: FILL_BOND,
+                fill_timeout: 2,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Bob fills the offer, with the bond in his first output
+        let fill_tx = |bond: OutputContent| Transaction {
+            inputs: vec![funding(1)],
+            outputs: vec![Output::new(bob, bond)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapFill {
+                swap_id: swap_id.0,
+                l2_recipient: bob,
+                l1_txid_bytes: vec![4u8; 32],
+                l1_amount: None,
+            }),
+        };
+        let rotxn = env.read_txn().unwrap();
+        let validate = |bond| {
+            let filled_tx = state.fill_transaction(&rotxn, &fill_tx(bond))?;
+            state.validate_filled_transaction(&rotxn, &filled_tx)
+        };
+        let value = bitcoin::Amount::from_sat(FILL_BOND);
+        let withdrawal = OutputContent::Withdrawal(WithdrawalOutputContent {
+            value,
+            main_fee: bitcoin::Amount::ZERO,
+            main_address: L1_RECIPIENT_ADDRESS.parse().unwrap(),
+        });
+        let htlc = OutputContent::Htlc(HtlcOutputContent {
+            value,
+            hash_lock: [7u8; 32],
+            recipient: bob,
+            timeout_height: 100,
+        });
+        assert!(matches!(
+            validate(withdrawal),
+            Err(Error::Swap(error::Swap::InsufficientFillBond { .. }))
+        ));
+        assert!(matches!(
+            validate(htlc),
+            Err(Error::Swap(error::Swap::InsufficientFillBond { .. }))
+        ));
+        assert_eq!(
+            validate(OutputContent::Bitcoin(BitcoinOutputContent(value)))
+                .unwrap(),
+            bitcoin::Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn claim_must_spend_escrow() {
+        const FILL_BOND: u64 = 1_000;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice and Bob
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(bob, FILL_BOND),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let funding = |vout| OutPoint::Coinbase { merkle_root, vout };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice posts an open offer that requires a fill bond
+        let swap_id = Swap::new_open(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bitcoin::Amount::from_sat(FILL_BOND),
+            2,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding(0)],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: None,
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: Some(checkpoint()),
+                fill_bond: FILL_BOND,
+                fill_timeout: 2,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let escrow = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 2: Bob fills the offer, locking his bond
+        let fill_tx = Transaction {
+            inputs: vec![funding(1)],
+            outputs: vec![bitcoin_output(bob, FILL_BOND)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapFill {
+                swap_id: swap_id.0,
+                l2_recipient: bob,
+                l1_txid_bytes: vec![4u8; 32],
+                l1_amount: None,
+            }),
+        };
+        let bond = OutPoint::Regular {
+            txid: fill_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![fill_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // A claim that spends only Bob's bond would leave the escrow locked
+        // without the swap pointing to it
+        let proof_data = L1TxProof {
+            tx: bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: Vec::new(),
+                output: Vec::new(),
+            },
+            tx_index: 0,
+            merkle_branch: Vec::new(),
+            headers: Vec::new(),
+            block_index: 0,
+        };
+        let claim_tx = Transaction {
+            inputs: vec![bond],
+            outputs: vec![bitcoin_output(bob, FILL_BOND)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapClaim {
+                swap_id: swap_id.0,
+                proof_data,
+            }),
+        };
+        let rotxn = env.read_txn().unwrap();
+        let filled_tx = state.fill_transaction(&rotxn, &claim_tx).unwrap();
+        assert!(matches!(
+            state.validate_filled_transaction(&rotxn, &filled_tx),
+            Err(Error::Swap(error::Swap::EscrowNotSpent {
+                escrow: not_spent,
+                ..
+            })) if not_spent == escrow
+        ));
+    }
+
+    #[test]
+    fn partial_fill_claim_re_escrows_the_rest() {
+        const FEE: u64 = 1_000;
+        const FILL_TIMEOUT: u32 = 10;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let carol = Address([3u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice, and Bob's claim fee
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(bob, FEE),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let funding = OutPoint::Coinbase {
+            merkle_root,
+            vout: 0,
+        };
+        let fee_input = OutPoint::Coinbase {
+            merkle_root,
+            vout: 1,
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice posts a partially fillable offer
+        let swap_id = Swap::new_open(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bitcoin::Amount::ZERO,
+            FILL_TIMEOUT,
+            Some(3),
+            1,
+        )
+        .with_partial_fills()
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: None,
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: 0,
+                fill_timeout: FILL_TIMEOUT,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: true,
+            }),
+        };
+        let escrow = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 2: Bob fills two fifths of the offer, and Carol three fifths
+        let proof = |lock_time| L1TxProof {
+            tx: bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::from_consensus(
+                    lock_time,
+                ),
+                input: Vec::new(),
+                output: Vec::new(),
+            },
+            tx_index: 0,
+            merkle_branch: Vec::new(),
+            headers: Vec::new(),
+            block_index: 0,
+        };
+        let l1_txid_bytes = |proof: &L1TxProof| match proof.l1_txid() {
+            TxId::Hash32(hash) => hash.to_vec(),
+            TxId::Hash(hash) => hash,
+        };
+        let fill_tx = |filler, proof: &L1TxProof, l1_amount| Transaction {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            memo: Vec::new(),
+            data: Some(TxData::SwapFill {
+                swap_id: swap_id.0,
+                l2_recipient: filler,
+                l1_txid_bytes: l1_txid_bytes(proof),
+                l1_amount: Some(l1_amount),
+            }),
+        };
+        let (bob_proof, carol_proof) = (proof(1), proof(2));
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![
+                fill_tx(bob, &bob_proof, 2 * L1_AMOUNT / 5),
+                fill_tx(carol, &carol_proof, 3 * L1_AMOUNT / 5),
+            ],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        {
+            let rotxn = env.read_txn().unwrap();
+            let validate = |tx: &Transaction| {
+                let filled_tx = state.fill_transaction(&rotxn, tx)?;
+                state.validate_filled_transaction(&rotxn, &filled_tx)
+            };
+            // The offer is fully reserved, and each L1 tx fills it once
+            assert!(matches!(
+                validate(&fill_tx(carol, &proof(3), 1)),
+                Err(Error::Swap(error::Swap::AlreadyFilled { .. }))
+            ));
+            let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+            assert!(matches!(
+                check_fillable(
+                    &swap,
+                    2 + FILL_TIMEOUT,
+                    &bob_proof.l1_txid(),
+                    Some(bitcoin::Amount::from_sat(L1_AMOUNT)),
+                ),
+                Err(error::Swap::DuplicateFill { .. })
+            ));
+        }
+
+        // Block 3: Bob claims his part, and re-escrows the rest for Alice
+        let claim_tx = Transaction {
+            inputs: vec![escrow, fee_input],
+            outputs: vec![
+                bitcoin_output(alice, 3 * L2_AMOUNT / 5),
+                bitcoin_output(bob, 2 * L2_AMOUNT / 5),
+            ],
+            memo: Vec::new(),
+            data: Some(TxData::SwapClaim {
+                swap_id: swap_id.0,
+                proof_data: bob_proof,
+            }),
+        };
+        let rest = OutPoint::Regular {
+            txid: claim_tx.txid(),
+            vout: 0,
+        };
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_tx = state.fill_transaction(&rotxn, &claim_tx).unwrap();
+            assert_eq!(
+                state.get_authorizers(&rotxn, &filled_tx).unwrap(),
+                vec![bob, bob]
+            );
+        }
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![claim_tx],
+            authorizations: Vec::new(),
+        };
+        let header = connect_block(&state, &env, &body, false);
+        {
+            let rotxn = env.read_txn().unwrap();
+            let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+            assert_eq!(swap.state, SwapState::Pending);
+            assert_eq!(swap.l1_amount_filled.to_sat(), 2 * L1_AMOUNT / 5);
+            assert_eq!(swap.l2_amount_remaining.to_sat(), 3 * L2_AMOUNT / 5);
+            assert_eq!(swap.claimant(&carol_proof.l1_txid()), Some(carol));
+            assert_eq!(swap.escrow, Some(rest));
+            assert_eq!(
+                state.is_output_locked_to_swap(&rotxn, &escrow).unwrap(),
+                None
+            );
+            assert_eq!(
+                state.is_output_locked_to_swap(&rotxn, &rest).unwrap(),
+                Some(swap_id.clone())
+            );
+        }
+
+        // Disconnecting the block restores the original escrow, and leaves
+        // Bob's fee input unlocked
+        {
+            let mut rwtxn = env.write_txn().unwrap();
+            block::disconnect_tip(&state, &mut rwtxn, &header, &body).unwrap();
+            rwtxn.commit().unwrap();
+        }
+        let rotxn = env.read_txn().unwrap();
+        let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+        assert_eq!(swap.l1_amount_filled, bitcoin::Amount::ZERO);
+        assert_eq!(swap.l2_amount_remaining.to_sat(), L2_AMOUNT);
+        assert_eq!(swap.escrow, Some(escrow));
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &escrow).unwrap(),
+            Some(swap_id.clone())
+        );
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &rest).unwrap(),
+            None
+        );
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &fee_input).unwrap(),
+            None
+        );
+        assert!(
+            state
+                .swap_rollbacks
+                .try_get(&rotxn, &swap_id)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn refund_after_expiry() {
+        const EXPIRES_AT_HEIGHT: u32 = 3;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, L2_AMOUNT)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice offers L2 coins to Bob, until height 3
+        let swap_id = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            alice,
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bob,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: Some(bob),
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: 0,
+                fill_timeout: 0,
+                expires_at_height: Some(EXPIRES_AT_HEIGHT),
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let escrow = OutPoint::Regular {
+            txid: create_tx.txid(),
+            vout: 0,
+        };
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        let refund_tx = Transaction {
+            inputs: vec![escrow],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapRefund { swap_id: swap_id.0 }),
+        };
+
+        // The swap cannot be refunded at height 2
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_refund_tx =
+                state.fill_transaction(&rotxn, &refund_tx).unwrap();
+            assert!(matches!(
+                state.validate_filled_transaction(&rotxn, &filled_refund_tx),
+                Err(Error::Swap(error::Swap::NotExpired { .. }))
+            ));
+        }
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 3: Alice refunds the expired swap
+        {
+            let rotxn = env.read_txn().unwrap();
+            let filled_refund_tx =
+                state.fill_transaction(&rotxn, &refund_tx).unwrap();
+            state
+                .validate_filled_transaction(&rotxn, &filled_refund_tx)
+                .unwrap();
+        }
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![refund_tx],
+            authorizations: Vec::new(),
+        };
+        let header = connect_block(&state, &env, &body, false);
+        {
+            let rotxn = env.read_txn().unwrap();
+            let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+            assert_eq!(swap.state, SwapState::Cancelled);
+            assert_eq!(
+                state.is_output_locked_to_swap(&rotxn, &escrow).unwrap(),
+                None
+            );
+        }
+
+        // Disconnecting the block locks the escrow output again
+        {
+            let mut rwtxn = env.write_txn().unwrap();
+            block::disconnect_tip(&state, &mut rwtxn, &header, &body).unwrap();
+            rwtxn.commit().unwrap();
+        }
+        let rotxn = env.read_txn().unwrap();
+        let swap = state.get_swap(&rotxn, &swap_id).unwrap().unwrap();
+        assert_eq!(swap.state, SwapState::Pending);
+        assert_eq!(
+            state.is_output_locked_to_swap(&rotxn, &escrow).unwrap(),
+            Some(swap_id)
+        );
+    }
+
+    #[test]
+    fn escrow_must_hold_l2_amount() {
+        const CHANGE: u64 = L2_AMOUNT / 2;
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, L2_AMOUNT + CHANGE)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Alice offers L2 coins to Bob, with change before the escrow output
+        let swap_id = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            alice,
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bob,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = |escrow_vout| Transaction {
+            inputs: vec![funding],
+            outputs: vec![
+                bitcoin_output(alice, CHANGE),
+                bitcoin_output(alice, L2_AMOUNT),
+            ],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: Some(bob),
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: Some(checkpoint()),
+                fill_bond: 0,
+                fill_timeout: 0,
+                expires_at_height: None,
+                escrow_vout,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let rotxn = env.read_txn().unwrap();
+        let validate = |escrow_vout| {
+            let filled_tx =
+                state.fill_transaction(&rotxn, &create_tx(escrow_vout))?;
+            state.validate_filled_transaction(&rotxn, &filled_tx)
+        };
+        assert!(matches!(
+            validate(0),
+            Err(Error::Swap(error::Swap::EscrowAmountMismatch { .. }))
+        ));
+        assert!(matches!(
+            validate(2),
+            Err(Error::Swap(error::Swap::InvalidEscrowOutput { .. }))
+        ));
+        assert_eq!(validate(1).unwrap(), bitcoin::Amount::ZERO);
+    }
+
+    #[test]
+    fn htlc_cannot_be_escrowed() {
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, L2_AMOUNT)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Alice offers L2 coins to Bob, escrowed in an HTLC output that holds
+        // the L2 amount of Bitcoin
+        let swap_id = Swap::new_l2_to_l1(
+            ParentChainType::Btc,
+            L1_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            alice,
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bob,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let htlc = HtlcOutputContent {
+            value: bitcoin::Amount::from_sat(L2_AMOUNT),
+            hash_lock: [7u8; 32],
+            recipient: alice,
+            timeout_height: 100,
+        };
+        let create_tx = Transaction {
+            inputs: vec![funding],
+            outputs: vec![Output::new(alice, OutputContent::Htlc(htlc))],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Btc,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: Some(bob),
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: Some(checkpoint()),
+                fill_bond: 0,
+                fill_timeout: 0,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let rotxn = env.read_txn().unwrap();
+        let filled_tx = state.fill_transaction(&rotxn, &create_tx).unwrap();
+        assert!(matches!(
+            state.validate_filled_transaction(&rotxn, &filled_tx),
+            Err(Error::Swap(error::Swap::InvalidEscrowOutput { .. }))
+        ));
+    }
+
+    #[test]
+    fn unprovable_parent_chains_are_rejected() {
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![bitcoin_output(alice, L2_AMOUNT)],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let funding = OutPoint::Coinbase {
+            merkle_root: body.compute_merkle_root(),
+            vout: 0,
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Alice offers L2 coins to Bob for ETH
+        const ETH_RECIPIENT_ADDRESS: &str =
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        let swap_id = Swap::new_l2_to_l1(
+            ParentChainType::Eth,
+            ETH_RECIPIENT_ADDRESS.to_owned(),
+            bitcoin::Amount::from_sat(L1_AMOUNT),
+            alice,
+            bitcoin::Amount::from_sat(L2_AMOUNT),
+            bob,
+            Some(3),
+            1,
+        )
+        .with_nonce(NONCE)
+        .id;
+        let create_tx = Transaction {
+            inputs: vec![funding],
+            outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapCreate {
+                swap_id: swap_id.0,
+                swap_id_version: SWAP_ID_VERSION,
+                nonce: NONCE,
+                parent_chain: ParentChainType::Eth,
+                l1_txid_bytes: vec![0u8; 32],
+                required_confirmations: 3,
+                l2_recipient: Some(bob),
+                l2_amount: L2_AMOUNT,
+                l1_recipient_address: Some(ETH_RECIPIENT_ADDRESS.to_owned()),
+                l1_amount: Some(L1_AMOUNT),
+                l1_checkpoint: None,
+                fill_bond: 0,
+                fill_timeout: 0,
+                expires_at_height: None,
+                escrow_vout: 0,
+                l2_asset: SwapAsset::BITCOIN,
+                partial_fills: false,
+            }),
+        };
+        let rotxn = env.read_txn().unwrap();
+        let filled_tx = state.fill_transaction(&rotxn, &create_tx).unwrap();
+        assert_eq!(new_swap(&filled_tx, 1).unwrap().id, swap_id);
+        // L1 payments on Ethereum cannot be proven, so the swap could never
+        // be claimed
+        assert!(matches!(
+            state.validate_filled_transaction(&rotxn, &filled_tx),
+            Err(Error::Swap(error::Swap::UnsupportedParentChain { .. }))
+        ));
+    }
+
+    #[test]
+    fn l1_output_pays_for_one_claim() {
+        let alice = Address([1u8; 20]);
+        let bob = Address([2u8; 20]);
+        let carol = Address([3u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(alice, L2_AMOUNT),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Block 1: Alice offers L2 coins to Bob and to Carol, for the same
+        // L1 amount paid to the same L1 address
+        let create_tx = |vout, l2_recipient| {
+            let swap_id = Swap::new_l2_to_l1(
+                ParentChainType::Btc,
+                L1_RECIPIENT_ADDRESS.to_owned(),
+                bitcoin::Amount::from_sat(L1_AMOUNT),
+                alice,
+                bitcoin::Amount::from_sat(L2_AMOUNT),
+                l2_recipient,
+                Some(3),
+                1,
+            )
+            .with_nonce(NONCE)
+            .id;
+            let tx = Transaction {
+                inputs: vec![OutPoint::Coinbase { merkle_root, vout }],
+                outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+                memo: Vec::new(),
+                data: Some(TxData::SwapCreate {
+                    swap_id: swap_id.0,
+                    swap_id_version: SWAP_ID_VERSION,
+                    nonce: NONCE,
+                    parent_chain: ParentChainType::Btc,
+                    l1_txid_bytes: vec![0u8; 32],
+                    required_confirmations: 3,
+                    l2_recipient: Some(l2_recipient),
+                    l2_amount: L2_AMOUNT,
+                    l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                    l1_amount: Some(L1_AMOUNT),
+                    l1_checkpoint: None,
+                    fill_bond: 0,
+                    fill_timeout: 0,
+                    expires_at_height: None,
+                    escrow_vout: 0,
+                    l2_asset: SwapAsset::BITCOIN,
+                    partial_fills: false,
+                }),
+            };
+            let escrow = OutPoint::Regular {
+                txid: tx.txid(),
+                vout: 0,
+            };
+            (swap_id, tx, escrow)
+        };
+        let (bob_swap_id, bob_create_tx, bob_escrow) = create_tx(0, bob);
+        let (carol_swap_id, carol_create_tx, carol_escrow) =
+            create_tx(1, carol);
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![bob_create_tx, carol_create_tx],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // A single L1 output pays for both offers
+        let proof = L1TxProof {
+            tx: bitcoin::Transaction {
+                version: bitcoin::transaction::Version::TWO,
+                lock_time: bitcoin::absolute::LockTime::ZERO,
+                input: Vec::new(),
+                output: vec![bitcoin::TxOut {
+                    value: bitcoin::Amount::from_sat(L1_AMOUNT),
+                    script_pubkey: address::script_pubkey(
+                        &ParentChainType::Btc,
+                        L1_RECIPIENT_ADDRESS,
+                    )
+                    .unwrap(),
+                }],
+            },
+            tx_index: 0,
+            merkle_branch: Vec::new(),
+            headers: Vec::new(),
+            block_index: 0,
+        };
+        let claim_tx = |swap_id: &SwapId, escrow, recipient| Transaction {
+            inputs: vec![escrow],
+            outputs: vec![bitcoin_output(recipient, L2_AMOUNT)],
+            memo: Vec::new(),
+            data: Some(TxData::SwapClaim {
+                swap_id: swap_id.0,
+                proof_data: proof.clone(),
+            }),
+        };
+        let payment_vouts = |swap_id: &SwapId| {
+            let rotxn = env.read_txn().unwrap();
+            let swap = state.get_swap(&rotxn, swap_id).unwrap().unwrap();
+            l1_payment_vouts(&state, &rotxn, &swap, &proof, &HashSet::new())
+        };
+        assert_eq!(payment_vouts(&bob_swap_id).unwrap(), vec![0]);
+        assert_eq!(payment_vouts(&carol_swap_id).unwrap(), vec![0]);
+
+        // Payments that do not fit in an amount are rejected
+        {
+            let mut proof = proof.clone();
+            let output = proof.tx.output[0].clone();
+            proof.tx.output = vec![
+                bitcoin::TxOut {
+                    value: bitcoin::Amount::from_sat(1),
+                    ..output.clone()
+                },
+                bitcoin::TxOut {
+                    value: bitcoin::Amount::MAX,
+                    ..output
+                },
+            ];
+            let rotxn = env.read_txn().unwrap();
+            let swap = state.get_swap(&rotxn, &bob_swap_id).unwrap().unwrap();
+            assert!(matches!(
+                l1_payment_vouts(
+                    &state,
+                    &rotxn,
+                    &swap,
+                    &proof,
+                    &HashSet::new()
+                ),
+                Err(Error::AmountOverflow(_))
+            ));
+        }
+
+        // Claims are not accepted for swaps without an L1 checkpoint
+        {
+            let rotxn = env.read_txn().unwrap();
+            let tx = claim_tx(&bob_swap_id, bob_escrow, bob);
+            let filled_tx = state.fill_transaction(&rotxn, &tx).unwrap();
+            assert!(matches!(
+                state.validate_filled_transaction(&rotxn, &filled_tx),
+                Err(Error::Swap(error::Swap::MissingL1Checkpoint { .. }))
+            ));
+        }
+
+        // Both claims can not be connected in the same block
+        {
+            let body = Body {
+                coinbase: Vec::new(),
+                transactions: vec![
+                    claim_tx(&bob_swap_id, bob_escrow, bob),
+                    claim_tx(&carol_swap_id, carol_escrow, carol),
+                ],
+                authorizations: Vec::new(),
+            };
+            let mut rwtxn = env.write_txn().unwrap();
+            let header = Header {
+                merkle_root: body.compute_merkle_root(),
+                prev_side_hash: state.try_get_tip(&rwtxn).unwrap(),
+                prev_main_hash: bitcoin::BlockHash::all_zeros(),
+            };
+            assert!(matches!(
+                block::connect(&state, &mut rwtxn, &header, &body),
+                Err(Error::Swap(error::Swap::L1PaymentClaimed { .. }))
+            ));
+        }
+        // ...and the second claim is rejected when validating the block
+        {
+            let rotxn = env.read_txn().unwrap();
+            let mut block_txs = block::BlockTxs::default();
+            let mut validate_claim = |swap_id, escrow, recipient| {
+                let tx = claim_tx(swap_id, escrow, recipient);
+                let filled_tx = state.fill_transaction(&rotxn, &tx).unwrap();
+                block_txs.validate_tx(&state, &rotxn, &filled_tx, 2)
+            };
+            assert!(validate_claim(&bob_swap_id, bob_escrow, bob).is_ok());
+            assert!(matches!(
+                validate_claim(&carol_swap_id, carol_escrow, carol),
+                Err(Error::Swap(error::Swap::L1PaymentClaimed { .. }))
+            ));
+        }
+
+        // Block 2: Bob claims with the L1 output, which Carol can then no
+        // longer claim with
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![claim_tx(&bob_swap_id, bob_escrow, bob)],
+            authorizations: Vec::new(),
+        };
+        let header = connect_block(&state, &env, &body, false);
+        assert!(matches!(
+            payment_vouts(&carol_swap_id),
+            Err(Error::Swap(error::Swap::L1PaymentClaimed { .. }))
+        ));
+
+        // Disconnecting the block releases the L1 output
+        {
+            let mut rwtxn = env.write_txn().unwrap();
+            block::disconnect_tip(&state, &mut rwtxn, &header, &body).unwrap();
+            rwtxn.commit().unwrap();
+        }
+        assert_eq!(payment_vouts(&carol_swap_id).unwrap(), vec![0]);
+        let rotxn = env.read_txn().unwrap();
+        let claimed: Vec<_> = state
+            .claimed_l1_outputs
+            .iter(&rotxn)
+            .unwrap()
+            .collect()
+            .unwrap();
+        assert!(claimed.is_empty());
+    }
+
+    #[test]
+    fn repeated_offers_have_distinct_ids() {
+        const FILL_TIMEOUT: u32 = 10;
+        let alice = Address([1u8; 20]);
+        let (state, env, _temp_dir) = create_test_state();
+
+        // Block 0: fund Alice
+        let body = Body {
+            coinbase: vec![
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(alice, L2_AMOUNT),
+                bitcoin_output(alice, L2_AMOUNT),
+            ],
+            transactions: Vec::new(),
+            authorizations: Vec::new(),
+        };
+        let merkle_root = body.compute_merkle_root();
+        let _: Header = connect_block(&state, &env, &body, false);
+
+        // Alice posts the same open offer several times, with distinct
+        // nonces
+        let create_tx = |vout, nonce, swap_id_version| {
+            let swap_id = Swap::new_open(
+                ParentChainType::Btc,
+                L1_RECIPIENT_ADDRESS.to_owned(),
+                bitcoin::Amount::from_sat(L1_AMOUNT),
+                bitcoin::Amount::from_sat(L2_AMOUNT),
+                bitcoin::Amount::ZERO,
+                FILL_TIMEOUT,
+                Some(3),
+                1,
+            )
+            .with_nonce(nonce)
+            .id;
+            Transaction {
+                inputs: vec![OutPoint::Coinbase { merkle_root, vout }],
+                outputs: vec![bitcoin_output(alice, L2_AMOUNT)],
+                memo: Vec::new(),
+                data: Some(TxData::SwapCreate {
+                    swap_id: swap_id.0,
+                    swap_id_version,
+                    nonce,
+                    parent_chain: ParentChainType::Btc,
+                    l1_txid_bytes: vec![0u8; 32],
+                    required_confirmations: 3,
+                    l2_recipient: None,
+                    l2_amount: L2_AMOUNT,
+                    l1_recipient_address: Some(L1_RECIPIENT_ADDRESS.to_owned()),
+                    l1_amount: Some(L1_AMOUNT),
+                    l1_checkpoint: Some(checkpoint()),
+                    fill_bond: 0,
+                    fill_timeout: FILL_TIMEOUT,
+                    expires_at_height: None,
+                    escrow_vout: 0,
+                    l2_asset: SwapAsset::BITCOIN,
+                    partial_fills: false,
+                }),
+            }
+        };
+
+        // Block 1: both offers are created
+        let body = Body {
+            coinbase: Vec::new(),
+            transactions: vec![
+                create_tx(0, [1u8; 32], SWAP_ID_VERSION),
+                create_tx(1, [2u8; 32], SWAP_ID_VERSION),
+            ],
+            authorizations: Vec::new(),
+        };
+        let _: Header = connect_block(&state, &env, &body, false);
+        let (swaps, locked) = swap_dbs(&state, &env);
+        assert_eq!(swaps.len(), 2);
+        assert_ne!(swaps[0].id, swaps[1].id);
+        assert_eq!(locked.len(), 2);
+
+        // Reusing a nonce gives the offer an existing swap ID, and swap IDs
+        // must be derived with a supported version
+        let rotxn = env.read_txn().unwrap();
+        let validate = |nonce, swap_id_version| {
+            let filled_tx = state.fill_transaction(
+                &rotxn,
+                &create_tx(2, nonce, swap_id_version),
+            )?;
+            state.validate_filled_transaction(&rotxn, &filled_tx)
+        };
+        assert!(matches!(
+            validate([1u8; 32], SWAP_ID_VERSION),
+            Err(Error::InvalidTransaction(_))
+        ));
+        assert!(matches!(
+            validate([3u8; 32], SWAP_ID_VERSION + 1),
+            Err(Error::Swap(error::Swap::UnsupportedIdVersion { .. }))
+        ));
+        assert_eq!(
+            validate([3u8; 32], SWAP_ID_VERSION).unwrap(),
+            bitcoin::Amount::ZERO
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Fixtures shared by state tests
+
+use bitcoin::hashes::Hash as _;
+use tempfile::TempDir;
+
+use crate::{
+    state::{PrevalidatedBlock, State, block},
+    types::{
+        Address, BitcoinOutputContent, Body, Header, Output, OutputContent,
+    },
+};
+
+/// Open a fresh state in a temporary directory
+pub(in crate::state) fn create_test_state() -> (State, sneed::Env, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let env = {
+        let mut env_open_opts = heed::EnvOpenOptions::new();
+        env_open_opts
+            .map_size(10 * 1024 * 1024)
+            .max_dbs(State::NUM_DBS);
+        unsafe { sneed::Env::open(&env_open_opts, temp_dir.path()) }.unwrap()
+    };
+    let state = State::new(&env).unwrap();
+    (state, env, temp_dir)
+}
+
+pub(in crate::state) fn bitcoin_output(address: Address, sats: u64) -> Output {
+    let value = BitcoinOutputContent(bitcoin::Amount::from_sat(sats));
+    Output::new(address, OutputContent::Bitcoin(value))
+}
+
+/// Connect a block on top of the current tip, either via
+/// [`block::connect`] or via [`block::connect_prevalidated`]
+pub(in crate::state) fn connect_block(
+    state: &State,
+    env: &sneed::Env,
+    body: &Body,
+    prevalidated: bool,
+) -> Header {
+    let mut rwtxn = env.write_txn().unwrap();
+    let prev_side_hash = state.try_get_tip(&rwtxn).unwrap();
+    let next_height =
+        state.try_get_height(&rwtxn).unwrap().map_or(0, |h| h + 1);
+    let header = Header {
+        merkle_root: body.compute_merkle_root(),
+        prev_side_hash,
+        prev_main_hash: bitcoin::BlockHash::all_zeros(),
+    };
+    if prevalidated {
+        let filled_transactions = body
+            .transactions
+            .iter()
+            .map(|tx| state.fill_transaction(&rwtxn, tx))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let prevalidated = PrevalidatedBlock {
+            filled_transactions,
+            computed_merkle_root: header.merkle_root,
+            total_fees: bitcoin::Amount::ZERO,
+            coinbase_value: body.get_coinbase_value().unwrap(),
+            next_height,
+        };
+        block::connect_prevalidated(
+            state,
+            &mut rwtxn,
+            &header,
+            body,
+            prevalidated,
+        )
+        .unwrap();
+    } else {
+        block::connect(state, &mut rwtxn, &header, body).unwrap();
+    }
+    rwtxn.commit().unwrap();
+    header
+}
@@ -35,6 +35,21 @@ impl ToSchema for BitcoinBlockHash {
     }
 }
 
+pub struct BitcoinBlockHeader;
+
+impl PartialSchema for BitcoinBlockHeader {
+    fn schema() -> RefOr<Schema> {
+        let obj = utoipa::openapi::Object::new();
+        RefOr::T(Schema::Object(obj))
+    }
+}
+
+impl ToSchema for BitcoinBlockHeader {
+    fn name() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("bitcoin.block.Header")
+    }
+}
+
 pub struct BitcoinOutPoint;
 
 impl PartialSchema for BitcoinOutPoint {
@@ -31,7 +31,8 @@ pub use output::{
     AssetContent as AssetOutputContent, AssetOutput,
     BitcoinContent as BitcoinOutputContent, BitcoinOutput,
     Content as OutputContent, FilledContent as FilledOutputContent,
-    FilledOutput, Output, Pointed as PointedOutput, SpentOutput,
+    FilledOutput, HtlcContent as HtlcOutputContent, Output,
+    Pointed as PointedOutput, SpentOutput,
     WithdrawalContent as WithdrawalOutputContent,
 };
 
@@ -390,6 +391,14 @@ pub enum TransactionData {
         #[serde(with = "serde_hexstr_human_readable")]
         #[schema(value_type = String)]
         swap_id: [u8; 32],
+        /// Version of the swap ID derivation
+        /// ([`crate::parent_chain::SWAP_ID_VERSION`])
+        swap_id_version: u8,
+        /// Nonce chosen by the offerer's wallet, that the swap ID commits
+        /// to so that identical offers have distinct IDs
+        #[serde(with = "serde_hexstr_human_readable")]
+        #[schema(value_type = String)]
+        nonce: [u8; 32],
         /// Parent chain type (BTC, BCH, LTC, XMR, ETH, Tron)
         parent_chain: crate::parent_chain::ParentChainType,
         /// L1 transaction ID that must be confirmed (for L2→L1, placeholder until filled)
@@ -397,25 +406,100 @@ pub enum TransactionData {
         l1_txid_bytes: Vec<u8>,
         /// Required number of confirmations
         required_confirmations: u32,
-        /// L2 address that will receive the coins (Bob's address for L2→L1)
-        l2_recipient: Address,
-        /// Amount of L2 coins to be paid
+        /// L2 address that will receive the coins (Bob's address for L2→L1).
+        /// `None` for open L2→L1 offers, which are filled by `SwapFill`.
+        l2_recipient: Option<Address>,
+        /// Amount of the L2 asset to be paid (sats for Bitcoin, base units
+        /// for other assets)
         l2_amount: u64,
         /// For L2→L1 swaps: L1 address where L1 coins should be sent (Alice's BTC address)
         l1_recipient_address: Option<String>,
         /// For L2→L1 swaps: Amount of L1 coins required
         l1_amount: Option<u64>,
+        /// For L2→L1 swaps on BTC-family chains: recent L1 block header that
+        /// claim proofs must build on
+        #[borsh(
+            serialize_with = "crate::parent_chain::proof::borsh_serialize_optional_block_header"
+        )]
+        #[schema(value_type = Option<crate::types::schema::BitcoinBlockHeader>)]
+        l1_checkpoint: Option<bitcoin::block::Header>,
+        /// For open offers: bond in sats that a filler must lock
+        fill_bond: u64,
+        /// For open offers: number of sidechain blocks for which a fill
+        /// reserves the offer
+        fill_timeout: u32,
+        /// Sidechain height from which the swap can no longer be filled or
+        /// claimed, and the offerer can refund the locked outputs
+        expires_at_height: Option<u32>,
+        /// For L2→L1 swaps: index of the output that escrows `l2_amount`.
+        /// Only this output is locked to the swap.
+        escrow_vout: u32,
+        /// For L2→L1 swaps: L2 asset held by the escrow output
+        l2_asset: crate::parent_chain::SwapAsset,
+        /// For open offers: whether several fills can each pay part of
+        /// `l1_amount`, for a pro-rata part of `l2_amount`
+        partial_fills: bool,
     },
-    /// Claim L2 coins from a swap after L1 transaction is confirmed
+    /// Claim L2 coins from a swap by proving that the L1 transaction is
+    /// confirmed.
+    /// A claim that leaves part of a partially fillable offer in escrow
+    /// re-escrows the rest in the first output, owned by the offerer.
     SwapClaim {
         /// Swap ID to claim
         #[serde(with = "serde_hexstr_human_readable")]
         #[schema(value_type = String)]
         swap_id: [u8; 32],
-        /// Optional proof data for future verification enhancements
+        /// Proof that the L1 transaction is confirmed, verified by every
+        /// node without querying the parent chain
+        proof_data: crate::parent_chain::L1TxProof,
+    },
+    /// Relay parent chain block headers. The headers must extend a header
+    /// that is already in the relay, unless the relay for the parent chain
+    /// is empty.
+    ParentChainHeaders {
+        /// Parent chain (BTC, BCH, or LTC)
+        parent_chain: crate::parent_chain::ParentChainType,
+        /// Parent chain height of the first header
+        start_height: u32,
+        /// Consecutive parent chain headers
+        #[borsh(
+            serialize_with = "crate::parent_chain::proof::borsh_serialize_block_headers"
+        )]
+        #[schema(value_type = Vec<crate::types::schema::BitcoinBlockHeader>)]
+        headers: Vec<bitcoin::block::Header>,
+    },
+    /// Fill an open swap offer, reserving it for `l2_recipient`.
+    /// If the offer requires a bond, the first output is the bond, and is
+    /// locked to the swap.
+    SwapFill {
+        /// Swap ID to fill
         #[serde(with = "serde_hexstr_human_readable")]
-        #[schema(value_type = Option<String>)]
-        proof_data: Option<Hash>,
+        #[schema(value_type = String)]
+        swap_id: [u8; 32],
+        /// L2 address that will claim the swap
+        l2_recipient: Address,
+        /// L1 transaction that pays the offerer.
+        /// Serialized as hex for Hash32, bytes for Hash
+        l1_txid_bytes: Vec<u8>,
+        /// For partially fillable offers: L1 amount in sats paid by the
+        /// fill. `None` for other offers, which are filled for `l1_amount`.
+        l1_amount: Option<u64>,
+    },
+    /// Refund the outputs locked to an expired swap. Every input must be
+    /// locked to the swap.
+    SwapRefund {
+        /// Swap ID to refund
+        #[serde(with = "serde_hexstr_human_readable")]
+        #[schema(value_type = String)]
+        swap_id: [u8; 32],
+    },
+    /// Reveal the preimage of a hash lock, so that the recipients of
+    /// hash time-locked inputs with that hash lock can redeem them before
+    /// they time out
+    HtlcRedeem {
+        #[serde(with = "serde_hexstr_human_readable")]
+        #[schema(value_type = String)]
+        preimage: [u8; 32],
     },
 }
 
@@ -1015,12 +1099,14 @@ impl FilledTransaction {
     }
 
     /** Return an iterator over spent assets (Bitcoin, BitAssets,
-     * and BitAsset control coins) */
+     * and BitAsset control coins). Hash time-locked outputs are spent as
+     * Bitcoin. */
     pub fn spent_assets(
         &self,
     ) -> impl DoubleEndedIterator<Item = (&OutPoint, &FilledOutput)> {
         self.spent_inputs().filter(|(_, filled_output)| {
             filled_output.is_bitcoin()
+                || filled_output.htlc().is_some()
                 || filled_output.is_bitasset()
                 || filled_output.is_bitasset_control()
         })
@@ -1591,6 +1677,11 @@ impl FilledTransaction {
                     OutputContent::Withdrawal(withdrawal) => {
                         FilledOutputContent::BitcoinWithdrawal(withdrawal)
                     }
+                    OutputContent::Htlc(htlc) => {
+                        output_bitcoin_max_value =
+                            output_bitcoin_max_value.checked_sub(htlc.value)?;
+                        FilledOutputContent::Htlc(htlc)
+                    }
                 };
                 Some(FilledOutput {
                     address: output.address,
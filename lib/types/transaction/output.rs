@@ -1,3 +1,4 @@
+use bitcoin::hashes::{Hash as _, sha256};
 use borsh::BorshSerialize;
 use serde::{Deserialize, Serialize};
 use serde_with::{DeserializeAs, IfIsHumanReadable, SerializeAs, serde_as};
@@ -64,6 +65,50 @@ pub struct BitcoinContent(
     pub bitcoin::Amount,
 );
 
+/// Hash time-locked Bitcoin, in the style of Bitcoin script HTLCs.
+/// Before `timeout_height`, `recipient` can redeem the output by revealing a
+/// preimage of `hash_lock` in an `HtlcRedeem` tx. From `timeout_height`, the
+/// output's address can refund it.
+#[serde_as]
+#[derive(
+    BorshSerialize,
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    ToSchema,
+)]
+#[schema(as = HtlcOutputContent)]
+pub struct HtlcContent {
+    #[borsh(serialize_with = "borsh_serialize_bitcoin_amount")]
+    #[serde_as(as = "IfIsHumanReadable<BitcoinAmountSats>")]
+    #[schema(value_type = u64)]
+    pub value: bitcoin::Amount,
+    /// SHA-256 hash of the 32-byte preimage
+    #[serde(with = "serde_hexstr_human_readable")]
+    #[schema(value_type = String)]
+    pub hash_lock: [u8; 32],
+    /// Address that can redeem the output with the preimage
+    pub recipient: Address,
+    /// Sidechain height from which the output can only be refunded
+    pub timeout_height: u32,
+}
+
+impl HtlcContent {
+    /// `true` if `preimage` hashes to the hash lock
+    pub fn is_preimage(&self, preimage: &[u8; 32]) -> bool {
+        sha256::Hash::hash(preimage).to_byte_array() == self.hash_lock
+    }
+
+    /// `true` if the output can be redeemed at the specified height
+    pub fn is_redeemable(&self, height: u32) -> bool {
+        height < self.timeout_height
+    }
+}
+
 fn borsh_serialize_bitcoin_address<V, W>(
     bitcoin_address: &bitcoin::Address<V>,
     writer: &mut W,
@@ -291,6 +336,8 @@ mod content {
                 /// Receipt used to redeem the proceeds of an auction
                 DutchAuctionReceipt,
                 Withdrawal(super::WithdrawalContent),
+                /// Hash time-locked Bitcoin
+                Htlc(super::HtlcContent),
             }
         }
     }
@@ -347,6 +394,12 @@ mod content {
             matches!(self, Self::Withdrawal { .. })
         }
 
+        /// `true` if the output content corresponds to a hash time-locked
+        /// output
+        pub fn is_htlc(&self) -> bool {
+            matches!(self, Self::Htlc(_))
+        }
+
         /// `true` if the output corresponds to an asset output
         pub fn is_asset(&self) -> bool {
             matches!(
@@ -381,7 +434,8 @@ mod content {
                 }
                 Self::AmmLpToken(_)
                 | Self::BitAssetReservation
-                | Self::DutchAuctionReceipt => None,
+                | Self::DutchAuctionReceipt
+                | Self::Htlc(_) => None,
             }
         }
     }
@@ -417,6 +471,7 @@ mod content {
                 DefaultRepr::Withdrawal(withdrawal) => {
                     Self::Withdrawal(withdrawal)
                 }
+                DefaultRepr::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -437,6 +492,7 @@ mod content {
                 HumanReadableRepr::Withdrawal(withdrawal) => {
                     Self::Withdrawal(withdrawal)
                 }
+                HumanReadableRepr::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -451,6 +507,7 @@ mod content {
                 Content::Bitcoin(value) => Self::Bitcoin(value),
                 Content::DutchAuctionReceipt => Self::DutchAuctionReceipt,
                 Content::Withdrawal(withdrawal) => Self::Withdrawal(withdrawal),
+                Content::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -465,6 +522,7 @@ mod content {
                 Content::Bitcoin(value) => Self::Bitcoin(value),
                 Content::DutchAuctionReceipt => Self::DutchAuctionReceipt,
                 Content::Withdrawal(withdrawal) => Self::Withdrawal(withdrawal),
+                Content::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -514,6 +572,7 @@ mod content {
                 | Self::DutchAuctionReceipt => bitcoin::Amount::ZERO,
                 Self::Bitcoin(value) => value.0,
                 Self::Withdrawal(withdrawal) => withdrawal.get_bitcoin_value(),
+                Self::Htlc(htlc) => htlc.value,
             }
         }
     }
@@ -567,6 +626,8 @@ mod filled_content {
                 ),
                 /// Auction ID
                 DutchAuctionReceipt(DutchAuctionId),
+                /// Hash time-locked Bitcoin
+                Htlc(super::HtlcContent),
             }
         }
     }
@@ -646,6 +707,9 @@ mod filled_content {
                 Self::Bitcoin(value) => {
                     Some((AssetId::Bitcoin, value.0.to_sat()))
                 }
+                Self::Htlc(htlc) => {
+                    Some((AssetId::Bitcoin, htlc.value.to_sat()))
+                }
                 _ => None,
             }
         }
@@ -692,6 +756,15 @@ mod filled_content {
             matches!(self, Self::DutchAuctionReceipt(_))
         }
 
+        /// Returns the hash time lock, if the filled output content
+        /// corresponds to a hash time-locked output
+        pub fn htlc(&self) -> Option<&super::HtlcContent> {
+            match self {
+                Self::Htlc(htlc) => Some(htlc),
+                _ => None,
+            }
+        }
+
         /// `true` if the output content corresponds to an LP token
         pub fn is_lp_token(&self) -> bool {
             matches!(self, Self::AmmLpToken { .. })
@@ -749,6 +822,7 @@ mod filled_content {
                 FilledContent::DutchAuctionReceipt(_) => {
                     super::Content::DutchAuctionReceipt
                 }
+                FilledContent::Htlc(htlc) => super::Content::Htlc(htlc),
             }
         }
     }
@@ -781,6 +855,7 @@ mod filled_content {
                 DefaultRepr::DutchAuctionReceipt(auction_id) => {
                     Self::DutchAuctionReceipt(auction_id)
                 }
+                DefaultRepr::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -813,6 +888,7 @@ mod filled_content {
                 HumanReadableRepr::DutchAuctionReceipt(auction_id) => {
                     Self::DutchAuctionReceipt(auction_id)
                 }
+                HumanReadableRepr::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -845,6 +921,7 @@ mod filled_content {
                 FilledContent::DutchAuctionReceipt(auction_id) => {
                     Self::DutchAuctionReceipt(auction_id)
                 }
+                FilledContent::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -877,6 +954,7 @@ mod filled_content {
                 FilledContent::DutchAuctionReceipt(auction_id) => {
                     Self::DutchAuctionReceipt(auction_id)
                 }
+                FilledContent::Htlc(htlc) => Self::Htlc(htlc),
             }
         }
     }
@@ -1094,6 +1172,12 @@ impl FilledOutput {
         self.content.is_dutch_auction_receipt()
     }
 
+    /// Returns the hash time lock, if the filled output content corresponds
+    /// to a hash time-locked output
+    pub fn htlc(&self) -> Option<&HtlcContent> {
+        self.content.htlc()
+    }
+
     /// `true` if the output content corresponds to an LP token
     pub fn is_lp_token(&self) -> bool {
         self.content.is_lp_token()
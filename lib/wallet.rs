@@ -22,14 +22,17 @@ use tokio_stream::{StreamMap, wrappers::WatchStream};
 
 use crate::{
     authorization::{self, Authorization, Signature, get_address},
-    parent_chain::{swap::Swap, config::ParentChainType, SwapId, client::TxId},
+    parent_chain::{
+        L1TxProof, SWAP_ID_VERSION, SwapAsset, SwapId, client::TxId,
+        config::ParentChainType, swap::Swap,
+    },
     types::{
         Address, AmountOverflowError, AmountUnderflowError, AssetId,
         AuthorizedTransaction, BitAssetData, BitAssetId, BitcoinOutputContent,
         DutchAuctionId, DutchAuctionParams, EncryptionPubKey, FilledOutput,
-        GetBitcoinValue, Hash, InPoint, OutPoint, Output, OutputContent,
-        SpentOutput, Transaction, TxData, VERSION, VerifyingKey, Version,
-        WithdrawalOutputContent, keys::Ecies,
+        GetBitcoinValue, Hash, HtlcOutputContent, InPoint, OutPoint, Output,
+        OutputContent, SpentOutput, Transaction, TxData, VERSION, VerifyingKey,
+        Version, WithdrawalOutputContent, keys::Ecies,
     },
     util::Watchable,
 };
@@ -476,6 +479,23 @@ impl Wallet {
         Ok(Transaction::new(inputs, outputs))
     }
 
+    /// Create a transaction that relays parent chain headers
+    pub fn create_parent_chain_headers_tx(
+        &self,
+        parent_chain: ParentChainType,
+        start_height: u32,
+        headers: Vec<bitcoin::block::Header>,
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let mut tx = self.create_regular_transaction(fee)?;
+        tx.data = Some(TxData::ParentChainHeaders {
+            parent_chain,
+            start_height,
+            headers,
+        });
+        Ok(tx)
+    }
+
     pub fn create_withdrawal(
         &self,
         main_address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
@@ -542,6 +562,82 @@ impl Wallet {
         Ok(Transaction::new(inputs, outputs))
     }
 
+    /// Create a transaction that locks `value` in an HTLC output.
+    /// The output can be redeemed by `recipient` with a preimage of
+    /// `hash_lock` before `timeout_height`, or refunded to a new address of
+    /// this wallet after it.
+    pub fn create_htlc_tx(
+        &self,
+        recipient: Address,
+        value: bitcoin::Amount,
+        hash_lock: [u8; 32],
+        timeout_height: u32,
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let (total, coins) = self.select_bitcoins(
+            value.checked_add(fee).ok_or(AmountOverflowError)?,
+        )?;
+        let change = total - value - fee;
+        let inputs = coins.into_keys().collect();
+        let mut outputs = vec![Output::new(
+            self.get_new_address()?,
+            OutputContent::Htlc(HtlcOutputContent {
+                value,
+                hash_lock,
+                recipient,
+                timeout_height,
+            }),
+        )];
+        if change != Amount::ZERO {
+            outputs.push(Output::new(
+                self.get_new_address()?,
+                OutputContent::Bitcoin(BitcoinOutputContent(change)),
+            ))
+        }
+        Ok(Transaction::new(inputs, outputs))
+    }
+
+    /// Spend an HTLC output into a new address of this wallet, paying the
+    /// fee from its value.
+    fn htlc_release_tx(
+        &self,
+        outpoint: OutPoint,
+        htlc: &HtlcOutputContent,
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let value = htlc.value.checked_sub(fee).ok_or(AmountUnderflowError)?;
+        let outputs = vec![Output::new(
+            self.get_new_address()?,
+            OutputContent::Bitcoin(BitcoinOutputContent(value)),
+        )];
+        Ok(Transaction::new(vec![outpoint], outputs))
+    }
+
+    /// Create a transaction that redeems an HTLC output with the preimage of
+    /// its hash lock. The HTLC output is not owned by this wallet, so it is
+    /// taken from node state by the caller.
+    pub fn create_htlc_redeem_tx(
+        &self,
+        outpoint: OutPoint,
+        htlc: &HtlcOutputContent,
+        preimage: [u8; 32],
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let mut tx = self.htlc_release_tx(outpoint, htlc, fee)?;
+        tx.data = Some(TxData::HtlcRedeem { preimage });
+        Ok(tx)
+    }
+
+    /// Create a transaction that refunds a timed out HTLC output
+    pub fn create_htlc_refund_tx(
+        &self,
+        outpoint: OutPoint,
+        htlc: &HtlcOutputContent,
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        self.htlc_release_tx(outpoint, htlc, fee)
+    }
+
     pub fn create_bitasset_transfer(
         &self,
         address: Address,
@@ -1410,7 +1506,8 @@ impl Wallet {
                     .total
                     .checked_add(value)
                     .ok_or(AmountOverflowError)?;
-                if !utxo.content.is_withdrawal() {
+                // HTLC outputs are only spendable once refundable
+                if !utxo.content.is_withdrawal() && utxo.htlc().is_none() {
                     balance.available = balance
                         .available
                         .checked_add(value)
@@ -1548,6 +1645,37 @@ impl Wallet {
         })
     }
 
+    /// Authorize each input of a tx with the key for the corresponding
+    /// address in `authorizers`. Unlike [`Self::authorize`], the spent
+    /// outputs do not need to be owned by the wallet, such as a swap escrow
+    /// that the wallet claims.
+    pub fn authorize_for(
+        &self,
+        transaction: Transaction,
+        authorizers: &[Address],
+    ) -> Result<AuthorizedTransaction, Error> {
+        let rotxn = self.env.read_txn()?;
+        let authorizations = authorizers
+            .iter()
+            .map(|authorizer| {
+                let tx_signing_key =
+                    self.get_tx_signing_key_for_addr(&rotxn, authorizer)?;
+                let signature = crate::authorization::sign_tx(
+                    &tx_signing_key,
+                    &transaction,
+                )?;
+                Ok(Authorization {
+                    verifying_key: tx_signing_key.verifying_key().into(),
+                    signature,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(AuthorizedTransaction {
+            authorizations,
+            transaction,
+        })
+    }
+
     pub fn get_num_addresses(&self) -> Result<u32, Error> {
         let rotxn = self.env.read_txn()?;
         let res = self.index_to_address.len(&rotxn)? as u32;
@@ -1584,52 +1712,95 @@ impl Wallet {
     }
 
     /// Create a SwapCreate transaction for L2 → L1 swap
-    /// Alice locks her L2 coins in exchange for L1 assets
+    /// Alice locks `l2_amount` of an L2 asset in exchange for L1 assets.
+    /// If `l2_recipient` is `None`, the swap is an open offer that any
+    /// filler can take, by locking `fill_bond` to the swap for
+    /// `fill_timeout` sidechain blocks.
+    /// The escrow output is owned by Alice, so that she can refund it once
+    /// the swap expires at `expires_at_height`.
+    /// If `partial_fills` is set, an open offer can be filled by several
+    /// fillers, each paying part of `l1_amount`.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_swap_create_tx(
         &self,
         parent_chain: ParentChainType,
         l1_recipient_address: String,
         l1_amount: bitcoin::Amount,
-        l2_recipient: Address,
-        l2_amount: bitcoin::Amount,
+        l2_recipient: Option<Address>,
+        l2_asset: SwapAsset,
+        l2_amount: u64,
         required_confirmations: Option<u32>,
+        l1_checkpoint: Option<bitcoin::block::Header>,
+        fill_bond: bitcoin::Amount,
+        fill_timeout: u32,
+        expires_at_height: Option<u32>,
+        partial_fills: bool,
         current_height: u32,
     ) -> Result<(Transaction, SwapId), Error> {
         // Create swap object to get swap ID
         let alice_address = self.get_new_address()?; // Alice's address (sender)
-        let swap = Swap::new_l2_to_l1(
-            parent_chain.clone(),
-            l1_recipient_address.clone(),
-            l1_amount,
-            alice_address, // Alice's L2 address
-            l2_amount,
-            l2_recipient,
-            required_confirmations,
-            current_height,
-        );
-        let swap_id = swap.id;
-
-        // Select UTXOs to spend (must be at least l2_amount)
-        let (total, coins) = self.select_bitcoins(l2_amount)?;
+        let swap = match l2_recipient {
+            Some(l2_recipient) => Swap::new_l2_to_l1(
+                parent_chain.clone(),
+                l1_recipient_address.clone(),
+                l1_amount,
+                alice_address, // Alice's L2 address
+                Amount::from_sat(l2_amount),
+                l2_recipient,
+                required_confirmations,
+                current_height,
+            ),
+            None => {
+                let swap = Swap::new_open(
+                    parent_chain,
+                    l1_recipient_address.clone(),
+                    l1_amount,
+                    Amount::from_sat(l2_amount),
+                    fill_bond,
+                    fill_timeout,
+                    required_confirmations,
+                    current_height,
+                );
+                if partial_fills {
+                    swap.with_partial_fills()
+                } else {
+                    swap
+                }
+            }
+        }
+        .with_l2_asset(l2_asset);
+
+        // Select UTXOs of the L2 asset to spend (must be at least l2_amount)
+        let (total, inputs): (u64, Vec<OutPoint>) = match l2_asset {
+            SwapAsset::Asset(asset) => {
+                let (total, utxos) =
+                    self.select_asset_utxos(asset, l2_amount)?;
+                (total, utxos.into_keys().collect())
+            }
+            SwapAsset::AmmLpToken { asset0, asset1 } => {
+                let (total, utxos) =
+                    self.select_amm_lp_tokens(asset0, asset1, l2_amount)?;
+                (total, utxos.into_keys().collect())
+            }
+        };
         let change = total - l2_amount;
 
-        // Create transaction inputs
-        let inputs: Vec<OutPoint> = coins.keys().copied().collect();
+        // The inputs can only be spent once, so a nonce derived from them
+        // gives identical offers distinct swap IDs
+        let nonce = *blake3::hash(&borsh::to_vec(&inputs)?).as_bytes();
+        let swap_id = swap.with_nonce(nonce).id;
 
         // Create transaction outputs
-        // For L2→L1 swaps, outputs will be locked to the swap
-        // We create outputs that will be locked when the transaction is processed
-        let mut outputs = vec![Output {
-            address: l2_recipient, // Bob's address (will receive after claim)
-            content: OutputContent::Bitcoin(BitcoinOutputContent(l2_amount)),
-            memo: Vec::new(),
-        }];
+        // The first output escrows l2_amount, and is locked to the swap when
+        // the transaction is processed. Change is not locked.
+        let mut outputs =
+            vec![Output::new(alice_address, l2_asset.output_content(l2_amount))];
 
         // Add change output if needed
-        if change != Amount::ZERO {
+        if change != 0 {
             outputs.push(Output::new(
                 self.get_new_address()?,
-                OutputContent::Bitcoin(BitcoinOutputContent(change)),
+                l2_asset.output_content(change),
             ));
         }
 
@@ -1643,89 +1814,180 @@ impl Wallet {
         let mut tx = Transaction::new(inputs, outputs);
         tx.data = Some(TxData::SwapCreate {
             swap_id: swap_id.0,
+            swap_id_version: SWAP_ID_VERSION,
+            nonce,
             parent_chain,
             l1_txid_bytes,
             required_confirmations: required_confirmations
                 .unwrap_or_else(|| crate::parent_chain::config::default_confirmations(parent_chain)),
             l2_recipient,
-            l2_amount: l2_amount.to_sat(),
+            l2_amount,
             l1_recipient_address: Some(l1_recipient_address),
             l1_amount: Some(l1_amount.to_sat()),
+            l1_checkpoint,
+            fill_bond: fill_bond.to_sat(),
+            fill_timeout,
+            expires_at_height,
+            escrow_vout: 0,
+            l2_asset,
+            partial_fills,
         });
 
         Ok((tx, swap_id))
     }
 
-    /// Create a SwapClaim transaction
-    /// Bob claims Alice's locked L2 coins after L1 payment is confirmed
-    /// Note: The locked outputs should be provided by the caller (RPC server)
-    /// which verifies they are locked to the swap. This method creates the transaction.
-    /// The outputs might not be in the wallet yet, so we need to get them from node state.
-    pub fn create_swap_claim_tx(
+    /// Create a SwapFill transaction for an open swap offer.
+    /// Bob reserves the offer for `l2_recipient`, committing to the L1
+    /// transaction that pays Alice. The first output is Bob's bond.
+    /// Fills of partially fillable offers reserve the part of the offer
+    /// that `l1_amount` pays for.
+    pub fn create_swap_fill_tx(
         &self,
         swap_id: SwapId,
-        locked_outputs: Vec<OutPoint>,
-        recipient: Address,
+        l2_recipient: Address,
+        l1_txid: TxId,
+        l1_amount: Option<bitcoin::Amount>,
+        bond: bitcoin::Amount,
+        fee: bitcoin::Amount,
     ) -> Result<Transaction, Error> {
-        // Get values from locked outputs
-        // Try wallet UTXOs first, but if not found, the RPC server should provide
-        // the values from state. For now, we'll require them to be in wallet.
-        let rotxn = self.env.read_txn()?;
-        let mut inputs = Vec::new();
-        let mut total_value = Amount::ZERO;
+        let mut tx = if bond == Amount::ZERO {
+            self.create_regular_transaction(fee)?
+        } else {
+            self.create_transfer(self.get_new_address()?, bond, fee, None)?
+        };
+        let l1_txid_bytes = match l1_txid {
+            TxId::Hash32(hash) => hash.to_vec(),
+            TxId::Hash(hash) => hash,
+        };
+        tx.data = Some(TxData::SwapFill {
+            swap_id: swap_id.0,
+            l2_recipient,
+            l1_txid_bytes,
+            l1_amount: l1_amount.map(|l1_amount| l1_amount.to_sat()),
+        });
+        Ok(tx)
+    }
 
-        for outpoint in &locked_outputs {
-            let key = crate::types::OutPointKey::from_outpoint(outpoint);
-            // Try confirmed UTXOs first
-            if let Some(output) = self.utxos.try_get(&rotxn, &key)? {
-                total_value = total_value
-                    .checked_add(output.get_bitcoin_value())
-                    .ok_or(AmountOverflowError)?;
-                inputs.push(*outpoint);
-            } else if let Some(output) = self.unconfirmed_utxos.try_get(&rotxn, &key)? {
-                // Try unconfirmed UTXOs
-                total_value = total_value
+    /// Outputs that release the outputs locked to a swap to `address`,
+    /// preserving their assets. The Bitcoin value of the locked outputs,
+    /// less `fee`, is paid in a single output.
+    fn swap_release_outputs(
+        address: Address,
+        locked_outputs: &HashMap<OutPoint, FilledOutput>,
+        fee: bitcoin::Amount,
+    ) -> Result<Vec<Output>, Error> {
+        let mut bitcoin_value = Amount::ZERO;
+        let mut outputs = Vec::new();
+        for output in locked_outputs.values() {
+            if output.is_bitcoin() {
+                bitcoin_value = bitcoin_value
                     .checked_add(output.get_bitcoin_value())
                     .ok_or(AmountOverflowError)?;
-                inputs.push(*outpoint);
             } else {
-                // Output not in wallet - this is OK for swap claims
-                // The validation will happen at the state level
-                // We'll still add it as input and let state validation handle it
-                inputs.push(*outpoint);
+                let content = output.content.clone().into();
+                outputs.push(Output::new(address, content));
             }
         }
-
-        if inputs.is_empty() {
-            return Err(Error::NotEnoughFunds);
+        let bitcoin_value =
+            bitcoin_value.checked_sub(fee).ok_or(Error::NotEnoughFunds)?;
+        if bitcoin_value != Amount::ZERO {
+            outputs.push(Output::new(
+                address,
+                OutputContent::Bitcoin(BitcoinOutputContent(bitcoin_value)),
+            ));
         }
+        Ok(outputs)
+    }
 
-        // If we couldn't get all values from wallet, we need to estimate
-        // The RPC server should have verified the outputs exist in state
-        // For now, if total_value is zero, we'll use the swap's l2_amount
-        // But this is a limitation - ideally we'd get values from state
-        
-        // Create output - send to swap recipient (Bob)
-        let outputs = vec![Output {
-            address: recipient,
-            content: OutputContent::Bitcoin(BitcoinOutputContent(
-                if total_value == Amount::ZERO {
-                    // Fallback: This shouldn't happen if wallet is synced
-                    // But we'll allow it and let state validation catch errors
-                    Amount::ZERO
-                } else {
-                    total_value
-                }
-            )),
-            memo: Vec::new(),
-        }];
+    /// Create a SwapRefund transaction
+    /// Alice reclaims the outputs locked to an expired swap. The fee is paid
+    /// from the Bitcoin value of the locked outputs.
+    pub fn create_swap_refund_tx(
+        &self,
+        swap_id: SwapId,
+        locked_outputs: HashMap<OutPoint, FilledOutput>,
+        fee: bitcoin::Amount,
+    ) -> Result<Transaction, Error> {
+        let outputs = Self::swap_release_outputs(
+            self.get_new_address()?,
+            &locked_outputs,
+            fee,
+        )?;
+        let mut tx =
+            Transaction::new(locked_outputs.into_keys().collect(), outputs);
+        tx.data = Some(TxData::SwapRefund { swap_id: swap_id.0 });
+        Ok(tx)
+    }
 
+    /// Create a SwapClaim transaction
+    /// Bob claims Alice's locked L2 assets after L1 payment is confirmed,
+    /// proven by `proof_data`.
+    /// The locked outputs are taken from node state by the caller, since
+    /// they are not owned by this wallet.
+    /// If Bob claims part of a partially fillable offer, the rest of the
+    /// escrow is re-escrowed for Alice in the first output.
+    pub fn create_swap_claim_tx(
+        &self,
+        swap: &Swap,
+        mut locked_outputs: HashMap<OutPoint, FilledOutput>,
+        recipient: Address,
+        proof_data: L1TxProof,
+    ) -> Result<Transaction, Error> {
+        if locked_outputs.is_empty() {
+            return Err(Error::NotEnoughFunds);
+        }
+        let rest = swap
+            .claimable_fill(&proof_data.l1_txid())
+            .map_or(Amount::ZERO, |fill| {
+                let released = swap.fill_l2_amount(
+                    fill.l1_amount.unwrap_or(Amount::ZERO),
+                );
+                swap.l2_amount_remaining - released
+            })
+            .to_sat();
+        let mut outputs = Vec::new();
+        let mut inputs = Vec::new();
+        if rest != 0 {
+            let offerer = swap.l2_offerer.ok_or(Error::NotEnoughFunds)?;
+            // Split the escrow between the offerer and the recipient
+            let escrow: Vec<OutPoint> = locked_outputs
+                .iter()
+                .filter(|(_, output)| output.address == offerer)
+                .map(|(outpoint, _)| *outpoint)
+                .collect();
+            let mut escrowed = 0;
+            for outpoint in escrow {
+                let output = locked_outputs.remove(&outpoint).unwrap();
+                let (_, amount) = SwapAsset::of_output_content(&output.content)
+                    .filter(|(asset, _)| *asset == swap.l2_asset)
+                    .ok_or(Error::NotEnoughFunds)?;
+                escrowed += amount;
+                inputs.push(outpoint);
+            }
+            let released =
+                escrowed.checked_sub(rest).ok_or(Error::NotEnoughFunds)?;
+            outputs.push(Output::new(
+                offerer,
+                swap.l2_asset.output_content(rest),
+            ));
+            if released != 0 {
+                outputs.push(Output::new(
+                    recipient,
+                    swap.l2_asset.output_content(released),
+                ));
+            }
+        }
+        outputs.extend(Self::swap_release_outputs(
+            recipient,
+            &locked_outputs,
+            Amount::ZERO,
+        )?);
+        inputs.extend(locked_outputs.into_keys());
         let mut tx = Transaction::new(inputs, outputs);
         tx.data = Some(TxData::SwapClaim {
-            swap_id: swap_id.0,
-            proof_data: None,
+            swap_id: swap.id.0,
+            proof_data,
         });
-
         Ok(tx)
     }
 }
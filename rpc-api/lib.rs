@@ -9,14 +9,18 @@ use l2l_openapi::open_api;
 use plain_bitassets::{
     authorization::{Dst, Signature},
     net::{Peer, PeerConnectionStatus},
-    parent_chain::{swap::Swap, config::ParentChainType, SwapId},
-    state::{AmmPoolState, BitAssetSeqId, DutchAuctionState},
+    parent_chain::{
+        swap::Swap, config::ParentChainType, SwapAsset, SwapId, SwapStatus,
+    },
+    state::{
+        AmmPoolState, BitAssetSeqId, DutchAuctionState, RelayHeader, RelayTip,
+    },
     types::{
         Address, AssetId, Authorization, BitAssetData, BitAssetDataUpdates,
         BitAssetId, BitcoinOutputContent, Block, BlockHash, Body,
         DutchAuctionId, DutchAuctionParams, EncryptionPubKey,
-        FilledOutputContent, Header, MerkleRoot, OutPoint, Output,
-        OutputContent, PointedOutput, Transaction, TxData, TxIn, Txid,
+        FilledOutputContent, Header, HtlcOutputContent, MerkleRoot, OutPoint,
+        Output, OutputContent, PointedOutput, Transaction, TxData, TxIn, Txid,
         VerifyingKey, WithdrawalBundle, WithdrawalOutputContent,
         schema as bitassets_schema,
     },
@@ -42,9 +46,9 @@ pub struct TxInfo {
     bitassets_schema::SocketAddr, Address, AssetId, Authorization,
     BitAssetData, BitAssetDataUpdates, BitAssetId, BitcoinOutputContent,
     BlockHash, Body, DutchAuctionId, DutchAuctionParams, EncryptionPubKey,
-    FilledOutputContent, Header, MerkleRoot, OutPoint, Output, OutputContent,
-    PeerConnectionStatus, Signature, Transaction, TxData, Txid, TxIn,
-    WithdrawalOutputContent, VerifyingKey,
+    FilledOutputContent, Header, HtlcOutputContent, MerkleRoot, OutPoint,
+    Output, OutputContent, PeerConnectionStatus, Signature, Transaction,
+    TxData, Txid, TxIn, WithdrawalOutputContent, VerifyingKey,
 ])]
 #[rpc(client, server)]
 pub trait Rpc {
@@ -421,41 +425,152 @@ pub trait Rpc {
         mainchain_fee_sats: u64,
     ) -> RpcResult<Txid>;
 
-    /// Create a new L2 → L1 swap (offer L2 coins for L1 assets)
+    /// Create a new L2 → L1 swap (offer L2 coins for L1 assets).
+    /// If `l2_recipient` is not set, the swap is an open offer that any
+    /// filler can take with `fill_swap`.
+    /// `l2_asset` defaults to Bitcoin. For other assets, such as BitAssets
+    /// or AMM LP tokens, `l2_amount_sats` is in base units of the asset.
+    /// If `partial_fills` is set, an open offer can be filled by several
+    /// fillers, each paying part of the L1 amount.
     #[method(name = "create_swap")]
     async fn create_swap(
         &self,
         parent_chain: ParentChainType,
         l1_recipient_address: String,
         l1_amount_sats: u64,
-        l2_recipient: Address,
+        l2_recipient: Option<Address>,
         l2_amount_sats: u64,
         required_confirmations: Option<u32>,
+        fill_bond_sats: Option<u64>,
+        fill_timeout: Option<u32>,
+        expiry_blocks: Option<u32>,
+        l2_asset: Option<SwapAsset>,
+        partial_fills: Option<bool>,
     ) -> RpcResult<String>; // Returns swap_id as hex string
 
-    /// Update swap with L1 transaction ID (when L1 payment is sent)
+    /// Fill an open swap offer, committing to the L1 transaction that pays
+    /// the offerer. The swap's fill bond is locked to the swap.
+    /// Partially fillable offers must set `l1_amount_sats`, the part of the
+    /// L1 amount that the L1 transaction pays.
+    #[method(name = "fill_swap")]
+    async fn fill_swap(
+        &self,
+        swap_id: String, // hex encoded
+        l1_txid: String, // hex encoded transaction ID
+        l2_recipient: Option<Address>,
+        fee_sats: u64,
+        l1_amount_sats: Option<u64>,
+    ) -> RpcResult<Txid>;
+
+    /// Update swap with L1 transaction ID (when L1 payment is sent).
+    /// The L1 transaction ID is only tracked by this node's swap watcher.
+    /// `l1_payment_proof` is reserved for Monero payments, which can only be
+    /// checked with a tx key or tx proof. Monero swaps are not supported
+    /// yet, so setting it is an error.
     #[method(name = "update_swap_l1_txid")]
     async fn update_swap_l1_txid(
         &self,
         swap_id: String, // hex encoded
         l1_txid: String, // hex encoded transaction ID
+        l1_payment_proof: Option<String>,
+    ) -> RpcResult<()>;
+
+    /// Set or clear this node's note on a swap. Notes are only stored
+    /// locally, and are reported by `get_swap_status`.
+    #[method(name = "set_swap_note")]
+    async fn set_swap_note(
+        &self,
+        swap_id: String, // hex encoded
+        note: Option<String>,
     ) -> RpcResult<()>;
 
-    /// Get swap status
+    /// Get swap status, including any disagreement between this node's
+    /// parent chain backends about the swap's L1 transaction
     #[method(name = "get_swap_status")]
     async fn get_swap_status(
         &self,
         swap_id: String, // hex encoded
-    ) -> RpcResult<Swap>;
+    ) -> RpcResult<SwapStatus>;
 
-    /// Claim a swap (create SwapClaim transaction)
+    /// Claim a swap (create SwapClaim transaction).
+    /// For partially fillable offers, `l1_txid` selects the fill to claim,
+    /// and defaults to the latest claimable fill of this wallet.
     #[method(name = "claim_swap")]
     async fn claim_swap(
+        &self,
+        swap_id: String,         // hex encoded
+        l1_txid: Option<String>, // hex encoded transaction ID
+    ) -> RpcResult<Txid>;
+
+    /// Refund the outputs locked to an expired swap that this wallet can
+    /// sign for, including bonds that timed-out fills forfeited to the
+    /// offerer
+    #[method(name = "refund_swap")]
+    async fn refund_swap(
         &self,
         swap_id: String, // hex encoded
+        fee_sats: u64,
     ) -> RpcResult<Txid>;
 
     /// List all swaps
     #[method(name = "list_swaps")]
     async fn list_swaps(&self) -> RpcResult<Vec<Swap>>;
+
+    /// Lock coins in a hash time-locked output, that `recipient` can redeem
+    /// with a preimage of `hash_lock` before `timeout_height`. After it,
+    /// the output can be refunded to this wallet with `refund_htlc`.
+    #[method(name = "create_htlc")]
+    async fn create_htlc(
+        &self,
+        recipient: Address,
+        value_sats: u64,
+        hash_lock: String, // hex encoded SHA-256 hash
+        timeout_height: u32,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Redeem a hash time-locked output with the preimage of its hash lock.
+    /// The fee is paid from the value of the output.
+    #[method(name = "redeem_htlc")]
+    async fn redeem_htlc(
+        &self,
+        outpoint: OutPoint,
+        preimage: String, // hex encoded
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Refund a timed out hash time-locked output to its creator.
+    /// The fee is paid from the value of the output.
+    #[method(name = "refund_htlc")]
+    async fn refund_htlc(
+        &self,
+        outpoint: OutPoint,
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Relay parent chain headers to the sidechain
+    #[method(name = "relay_parent_chain_headers")]
+    async fn relay_parent_chain_headers(
+        &self,
+        parent_chain: ParentChainType,
+        start_height: u32,
+        headers: Vec<String>, // hex encoded block headers
+        fee_sats: u64,
+    ) -> RpcResult<Txid>;
+
+    /// Get the tip of the relayed chain with the most work, including its
+    /// chain work
+    #[method(name = "get_relay_tip")]
+    async fn get_relay_tip(
+        &self,
+        parent_chain: ParentChainType,
+    ) -> RpcResult<Option<RelayTip>>;
+
+    /// Get a relayed header, including its height and chain work
+    #[method(name = "get_relay_header")]
+    async fn get_relay_header(
+        &self,
+        parent_chain: ParentChainType,
+        block_hash: bitcoin::BlockHash,
+    ) -> RpcResult<Option<RelayHeader>>;
 }